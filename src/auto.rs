@@ -0,0 +1,264 @@
+use std::time::{Duration, Instant};
+
+use image::RgbaImage;
+use pelican_ui::hardware::{Camera, ImageSettings};
+use serde::{Serialize, Deserialize};
+
+use crate::settings::CameraSetting;
+use crate::storage;
+
+// Preview frames already have the current gains applied, so each update corrects
+// the previous one. Updating a few times a second gives the camera time to catch up.
+const UPDATE: Duration = Duration::from_millis(150);
+// Fraction of the measured correction applied per update.
+const SMOOTHING: f32 = 0.25;
+// The camera only uses the white balance gains at this temperature.
+const NEUTRAL_TEMPERATURE: f32 = 6500.0;
+// Every SAMPLE_STEP-th pixel in each direction is measured.
+const SAMPLE_STEP: usize = 4;
+// Pixels this close to black or white say little about the light's color.
+const DARK: u8 = 16;
+const CLIPPED: u8 = 250;
+// White patch averages this share of the brightest unclipped pixels.
+const WHITE_PATCH: f32 = 0.02;
+// Mean luma of a well exposed frame, about middle gray once gamma encoded.
+const TARGET_LUMA: f32 = 118.0;
+// Exposure isn't touched for errors smaller than this, in EV, so it doesn't hunt.
+const DEAD_BAND: f32 = 0.05;
+// More clipped highlights than this pulls exposure down even if the mean is low.
+const MAX_CLIPPED: f32 = 0.02;
+const HIGHLIGHT_PULL: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AwbMethod {
+    /// Assumes the scene averages to gray.
+    GrayWorld,
+    /// Assumes the brightest part of the scene is white.
+    WhitePatch,
+}
+
+impl AwbMethod {
+    pub const ALL: [AwbMethod; 2] = [AwbMethod::GrayWorld, AwbMethod::WhitePatch];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AwbMethod::GrayWorld => "Gray World",
+            AwbMethod::WhitePatch => "White Patch",
+        }
+    }
+
+    /// Per-channel factors that would make the frame neutral, relative to green.
+    pub fn correction(&self, frame: &RgbaImage) -> Option<[f32; 3]> {
+        match self {
+            AwbMethod::GrayWorld => gray_world(frame),
+            AwbMethod::WhitePatch => white_patch(frame),
+        }
+    }
+}
+
+/// Which settings are driven from the preview. Moving one of their sliders turns its auto mode off.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoAdjust {
+    pub white_balance: Option<AwbMethod>,
+    pub exposure: bool,
+}
+
+impl AutoAdjust {
+    const FILE: &'static str = "auto_adjust.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn drives(&self, setting: CameraSetting) -> bool {
+        match setting {
+            CameraSetting::Temperature | CameraSetting::WhiteBalanceR | CameraSetting::WhiteBalanceG | CameraSetting::WhiteBalanceB => self.white_balance.is_some(),
+            CameraSetting::Exposure => self.exposure,
+            _ => false,
+        }
+    }
+
+    /// Hands `setting` back to the user.
+    pub fn release(&mut self, setting: CameraSetting) {
+        match setting {
+            CameraSetting::Temperature | CameraSetting::WhiteBalanceR | CameraSetting::WhiteBalanceG | CameraSetting::WhiteBalanceB => self.white_balance = None,
+            CameraSetting::Exposure => self.exposure = false,
+            _ => {}
+        }
+    }
+}
+
+fn samples(frame: &RgbaImage) -> impl Iterator<Item = [u8; 3]> + '_ {
+    let width = frame.width() as usize;
+    frame.pixels().enumerate()
+        .filter(move |(i, _)| (i % width).is_multiple_of(SAMPLE_STEP) && (i / width).is_multiple_of(SAMPLE_STEP))
+        .map(|(_, p)| [p.0[0], p.0[1], p.0[2]])
+}
+
+fn luma([r, g, b]: [u8; 3]) -> f32 {
+    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+}
+
+fn neutralize(sum: [f32; 3]) -> Option<[f32; 3]> {
+    sum.iter().all(|c| *c > 0.0).then(|| [sum[1] / sum[0], 1.0, sum[1] / sum[2]])
+}
+
+/// Gains that bring the average of the well exposed pixels to gray.
+pub fn gray_world(frame: &RgbaImage) -> Option<[f32; 3]> {
+    let mut sum = [0.0; 3];
+    samples(frame)
+        .filter(|p| p.iter().all(|c| *c < CLIPPED) && p.iter().any(|c| *c > DARK))
+        .for_each(|p| (0..3).for_each(|c| sum[c] += p[c] as f32));
+    neutralize(sum)
+}
+
+/// Gains that bring the brightest unclipped pixels to white.
+pub fn white_patch(frame: &RgbaImage) -> Option<[f32; 3]> {
+    let mut pixels = samples(frame).filter(|p| p.iter().all(|c| *c < CLIPPED)).collect::<Vec<_>>();
+    if pixels.is_empty() { return None; }
+    let count = ((pixels.len() as f32 * WHITE_PATCH).ceil() as usize).max(1);
+    pixels.select_nth_unstable_by(count - 1, |a, b| luma(*b).total_cmp(&luma(*a)));
+    let mut sum = [0.0; 3];
+    pixels[..count].iter().for_each(|p| (0..3).for_each(|c| sum[c] += p[c] as f32));
+    neutralize(sum)
+}
+
+/// Luma histogram of the sampled pixels.
+pub fn histogram(frame: &RgbaImage) -> [u32; 256] {
+    let mut bins = [0; 256];
+    samples(frame).for_each(|p| bins[luma(p).round() as usize] += 1);
+    bins
+}
+
+/// How many EV the exposure is off from the target, positive when the frame is too dark.
+pub fn exposure_error(histogram: &[u32; 256]) -> Option<f32> {
+    let total = histogram.iter().sum::<u32>() as f32;
+    if total == 0.0 { return None; }
+    let mean = histogram.iter().enumerate().map(|(v, n)| v as f32 * *n as f32).sum::<f32>() / total;
+    let error = (TARGET_LUMA / mean.max(1.0)).log2();
+    let clipped = histogram[CLIPPED as usize..].iter().sum::<u32>() as f32 / total;
+    Some(match clipped > MAX_CLIPPED {
+        true => error.min(-HIGHLIGHT_PULL),
+        false => error
+    })
+}
+
+/// The white balance changes for one update. The camera ignores the gains away from the neutral
+/// temperature, so the first update hands the temperature's tint over to the gains instead.
+pub fn white_balance_step(settings: &ImageSettings, correction: [f32; 3]) -> Vec<(CameraSetting, f32)> {
+    let channels = [CameraSetting::WhiteBalanceR, CameraSetting::WhiteBalanceG, CameraSetting::WhiteBalanceB];
+    if settings.temperature != NEUTRAL_TEMPERATURE {
+        let tint = settings.temperature_to_rgb_multipliers();
+        let mut changes = vec![(CameraSetting::Temperature, NEUTRAL_TEMPERATURE)];
+        changes.extend(channels.into_iter().enumerate().map(|(c, setting)| (setting, tint[c] / tint[1])));
+        return changes;
+    }
+    let gains = [settings.white_balance_r, settings.white_balance_g, settings.white_balance_b];
+    let target = std::array::from_fn::<f32, 3, _>(|c| gains[c] * correction[c]);
+    // Keep green at 1 so the overall brightness is left to exposure.
+    let target = target.map(|gain| gain / target[1]);
+    channels.into_iter().enumerate().map(|(c, setting)| (setting, gains[c] + (target[c] - gains[c]) * SMOOTHING)).collect()
+}
+
+/// Steps the camera's white balance and exposure toward the preview's measurements.
+#[derive(Debug, Default)]
+pub struct AutoController {
+    last: Option<Instant>,
+    /// Set while the settings are being held somewhere else, such as during a before/after compare.
+    pub paused: bool,
+}
+
+impl AutoController {
+    pub fn update(&mut self, camera: &mut Camera, frame: &RgbaImage, auto: AutoAdjust) {
+        if self.paused || auto == AutoAdjust::default() || self.last.is_some_and(|last| last.elapsed() < UPDATE) { return; }
+        self.last = Some(Instant::now());
+        let Ok(settings) = camera.get_settings() else { return };
+        let mut changes = Vec::new();
+
+        if let Some(correction) = auto.white_balance.and_then(|method| method.correction(frame)) {
+            changes.extend(white_balance_step(&settings, correction));
+        }
+
+        if auto.exposure {
+            if let Some(error) = exposure_error(&histogram(frame)).filter(|e| e.abs() > DEAD_BAND) {
+                changes.push((CameraSetting::Exposure, settings.exposure + error * SMOOTHING));
+            }
+        }
+
+        CameraSetting::apply_all(camera, changes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn assert_gains(gains: Option<[f32; 3]>, expected: [f32; 3]) {
+        let gains = gains.expect("no gains");
+        assert!(gains.iter().zip(expected).all(|(g, e)| (g - e).abs() < 0.02), "{:?} is not {:?}", gains, expected);
+    }
+
+    #[test]
+    fn gray_world_removes_a_cast() {
+        // A gray ramp seen through a warm cast, red up a fifth and blue down a fifth.
+        let frame = RgbaImage::from_fn(64, 64, |x, y| {
+            let v = 40.0 + (x + y) as f32;
+            Rgba([(v * 1.2) as u8, v as u8, (v * 0.8) as u8, 255])
+        });
+        assert_gains(gray_world(&frame), [1.0 / 1.2, 1.0, 1.0 / 0.8]);
+    }
+
+    #[test]
+    fn white_patch_uses_the_brightest_pixels() {
+        // A dim green scene with a bluish white card in one corner.
+        let frame = RgbaImage::from_fn(64, 64, |x, y| match x < 16 && y < 16 {
+            true => Rgba([180, 200, 240, 255]),
+            false => Rgba([20, 60 + (x % 20) as u8, 30, 255])
+        });
+        assert_gains(white_patch(&frame), [200.0 / 180.0, 1.0, 200.0 / 240.0]);
+        assert_eq!(white_patch(&RgbaImage::from_pixel(8, 8, Rgba([255, 255, 255, 255]))), None);
+    }
+
+    #[test]
+    fn white_balance_takes_over_from_temperature() {
+        let mut settings = ImageSettings::default();
+        settings.temperature = 3200.0;
+        let changes = white_balance_step(&settings, [2.0, 1.0, 0.5]);
+        assert_eq!(changes[0], (CameraSetting::Temperature, NEUTRAL_TEMPERATURE));
+        let tint = settings.temperature_to_rgb_multipliers();
+        assert_gains(Some([changes[1].1, changes[2].1, changes[3].1]), [tint[0] / tint[1], 1.0, tint[2] / tint[1]]);
+
+        // Once the temperature is neutral the gains follow the correction.
+        settings.temperature = NEUTRAL_TEMPERATURE;
+        (settings.white_balance_r, settings.white_balance_g, settings.white_balance_b) = (1.0, 1.0, 1.0);
+        let changes = white_balance_step(&settings, [2.0, 1.0, 0.5]);
+        assert!(changes.iter().all(|(setting, _)| *setting != CameraSetting::Temperature));
+        assert_gains(Some([changes[0].1, changes[1].1, changes[2].1]), [1.25, 1.0, 0.875]);
+    }
+
+    #[test]
+    fn exposure_error_is_in_stops() {
+        let gray = |v: u8| histogram(&RgbaImage::from_pixel(64, 64, Rgba([v, v, v, 255])));
+        let under = exposure_error(&gray(59)).unwrap();
+        let over = exposure_error(&gray(236)).unwrap();
+        assert!((under - 1.0).abs() < 0.01, "{under}");
+        assert!((over + 1.0).abs() < 0.01, "{over}");
+        assert_eq!(exposure_error(&[0; 256]), None);
+    }
+
+    #[test]
+    fn clipped_highlights_pull_exposure_down() {
+        // Well exposed on average, but a tenth of the frame is blown out.
+        let frame = RgbaImage::from_fn(64, 64, |x, _| match x < 6 {
+            true => Rgba([255, 255, 255, 255]),
+            false => Rgba([105, 105, 105, 255])
+        });
+        let error = exposure_error(&histogram(&frame)).unwrap();
+        assert!(error <= -HIGHLIGHT_PULL, "{error}");
+    }
+}
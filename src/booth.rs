@@ -0,0 +1,208 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use image::{Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
+use serde::{Serialize, Deserialize};
+
+use crate::draw;
+use crate::storage;
+
+pub const SHOT_COUNTS: [usize; 3] = [3, 4, 6];
+pub const COUNTDOWNS: [u64; 3] = [3, 5, 10];
+// Each shot is scaled to this width in the composite.
+const CELL_WIDTH: u32 = 600;
+const BORDER: u32 = 24;
+const CAPTION_SCALE: f32 = 6.0;
+const PAPER: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const INK: Rgba<u8> = Rgba([20, 20, 20, 255]);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoothLayout {
+    /// One shot above the other, like a classic booth print.
+    #[default]
+    Strip,
+    /// Two columns.
+    Grid,
+}
+
+impl BoothLayout {
+    pub const ALL: [BoothLayout; 2] = [BoothLayout::Strip, BoothLayout::Grid];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BoothLayout::Strip => "Strip",
+            BoothLayout::Grid => "Grid",
+        }
+    }
+
+    fn columns(&self) -> u32 {
+        match self {
+            BoothLayout::Strip => 1,
+            BoothLayout::Grid => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PhotoBooth {
+    pub shots: usize,
+    /// Seconds counted down before each shot.
+    pub countdown: u64,
+    pub layout: BoothLayout,
+    /// Printed under the shots. Empty for none.
+    pub caption: String,
+    /// Adds the date to the caption line.
+    pub date: bool,
+}
+
+impl Default for PhotoBooth {
+    fn default() -> Self {
+        PhotoBooth { shots: 4, countdown: 3, layout: BoothLayout::Strip, caption: String::new(), date: true }
+    }
+}
+
+impl PhotoBooth {
+    const FILE: &'static str = "photo_booth.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.countdown)
+    }
+
+    /// The line printed under the shots, if there is anything to print.
+    pub fn caption_line(&self, date: DateTime<Local>) -> Option<String> {
+        let date = self.date.then(|| date.format("%b %-d, %Y").to_string());
+        let line = [Some(self.caption.trim().to_string()).filter(|c| !c.is_empty()), date]
+            .into_iter().flatten().collect::<Vec<_>>().join(" - ");
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+/// A countdown sequence in progress.
+#[derive(Debug)]
+pub struct BoothSession {
+    pub shots: Vec<RgbaImage>,
+    /// When the next shot is taken.
+    pub next: Instant,
+    /// When the last shot was taken, for the flash.
+    pub flash: Option<Instant>,
+}
+
+impl BoothSession {
+    pub fn new(settings: &PhotoBooth) -> Self {
+        BoothSession { shots: Vec::new(), next: Instant::now() + settings.interval(), flash: None }
+    }
+
+    /// Whole seconds left before the next shot, counting down to 1.
+    pub fn remaining(&self) -> u64 {
+        self.next.saturating_duration_since(Instant::now()).as_secs_f32().ceil().max(1.0) as u64
+    }
+}
+
+/// Lays the shots out on a white card with borders and the caption line at the bottom.
+pub fn compose(shots: &[RgbaImage], layout: BoothLayout, caption: Option<&str>) -> Option<RgbaImage> {
+    let first = shots.first()?;
+    let cell = (CELL_WIDTH, (first.height() as f32 / first.width() as f32 * CELL_WIDTH as f32).round() as u32);
+    let columns = layout.columns().min(shots.len() as u32);
+    let rows = (shots.len() as u32).div_ceil(columns);
+    let caption_height = caption.map(|_| draw::text_height(CAPTION_SCALE) as u32 + BORDER).unwrap_or(0);
+    let width = columns * cell.0 + (columns + 1) * BORDER;
+    let height = rows * cell.1 + (rows + 1) * BORDER + caption_height;
+
+    let mut card = RgbaImage::from_pixel(width, height, PAPER);
+    for (i, shot) in shots.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let shot = imageops::resize(shot, cell.0, cell.1, FilterType::Triangle);
+        let (x, y) = (BORDER + column * (cell.0 + BORDER), BORDER + row * (cell.1 + BORDER));
+        imageops::replace(&mut card, &shot, x as i64, y as i64);
+    }
+    if let Some(caption) = caption {
+        // Shrink long captions to fit rather than clipping them.
+        let room = (width - 2 * BORDER) as f32;
+        let scale = (room / draw::text_width(caption, 1.0)).min(CAPTION_SCALE);
+        let x = (width as f32 - draw::text_width(caption, scale)) / 2.0;
+        let y = (height - caption_height) as f32 + (draw::text_height(CAPTION_SCALE) - draw::text_height(scale)) / 2.0;
+        draw::text(&mut card, caption, (x, y), scale, INK);
+    }
+    Some(card)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn shots(count: usize) -> Vec<RgbaImage> {
+        (0..count).map(|i| RgbaImage::from_pixel(400, 300, Rgba([40 * i as u8, 100, 200, 255]))).collect()
+    }
+
+    // Columns of the card with any ink in them, between rows `top` and `bottom`.
+    fn inked(card: &RgbaImage, top: u32, bottom: u32) -> Vec<u32> {
+        (0..card.width()).filter(|x| (top..bottom).any(|y| *card.get_pixel(*x, y) == INK)).collect()
+    }
+
+    #[test]
+    fn strip_stacks_the_shots() {
+        let card = compose(&shots(4), BoothLayout::Strip, None).unwrap();
+        assert_eq!(card.dimensions(), (CELL_WIDTH + 2 * BORDER, 4 * 450 + 5 * BORDER));
+        for i in 0..4 {
+            let y = BORDER + i * (450 + BORDER) + 10;
+            assert_eq!(card.get_pixel(BORDER + 10, y).0[0], 40 * i as u8);
+        }
+        assert_eq!(*card.get_pixel(BORDER / 2, BORDER / 2), PAPER);
+    }
+
+    #[test]
+    fn grid_fills_rows_of_two() {
+        let card = compose(&shots(3), BoothLayout::Grid, None).unwrap();
+        assert_eq!(card.dimensions(), (2 * CELL_WIDTH + 3 * BORDER, 2 * 450 + 3 * BORDER));
+        assert_eq!(card.get_pixel(2 * BORDER + CELL_WIDTH + 10, BORDER + 10).0[0], 40);
+        assert_eq!(card.get_pixel(BORDER + 10, 2 * BORDER + 450 + 10).0[0], 80);
+        // The empty last cell is left as paper.
+        assert_eq!(*card.get_pixel(2 * BORDER + CELL_WIDTH + 10, 2 * BORDER + 450 + 10), PAPER);
+        // A single shot doesn't leave an empty column.
+        assert_eq!(compose(&shots(1), BoothLayout::Grid, None).unwrap().width(), CELL_WIDTH + 2 * BORDER);
+        assert_eq!(compose(&[], BoothLayout::Grid, None), None);
+    }
+
+    #[test]
+    fn caption_goes_under_the_shots() {
+        let plain = compose(&shots(3), BoothLayout::Strip, None).unwrap();
+        let card = compose(&shots(3), BoothLayout::Strip, Some("Party")).unwrap();
+        let band = draw::text_height(CAPTION_SCALE) as u32 + BORDER;
+        assert_eq!(card.height(), plain.height() + band);
+        assert!(inked(&card, 0, plain.height()).is_empty());
+        let columns = inked(&card, plain.height(), card.height());
+        assert!(!columns.is_empty());
+        // Centered, give or take a pixel of rounding.
+        let (left, right) = (columns[0], card.width() - 1 - columns[columns.len() - 1]);
+        assert!(left.abs_diff(right) <= 2, "{left} and {right}");
+    }
+
+    #[test]
+    fn long_captions_shrink_to_fit() {
+        let caption = "A caption far too long to print at full size under a single strip";
+        let card = compose(&shots(3), BoothLayout::Strip, Some(caption)).unwrap();
+        let columns = inked(&card, card.height() - draw::text_height(CAPTION_SCALE) as u32 - BORDER, card.height());
+        assert!(columns[0] >= BORDER && columns[columns.len() - 1] < card.width() - BORDER, "{columns:?}");
+    }
+
+    #[test]
+    fn caption_line_joins_text_and_date() {
+        let date = Local.with_ymd_and_hms(2025, 7, 4, 12, 0, 0).unwrap();
+        let mut settings = PhotoBooth { caption: "  Party ".to_string(), ..PhotoBooth::default() };
+        assert_eq!(settings.caption_line(date).as_deref(), Some("Party - Jul 4, 2025"));
+        settings.date = false;
+        assert_eq!(settings.caption_line(date).as_deref(), Some("Party"));
+        settings.caption.clear();
+        assert_eq!(settings.caption_line(date), None);
+    }
+}
@@ -0,0 +1,126 @@
+use image::{Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+use crate::storage;
+
+// Live previews are keyed at this width; captures are keyed at full resolution.
+pub const PREVIEW_WIDTH: u32 = 640;
+// Chroma distance covered by the full tolerance slider, and the soft edge beyond it.
+const MAX_TOLERANCE: f32 = 120.0;
+const FEATHER: f32 = 20.0;
+const SAMPLE_RADIUS: i64 = 2;
+const CHECKER: u32 = 16;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ChromaKey {
+    /// The color replaced by the background, picked by tapping the viewfinder.
+    pub key: [u8; 3],
+    /// 0 to 100. How far from the key color a pixel can be and still be replaced.
+    pub tolerance: f32,
+    /// 0 to 100. How much of the key color's cast is removed from the subject's edges.
+    pub spill: f32,
+    /// A photo from the camera roll.
+    pub background: Option<Uuid>,
+}
+
+impl Default for ChromaKey {
+    fn default() -> Self {
+        ChromaKey { key: [0, 177, 64], tolerance: 40.0, spill: 50.0, background: None }
+    }
+}
+
+impl ChromaKey {
+    const FILE: &'static str = "chroma_key.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+}
+
+fn chroma([r, g, b]: [f32; 3]) -> (f32, f32) {
+    (-0.1687 * r - 0.3313 * g + 0.5 * b, 0.5 * r - 0.4187 * g - 0.0813 * b)
+}
+
+/// Averages a small patch around a point given in fractions of the frame.
+pub fn sample(frame: &RgbaImage, (fx, fy): (f32, f32)) -> [u8; 3] {
+    let (w, h) = frame.dimensions();
+    let (cx, cy) = ((fx * w as f32) as i64, (fy * h as f32) as i64);
+    let mut sum = [0u32; 3];
+    let mut count = 0;
+    for y in (cy - SAMPLE_RADIUS)..=(cy + SAMPLE_RADIUS) {
+        for x in (cx - SAMPLE_RADIUS)..=(cx + SAMPLE_RADIUS) {
+            if x < 0 || y < 0 || x >= w as i64 || y >= h as i64 { continue; }
+            let p = frame.get_pixel(x as u32, y as u32);
+            (0..3).for_each(|c| sum[c] += p.0[c] as u32);
+            count += 1;
+        }
+    }
+    std::array::from_fn(|c| (sum[c] / count.max(1)) as u8)
+}
+
+/// Scales and center-crops `background` to cover `size`.
+pub fn cover(background: &RgbaImage, (width, height): (u32, u32)) -> RgbaImage {
+    let (w, h) = background.dimensions();
+    let scale = (width as f32 / w as f32).max(height as f32 / h as f32);
+    let (sw, sh) = (((w as f32 * scale).ceil() as u32).max(width), ((h as f32 * scale).ceil() as u32).max(height));
+    let scaled = imageops::resize(background, sw, sh, FilterType::Triangle);
+    imageops::crop_imm(&scaled, (sw - width) / 2, (sh - height) / 2, width, height).to_image()
+}
+
+/// What shows where the key color was.
+#[derive(Clone, Copy, Debug)]
+pub enum Backdrop<'a> {
+    /// Must already be the frame's size.
+    Image(&'a RgbaImage),
+    /// Stands in for a background that hasn't been chosen yet in the preview.
+    Checkerboard,
+    /// Keeps the cut-out, for saving with no background.
+    Transparent,
+}
+
+/// Replaces the key color in `frame` with `backdrop`.
+pub fn composite(frame: &RgbaImage, backdrop: Backdrop, settings: &ChromaKey) -> RgbaImage {
+    let key = chroma(settings.key.map(|c| c as f32));
+    let key_length = (key.0 * key.0 + key.1 * key.1).sqrt().max(1.0);
+    let unit = (key.0 / key_length, key.1 / key_length);
+    let inner = settings.tolerance.clamp(0.0, 100.0) / 100.0 * MAX_TOLERANCE;
+    let spill = settings.spill.clamp(0.0, 100.0) / 100.0;
+
+    RgbaImage::from_fn(frame.width(), frame.height(), |x, y| {
+        let p = frame.get_pixel(x, y).0;
+        let rgb = [p[0] as f32, p[1] as f32, p[2] as f32];
+        let (cb, cr) = chroma(rgb);
+        let distance = ((cb - key.0).powi(2) + (cr - key.1).powi(2)).sqrt();
+        let alpha = ((distance - inner) / FEATHER).clamp(0.0, 1.0);
+
+        // Pull the subject's chroma away from the key so edges don't keep a green fringe.
+        let toward = (cb * unit.0 + cr * unit.1).max(0.0) * spill;
+        let (cb, cr) = (cb - toward * unit.0, cr - toward * unit.1);
+        let luma = 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+        let subject = [luma + 1.402 * cr, luma - 0.344136 * cb - 0.714136 * cr, luma + 1.772 * cb];
+
+        let behind = match backdrop {
+            Backdrop::Image(background) => background.get_pixel(x, y).0.map(|c| c as f32),
+            Backdrop::Checkerboard => {
+                let shade = if (x / CHECKER + y / CHECKER).is_multiple_of(2) {200.0} else {120.0};
+                [shade, shade, shade, 255.0]
+            },
+            Backdrop::Transparent => {
+                return Rgba(std::array::from_fn(|c| match c {
+                    3 => (alpha * 255.0).round() as u8,
+                    _ => subject[c].round().clamp(0.0, 255.0) as u8
+                }));
+            }
+        };
+        Rgba(std::array::from_fn(|c| match c {
+            3 => 255,
+            _ => (behind[c] * (1.0 - alpha) + subject[c] * alpha).round().clamp(0.0, 255.0) as u8
+        }))
+    })
+}
@@ -0,0 +1,139 @@
+use image::{Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
+use pelican_ui::hardware::ImageSettings;
+use serde::{Serialize, Deserialize};
+
+use crate::draw;
+
+const DIVIDER: Rgba<u8> = Rgba([255, 255, 255, 230]);
+const LABEL: Rgba<u8> = Rgba([255, 255, 255, 230]);
+const LABEL_SHADOW: Rgba<u8> = Rgba([0, 0, 0, 140]);
+const GAP: Rgba<u8> = Rgba([0, 0, 0, 255]);
+// Sizes are per thousand pixels of the long edge, like markup's stroke widths.
+const PER_MILLE: f32 = 1000.0;
+const DIVIDER_WIDTH: f32 = 3.0;
+const HANDLE_RADIUS: f32 = 14.0;
+const GAP_WIDTH: f32 = 8.0;
+const LABEL_DOTS: f32 = 3.0;
+const MARGIN: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareMode {
+    /// The original left of a line that can be dragged across, the adjusted version right of it.
+    #[default]
+    Split,
+    /// Both in full, next to each other. Wide images are stacked instead so neither gets too small.
+    SideBySide,
+}
+
+impl CompareMode {
+    pub const ALL: [CompareMode; 2] = [CompareMode::Split, CompareMode::SideBySide];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CompareMode::Split => "Split",
+            CompareMode::SideBySide => "Side by Side",
+        }
+    }
+}
+
+/// How an original and its adjusted version are being shown against each other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Comparison {
+    pub mode: CompareMode,
+    /// Where the split sits, as a fraction of the width.
+    pub split: f32,
+    /// Shows only the original while set.
+    pub holding: bool,
+}
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Comparison { mode: CompareMode::default(), split: 0.5, holding: false }
+    }
+}
+
+impl Comparison {
+    pub fn new(mode: CompareMode) -> Self {
+        Comparison { mode, ..Default::default() }
+    }
+
+    /// The two images in one, labelled. `original` is scaled to match if the sizes differ.
+    pub fn compose(&self, original: &RgbaImage, adjusted: &RgbaImage) -> RgbaImage {
+        let (w, h) = adjusted.dimensions();
+        let original = match original.dimensions() == (w, h) {
+            true => original.clone(),
+            false => imageops::resize(original, w, h, FilterType::Triangle)
+        };
+        if self.holding { return original; }
+        let unit = w.max(h) as f32 / PER_MILLE;
+        match self.mode {
+            CompareMode::Split => {
+                let x = (self.split.clamp(0.0, 1.0) * w as f32).round() as u32;
+                let mut image = adjusted.clone();
+                imageops::replace(&mut image, &imageops::crop_imm(&original, 0, 0, x, h).to_image(), 0, 0);
+                let (x, h) = (x as f32, h as f32);
+                draw::line(&mut image, (x, 0.0), (x, h), (DIVIDER_WIDTH * unit).max(1.0), DIVIDER);
+                draw::dot(&mut image, x, h / 2.0, HANDLE_RADIUS * unit, DIVIDER);
+                label(&mut image, "Before", (0.0, 0.0), unit, false);
+                label(&mut image, "After", (w as f32, 0.0), unit, true);
+                image
+            },
+            CompareMode::SideBySide => {
+                let gap = (GAP_WIDTH * unit).round() as u32;
+                let stacked = w > h;
+                let (offset, size) = match stacked {
+                    true => ((0, h + gap), (w, h * 2 + gap)),
+                    false => ((w + gap, 0), (w * 2 + gap, h))
+                };
+                let mut image = RgbaImage::from_pixel(size.0, size.1, GAP);
+                imageops::replace(&mut image, &original, 0, 0);
+                imageops::replace(&mut image, adjusted, offset.0 as i64, offset.1 as i64);
+                label(&mut image, "Before", (0.0, 0.0), unit, false);
+                label(&mut image, "After", (offset.0 as f32, offset.1 as f32), unit, false);
+                image
+            }
+        }
+    }
+}
+
+// Writes `text` inset from `corner`, measuring back from it when `right` is set.
+fn label(image: &mut RgbaImage, text: &str, corner: (f32, f32), unit: f32, right: bool) {
+    let scale = (LABEL_DOTS * unit).max(1.0);
+    let margin = MARGIN * unit;
+    let x = match right {
+        true => corner.0 - margin - draw::text_width(text, scale),
+        false => corner.0 + margin
+    };
+    let y = corner.1 + margin;
+    draw::text(image, text, (x + scale / 2.0, y + scale / 2.0), scale, LABEL_SHADOW);
+    draw::text(image, text, (x, y), scale, LABEL);
+}
+
+/// A photo before and after its edits, rendered once when the comparison opens.
+#[derive(Debug)]
+pub struct BeforeAfter {
+    pub comparison: Comparison,
+    original: RgbaImage,
+    edited: RgbaImage,
+}
+
+impl BeforeAfter {
+    pub fn new(original: RgbaImage, edited: RgbaImage, mode: CompareMode) -> Self {
+        BeforeAfter { comparison: Comparison::new(mode), original, edited }
+    }
+
+    pub fn render(&self) -> RgbaImage {
+        self.comparison.compose(&self.original, &self.edited)
+    }
+}
+
+/// The comparison open on the viewfinder, if any. The camera runs at its defaults while
+/// it's open, and `settings` are applied to the preview in their place.
+#[derive(Clone, Debug, Default)]
+pub struct LiveComparison {
+    pub comparison: Option<Comparison>,
+    pub settings: ImageSettings,
+    /// Whether a finger is on the viewfinder, moving the split.
+    pub dragging: bool,
+}
@@ -1,45 +1,183 @@
 use pelican_ui::{resources, Component, Context};
 use pelican_ui::drawable::{Align, ShapeType, Drawable, Component, Image};
 use pelican_ui::layout::{Area, SizeRequest, Layout};
-use pelican_ui::events::{Event, OnEvent, TickEvent, MouseEvent, MouseState};
+use pelican_ui::events::{Event, OnEvent, TickEvent, MouseEvent, MouseState, KeyboardEvent, KeyboardState, Key, NamedKey};
 use pelican_ui::hardware::Camera;
 use pelican_ui::hardware::ImageSettings;
 
-use image::RgbaImage;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use image::{Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
 
 // use crate::pages::CameraRoll;
 use crate::service::LensRequest;
-use crate::events::{TakePhotoEvent, SetCameraSetting, OpenSettingsEvent, NewSettingSelectedEvent, SelectImageEvent, SettingsSelect};
+use crate::events::{TakePhotoEvent, SetCameraSetting, OpenSettingsEvent, SelectImageEvent, SettingsSelect, DeletePhotosEvent};
+use crate::events::{SetCaptureModeEvent, CodesScannedEvent, CloseSheetEvent};
+use crate::events::{DocumentPageAddedEvent, SetScanFilterEvent, SaveDocumentEvent, DiscardDocumentEvent};
+use crate::events::{OpenMotionSettingsEvent, MotionSettingsChangedEvent, StopMotionEditEvent, OpenLongExposureEvent, OpenPhotoBoothEvent, PresetEvent, ResetSettingsEvent, OpenFiltersEvent, HistoryEvent, EditPhotoEvent};
+use crate::events::{OpenToneEvent, ToneEvent, EffectEvent, RollSelectionEvent, CompareEvent, ShowNeighbourEvent, MoreBackgroundsEvent};
 use crate::LensPlugin;
 use crate::MyCameraRoll;
-use crate::pages::SettingsValue;
+use crate::service::Photo;
+use crate::quality::QualityIssue;
+use crate::frames::{self, Finish, FrameRing, FrameSource, LivePhotos, RING_CAPACITY};
+use crate::worker::Worker;
+use crate::modes::{CaptureMode, ModeState};
+use crate::scanner::{ScannedCode, open_link};
+use crate::document::{self, DocumentDraft, Quad, ScanFilter};
+use crate::motion::{MotionAlbum, MotionLog, MotionSettings, Zone};
+use crate::stopmotion::{self, StopMotion, Project, MIN_FPS, MAX_FPS, MAX_ONION};
+use crate::exposure::{LongExposure, Stacker, ExposureRecord, BlendMode, DURATIONS};
+use crate::chroma::{self, Backdrop, ChromaKey};
+use crate::booth::{self, PhotoBooth, BoothSession, BoothLayout, SHOT_COUNTS, COUNTDOWNS};
+use crate::draw;
+use crate::settings::CameraSetting;
+use crate::presets::{Preset, Presets};
+use crate::auto::{AutoAdjust, AutoController, AwbMethod};
+use crate::history::SettingsHistory;
+use crate::session::CameraSession;
+use crate::lut::{self, Lut, Luts, Filters, LiveThumbnail, Interpolation};
+use crate::edit::{self, EditOp, Edits};
+use crate::curves::{self, Channel, Histogram, LevelPoint, Levels, ToneCurves};
+use crate::effects::{Effect, Param};
+use crate::compare::{CompareMode, LiveComparison};
+use crate::viewer::{self, DecodedPhotos, Preloader, Viewer};
+use crate::timeline::{self, RollView, TimelineLayout};
+use std::sync::Arc;
 
 use pelican_ui_std::{
     Row, IconButton, Text,
     Stack, ExpandableImage, 
     Size, Offset, Padding, 
-    Wrap, TextStyle, Header,
-    NavigateEvent, ExpandableText, 
-    EncodedImage, AppPage, Column,
-    Bumper, Icon, Bin, ButtonState,
-    RoundedRectangle, ButtonStyle,
-    ButtonWidth, ButtonSize, Button,
-    Slider, Rectangle, Scroll,
-    AdjustScrollEvent, ScrollAnchor,
-    ElementID, NavigationButton,
-    NavigatorSelect, Brand
+    TextStyle, NavigateEvent,
+    ExpandableText, EncodedImage,
+    Column, Bumper, Icon, Bin,
+    ButtonState, RoundedRectangle,
+    ButtonStyle, ButtonWidth,
+    ButtonSize, Button, Slider,
+    Scroll, AdjustScrollEvent,
+    ScrollAnchor, Alert,
+    QuickActions, TextInput, InputState,
 };
 
 pub struct CameraBumper;
 impl CameraBumper {
     pub fn new(ctx: &mut Context, library_location: usize) -> Bumper {
-        let settings = IconButton::ghost(ctx, "sliders", Box::new(|ctx: &mut Context| ctx.trigger_event(OpenSettingsEvent::Open)));
+        let settings = IconButton::ghost(ctx, "sliders", Box::new(|ctx: &mut Context| {
+            match ctx.state().get_or_default::<CaptureMode>() {
+                CaptureMode::Motion => ctx.trigger_event(OpenMotionSettingsEvent),
+                CaptureMode::StopMotion => ctx.trigger_event(NavigateEvent(3)),
+                CaptureMode::LongExposure => ctx.trigger_event(OpenLongExposureEvent),
+                CaptureMode::PhotoBooth => ctx.trigger_event(OpenPhotoBoothEvent),
+                _ => ctx.trigger_event(OpenSettingsEvent::Open)
+            }
+        }));
+        let options = CameraOptions(Row::center(8.0), LiveButton::new(ctx), settings);
 
         let camera_roll = CameraRollButton::new(ctx, library_location);
         let shutter_button = ShutterButton::new(ctx);
+        let capture = CaptureRow(Row::new(16.0, Offset::Center, Size::Fit, Padding::default()), camera_roll, shutter_button, options);
+
+        let controls = CameraControls(Column::new(8.0, Offset::Center, Size::Fit, Padding::default()), ModeOptions::new(ctx), capture);
+        Bumper::new(ctx, vec![Box::new(controls)])
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct CameraControls(Column, ModeOptions, CaptureRow);
+impl OnEvent for CameraControls {}
+
+#[derive(Debug, Component)]
+pub struct CaptureRow(Row, CameraRollButton, ShutterButton, CameraOptions);
+impl OnEvent for CaptureRow {}
+
+#[derive(Debug, Component)]
+pub struct ModeOptions(Scroll, ModeOptionsContent);
+
+impl ModeOptions {
+    pub fn new(ctx: &mut Context) -> Self {
+        let width = Size::custom(move |_widths| (0.0, f32::MAX));
+        let height = Size::custom(move |heights: Vec<(f32, f32)>| (heights[0].0.min(40.0), 40.0));
+        let layout = Scroll::horizontal(Offset::Center, Offset::Start, width, height, Padding::default(), ScrollAnchor::Start);
+        ModeOptions(layout, ModeOptionsContent::new(ctx))
+    }
+}
+
+impl OnEvent for ModeOptions {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(AdjustScrollEvent::Horizontal(a)) = event.downcast_ref::<AdjustScrollEvent>() {
+            self.0.adjust_scroll(*a);
+        } else if let Some(MouseEvent { state: MouseState::Scroll(x, _), position: Some(_) }) = event.downcast_ref::<MouseEvent>() {
+            self.0.adjust_scroll(*x);
+        }
+        true
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct ModeOptionsContent(Row, Vec<Button>);
+
+impl ModeOptionsContent {
+    pub fn new(ctx: &mut Context) -> Self {
+        let current = *ctx.state().get_or_default::<CaptureMode>();
+        let buttons = CaptureMode::ALL.into_iter().map(|mode| {
+            let state = if mode == current {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(mode.label()), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| {
+                    ctx.state().set(mode);
+                    ctx.trigger_event(SetCaptureModeEvent(mode));
+                },
+                None,
+            )
+        }).collect();
+        ModeOptionsContent(Row::center(8.0), buttons)
+    }
+}
+
+impl OnEvent for ModeOptionsContent {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(SetCaptureModeEvent(mode)) = event.downcast_ref::<SetCaptureModeEvent>() {
+            self.1.iter_mut().zip(CaptureMode::ALL).for_each(|(button, m)| {
+                *button.status() = if m == *mode {ButtonState::Selected} else {ButtonState::Default};
+                button.color(ctx);
+            });
+        }
+        true
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct CameraOptions(Row, LiveButton, IconButton);
+impl OnEvent for CameraOptions {}
+
+#[derive(Debug, Component)]
+pub struct LiveButton(Stack, IconButton, #[skip] bool);
+
+impl LiveButton {
+    pub fn new(ctx: &mut Context) -> Self {
+        let live = ctx.state().get_or_default::<LivePhotos>().0;
+        let button = IconButtonPreset::new(ctx, "infinite", live, |ctx: &mut Context| {
+            let live = ctx.state().get_or_default::<LivePhotos>().0;
+            ctx.state().set(LivePhotos(!live));
+        });
+        LiveButton(Stack::default(), button, live)
+    }
+}
 
-        Bumper::new(ctx, vec![Box::new(camera_roll), Box::new(shutter_button), Box::new(settings)])
+impl OnEvent for LiveButton {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            let live = ctx.state().get_or_default::<LivePhotos>().0;
+            if live != self.2 {
+                self.2 = live;
+                let status = if live {ButtonState::Selected} else {ButtonState::Default};
+                *self.1.status() = status;
+                self.1.color(ctx, status);
+            }
+        }
+        true
     }
 }
 
@@ -74,7 +212,7 @@ impl CameraRollButton {
         let color = ctx.theme.colors.text.heading;
         let photos = ctx.state().get_or_default::<MyCameraRoll>().0.clone();
         let blank = ctx.theme.brand.illustrations.get("blank").unwrap();
-        let image = photos.last().map(|(p, _)| EncodedImage::decode(ctx, &p)).unwrap_or(blank);
+        let image = photos.last().map(|p| EncodedImage::decode(ctx, p.shown())).unwrap_or(blank);
         let image = Image{shape: ShapeType::RoundedRectangle(0.0, (48.0, 48.0), 8.0), image, color: None};
         let layout = Stack(Offset::Center, Offset::Center, Size::Static(48.0), Size::Static(48.0), Padding::default());
         let len = ctx.state().get_or_default::<MyCameraRoll>().0.len();
@@ -84,7 +222,7 @@ impl CameraRollButton {
     pub fn update(&mut self, ctx: &mut Context) {
         while self.5 > self.4 { 
             if let Some(p) = ctx.state().get_or_default::<MyCameraRoll>().0.clone().last() {
-                let image = p.0.clone();
+                let image = p.shown().clone();
                 self.1.image = EncodedImage::decode(ctx, &image);
             }
             self.4 += 1;
@@ -106,35 +244,221 @@ impl OnEvent for CameraRollButton {
 }
 
 #[derive(Debug, Component)]
-pub struct EditSettingsBumper(Column, Text, SettingsOptions, EditSlider);
+pub struct EditSettingsBumper(Column, Text, SettingsOptions, EditSlider, HistoryControls, CompareOptions, AutoOptions, SettingsActions, Option<ChromaKeyOptions>);
 impl OnEvent for EditSettingsBumper {}
 
 impl EditSettingsBumper {
     pub fn new(ctx: &mut Context, settings: ImageSettings) -> Self {
+        let setting = CameraSetting::Brightness;
         let text_size = ctx.theme.fonts.size.h5;
-        let text = Text::new(ctx, "Brightness", TextStyle::Heading, text_size, Align::Center);
+        let text = Text::new(ctx, setting.label(), TextStyle::Heading, text_size, Align::Center);
         let options = SettingsOptions::new(ctx);
-        let action = SettingsValue::event("brightness".to_string());
-        let value = SettingsValue::get(settings, "brightness".to_string());
-        let edit_slider = EditSlider::new(ctx, value, action);
+        let edit_slider = EditSlider::new(ctx, setting, setting.get(&settings));
         let layout = Column::new(24.0, Offset::Center, Size::Fit, Padding::default());
-        EditSettingsBumper(layout, text, options, edit_slider)
+        let presets = Button::secondary(ctx, None, "Presets", None, |ctx: &mut Context| ctx.trigger_event(PresetEvent::Open(None)), None);
+        let reset = Button::secondary(ctx, None, "Reset All", None, |ctx: &mut Context| ctx.trigger_event(ResetSettingsEvent), None);
+        let filters = Button::secondary(ctx, None, "Filters", None, |ctx: &mut Context| ctx.trigger_event(OpenFiltersEvent(None)), None);
+        let tone = Button::secondary(ctx, None, "Tone", None, |ctx: &mut Context| ctx.trigger_event(OpenToneEvent), None);
+        let actions = SettingsActions(Row::center(16.0), presets, filters, tone, reset);
+        let chroma_key = (*ctx.state().get_or_default::<CaptureMode>() == CaptureMode::ChromaKey).then(|| ChromaKeyOptions::new(ctx));
+        let mode = ctx.state().get_or_default::<LiveComparison>().comparison.map(|c| c.mode);
+        let compare = CompareOptions::new(ctx, mode);
+        EditSettingsBumper(layout, text, options, edit_slider, HistoryControls::new(ctx), compare, AutoOptions::new(ctx), actions, chroma_key)
+    }
+
+    /// The setting the slider is showing.
+    pub fn setting(&self) -> CameraSetting {
+        self.3.2.2
+    }
+
+    /// Moves the slider and value field to `value`, in the setting's units.
+    pub fn set_value(&mut self, value: f32) {
+        self.3.set_value(value)
+    }
+
+    pub fn set_text(&mut self, setting: CameraSetting) {
+        self.1.text().spans[0].text = setting.label().to_string();
+    }
+
+    pub fn set_slider(&mut self, ctx: &mut Context, setting: CameraSetting) {
+        self.3 = EditSlider::new(ctx, setting, setting.spec().default);
+    }
+}
+
+/// Undo, redo, and a hold-to-compare button that shows the camera unadjusted.
+#[derive(Debug, Component)]
+pub struct HistoryControls(Row, IconButton, IconButton, CompareButton, #[skip] (bool, bool));
+
+impl HistoryControls {
+    pub fn new(ctx: &mut Context) -> Self {
+        let undo = IconButton::ghost(ctx, "back", Box::new(|ctx: &mut Context| ctx.trigger_event(HistoryEvent::Undo)));
+        let redo = IconButton::ghost(ctx, "forward", Box::new(|ctx: &mut Context| ctx.trigger_event(HistoryEvent::Redo)));
+        let compare = CompareButton::new(ctx, "Hold to Compare", |ctx, on| ctx.trigger_event(HistoryEvent::Compare(on)));
+        let mut controls = HistoryControls(Row::center(16.0), undo, redo, compare, (true, true));
+        controls.update(ctx);
+        controls
+    }
+
+    // Disables undo and redo when there's nothing to step through.
+    fn update(&mut self, ctx: &mut Context) {
+        let history = ctx.state().get_or_default::<SettingsHistory>();
+        let available = (history.can_undo(), history.can_redo());
+        if available != self.4 {
+            self.4 = available;
+            for (button, enabled) in [(&mut self.1, available.0), (&mut self.2, available.1)] {
+                let status = if enabled {ButtonState::Default} else {ButtonState::Disabled};
+                *button.status() = status;
+                button.color(ctx, status);
+            }
+        }
+    }
+}
+
+impl OnEvent for HistoryControls {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            self.update(ctx);
+        }
+        true
+    }
+}
+
+/// Called with `true` when the button is pressed and `false` once it's let go.
+pub type HoldAction = fn(&mut Context, bool);
+
+#[derive(Debug, Component)]
+pub struct CompareButton(Stack, Button, #[skip] bool, #[skip] HoldAction);
+
+impl CompareButton {
+    pub fn new(ctx: &mut Context, label: &str, on_hold: HoldAction) -> Self {
+        let button = Button::secondary(ctx, None, label, None, |_ctx: &mut Context| {}, None);
+        CompareButton(Stack::default(), button, false, on_hold)
+    }
+}
+
+impl OnEvent for CompareButton {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent { state: MouseState::Pressed, position: Some(_) }) = event.downcast_ref::<MouseEvent>() {
+            self.2 = true;
+            ctx.hardware.haptic();
+            (self.3)(ctx, true);
+        } else if let Some(MouseEvent { state: MouseState::Released | MouseState::LongPressReleased, .. }) = event.downcast_ref::<MouseEvent>() {
+            // Released anywhere, since the finger may have slid off the button.
+            if std::mem::take(&mut self.2) {
+                (self.3)(ctx, false);
+            }
+        }
+        true
+    }
+}
+
+/// Off, or one of the ways to show the original against the adjusted version.
+#[derive(Debug, Component)]
+pub struct CompareOptions(Row, Vec<Button>);
+
+impl CompareOptions {
+    pub fn new(ctx: &mut Context, selected: Option<CompareMode>) -> Self {
+        let modes = std::iter::once(None).chain(CompareMode::ALL.into_iter().map(Some));
+        let buttons = modes.map(|mode| {
+            let state = if mode == selected {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(mode.map_or("Off", |m| m.label())), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| ctx.trigger_event(CompareEvent::Mode(mode)),
+                None,
+            )
+        }).collect();
+        CompareOptions(Row::center(8.0), buttons)
+    }
+}
+
+impl OnEvent for CompareOptions {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(CompareEvent::Mode(selected)) = event.downcast_ref::<CompareEvent>() {
+            let modes = std::iter::once(None).chain(CompareMode::ALL.into_iter().map(Some));
+            for (button, mode) in self.1.iter_mut().zip(modes) {
+                let status = if mode == *selected {ButtonState::Selected} else {ButtonState::Default};
+                *button.status() = status;
+                button.color(ctx);
+            }
+        }
+        true
     }
+}
 
-    pub fn set_slider_value(&mut self, val: f32) {
-        self.3.set_value(val)
+/// Auto exposure and the auto white balance methods. Tap a selected method again to turn it off.
+#[derive(Debug, Component)]
+pub struct AutoOptions(Row, Vec<Button>, #[skip] AutoAdjust);
+
+impl AutoOptions {
+    pub fn new(ctx: &mut Context) -> Self {
+        let auto = *ctx.state().get_or_default::<AutoAdjust>();
+        let mut options = vec![("Auto Exposure", Box::new(|a: &mut AutoAdjust| a.exposure = !a.exposure) as Box<dyn Fn(&mut AutoAdjust)>)];
+        options.extend(AwbMethod::ALL.into_iter().map(|method| {
+            (method.label(), Box::new(move |a: &mut AutoAdjust| {
+                a.white_balance = (a.white_balance != Some(method)).then_some(method)
+            }) as Box<dyn Fn(&mut AutoAdjust)>)
+        }));
+        let buttons = options.into_iter().map(|(label, change)| {
+            Button::new(
+                ctx, None, None, Some(label), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, ButtonState::Default, Offset::Center,
+                move |ctx: &mut Context| {
+                    let mut auto = *ctx.state().get_or_default::<AutoAdjust>();
+                    change(&mut auto);
+                    auto.save();
+                    ctx.state().set(auto);
+                },
+                None,
+            )
+        }).collect();
+        let mut options = AutoOptions(Row::center(8.0), buttons, auto);
+        options.color(ctx, auto);
+        options
     }
 
-    pub fn set_text(&mut self, text: String) {
-        self.1.text().spans[0].text = text.replace('_', " ").split_whitespace().map(|w| w[..1].to_uppercase() + &w[1..]).collect::<Vec<_>>().join(" ");
+    fn color(&mut self, ctx: &mut Context, auto: AutoAdjust) {
+        let selected = [auto.exposure, auto.white_balance == Some(AwbMethod::GrayWorld), auto.white_balance == Some(AwbMethod::WhitePatch)];
+        self.1.iter_mut().zip(selected).for_each(|(button, selected)| {
+            *button.status() = if selected {ButtonState::Selected} else {ButtonState::Default};
+            button.color(ctx);
+        });
+        self.2 = auto;
     }
+}
 
-    pub fn set_slider(&mut self, settings: ImageSettings, ctx: &mut Context, i: String) {
-        let action = SettingsValue::event(i.to_string());
-        *self.3.slider() = Slider::new(ctx, 50.0, None, None, action);
+impl OnEvent for AutoOptions {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        // Follows the state so taking manual control shows up here straight away.
+        if event.downcast_ref::<TickEvent>().is_some() {
+            let auto = *ctx.state().get_or_default::<AutoAdjust>();
+            if auto != self.2 {
+                self.color(ctx, auto);
+            }
+        }
+        true
     }
 }
 
+#[derive(Debug, Component)]
+pub struct SettingsActions(Row, Button, Button, Button, Button);
+impl OnEvent for SettingsActions {}
+
+type SliderAction = Box<dyn FnMut(&mut Context, f32)>;
+
+// Drags snap to the setting's step and notable values, with a tap of haptics on landing at a notable one.
+fn slider_action(setting: CameraSetting, apply: fn(&mut Context, CameraSetting, f32)) -> SliderAction {
+    let mut last = None;
+    Box::new(move |ctx: &mut Context, p: f32| {
+        let value = setting.snap(setting.value_at(p));
+        if setting.is_notable(value) && last != Some(value) {
+            ctx.hardware.haptic();
+        }
+        last = Some(value);
+        apply(ctx, setting, value)
+    })
+}
+
 #[derive(Debug, Component)]
 pub struct SettingsOptions(Scroll, SettingsOptionsContent);
 
@@ -163,13 +487,12 @@ pub struct SettingsOptionsContent(Row, Vec<SettingsButton>);
 
 impl SettingsOptionsContent {
     pub fn new(ctx: &mut Context) -> Self {
-        let icons = vec!["brightness", "white_balance_r", "white_balance_g", "white_balance_b"];
-        let children = icons.into_iter().enumerate().map(|(idx, icon)| {
+        let children = CameraSetting::ALL.into_iter().enumerate().map(|(idx, setting)| {
             let closure = move |ctx: &mut Context| {
-                ctx.trigger_event(SettingsSelect(icon.to_string()));
+                ctx.trigger_event(SettingsSelect(setting));
             };
-            let button = IconButtonPreset::new(ctx, icon, 0 == idx, closure);
-            SettingsButton::new(icon.to_string(), button)
+            let button = IconButtonPreset::new(ctx, setting.spec().icon, 0 == idx, closure);
+            SettingsButton::new(setting, button)
         }).collect::<Vec<_>>();
         SettingsOptionsContent(Row::center(24.0), children)
     }
@@ -189,102 +512,412 @@ impl OnEvent for SettingsOptionsContent {
 }
 
 #[derive(Debug, Component)]
-pub struct EditSlider(Row, Button, Slider);
+pub struct EditSlider(Row, Button, SettingSlider, SettingValue);
 impl OnEvent for EditSlider {}
 
 impl EditSlider {
-    pub fn new(ctx: &mut Context, start: f32, on_click: Box<dyn FnMut(&mut Context, f32)>) -> Self {
+    pub fn new(ctx: &mut Context, setting: CameraSetting, value: f32) -> Self {
         let button = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(OpenSettingsEvent::Close));
-        let slider = Slider::new(ctx, start, None, None, on_click);
-        EditSlider(Row::center(24.0), button, slider)
+        EditSlider(Row::center(24.0), button, SettingSlider::new(ctx, setting, value), SettingValue::new(ctx, setting, value))
     }
 
-    pub fn set_value(&mut self, val: f32) {self.2.set_value(val)}
-    pub fn slider(&mut self) -> &mut Slider {&mut self.2}
+    pub fn set_value(&mut self, value: f32) {
+        self.2.set_value(value);
+        self.3.set_value(value);
+    }
 }
 
+/// The slider for one camera setting. Double-tap resets it to the setting's default.
 #[derive(Debug, Component)]
-pub struct PhotoWrap(Box<dyn Layout>, Vec<ImageButton>, Option<ExpandableText>);
-impl OnEvent for PhotoWrap {}
+pub struct SettingSlider(Stack, Slider, #[skip] CameraSetting, #[skip] Option<Instant>);
 
-impl PhotoWrap {
-    pub fn new(ctx: &mut Context) -> Self {
-        let text_size = ctx.theme.fonts.size.md;
-        let my_images: Vec<(String, (f32, f32))> = ctx.state().get_or_default::<MyCameraRoll>().0.clone();
-        let help_text = my_images.is_empty().then_some(ExpandableText::new(
-            ctx, "Your camera roll is empty.\nTake a photo to get started.", 
-            TextStyle::Primary, text_size, Align::Center, None
-        ));
+impl SettingSlider {
+    const DOUBLE_TAP: Duration = Duration::from_millis(300);
 
-        let layout = match my_images.is_empty() {
-            true => Box::new(Stack::center()) as Box<dyn Layout>,
-            false => Box::new(Wrap::new(8.0, 8.0)) as Box<dyn Layout>
-        };
+    pub fn new(ctx: &mut Context, setting: CameraSetting, value: f32) -> Self {
+        let slider = Slider::new(ctx, setting.slider_position(value), None, None, slider_action(setting, |ctx, setting, value| ctx.trigger_event(SetCameraSetting(setting, value))));
+        SettingSlider(Stack::default(), slider, setting, None)
+    }
+
+    pub fn set_value(&mut self, value: f32) {
+        self.1.set_value(self.2.slider_position(value))
+    }
+}
+
+impl OnEvent for SettingSlider {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent { state: MouseState::Pressed, position: Some(_) }) = event.downcast_ref::<MouseEvent>() {
+            if self.3.take().is_some_and(|t| t.elapsed() < Self::DOUBLE_TAP) {
+                // Keep the second tap from the slider so it doesn't start a drag.
+                ctx.hardware.haptic();
+                ctx.trigger_event(SetCameraSetting(self.2, self.2.spec().default));
+                return false;
+            }
+            self.3 = Some(Instant::now());
+        } else if let Some(SetCameraSetting(setting, value)) = event.downcast_ref::<SetCameraSetting>() {
+            if *setting == self.2 {
+                self.set_value(*value);
+            }
+        }
+        true
+    }
+}
+
+/// The current value in real units. Tap to type an exact value; it's applied when the field loses focus.
+#[derive(Debug, Component)]
+pub struct SettingValue(Stack, TextInput, #[skip] CameraSetting, #[skip] bool, #[skip] f32);
+
+impl SettingValue {
+    pub fn new(ctx: &mut Context, setting: CameraSetting, value: f32) -> Self {
+        let input = TextInput::new(ctx, Some(&setting.format(value)), None, setting.label(), None, TextInput::NO_ICON, false);
+        SettingValue(Stack(Offset::Center, Offset::Center, Size::Static(120.0), Size::Fit, Padding::default()), input, setting, false, value)
+    }
 
-        let my_photos = my_images.into_iter().map(|(i, s)| 
-            ImageButton::new(ctx, i, s)
-        ).collect();
+    pub fn set_value(&mut self, value: f32) {
+        self.4 = value;
+        if *self.1.status() != InputState::Focus {
+            *self.1.value() = self.2.format(value);
+        }
+    }
+}
 
-        PhotoWrap(layout, my_photos, help_text)
+impl OnEvent for SettingValue {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            let focused = *self.1.status() == InputState::Focus;
+            if self.3 && !focused {
+                match self.2.parse(self.1.value()) {
+                    Some(value) => ctx.trigger_event(SetCameraSetting(self.2, value)),
+                    None => *self.1.value() = self.2.format(self.4),
+                }
+            }
+            self.3 = focused;
+        } else if let Some(SetCameraSetting(setting, value)) = event.downcast_ref::<SetCameraSetting>() {
+            if *setting == self.2 {
+                self.set_value(*value);
+            }
+        }
+        true
     }
 }
 
+/// Applies `change` to the saved presets and reopens the sheet to show it.
+fn update_presets(ctx: &mut Context, change: impl FnOnce(&mut Presets)) {
+    let mut presets = ctx.state().get_or_default::<Presets>().clone();
+    change(&mut presets);
+    presets.save();
+    ctx.state().set(presets);
+    ctx.trigger_event(PresetEvent::Open(None));
+}
+
 #[derive(Debug, Component)]
-pub struct ImageButton(Stack, ExpandableImage, #[skip] String, #[skip] (f32, f32));
-impl OnEvent for ImageButton {
+pub struct PresetsBumper(Column, Text, Option<Text>, PresetNameRow, Vec<PresetRow>, PresetActions);
+impl OnEvent for PresetsBumper {}
+
+impl PresetsBumper {
+    pub fn new(ctx: &mut Context, message: Option<&str>) -> Self {
+        let presets = ctx.state().get_or_default::<Presets>().clone();
+        let font_size = ctx.theme.fonts.size;
+        let title = Text::new(ctx, "Presets", TextStyle::Heading, font_size.h5, Align::Center);
+        let message = message.map(|m| Text::new(ctx, m, TextStyle::Secondary, font_size.sm, Align::Center));
+        let name = TextInput::new(ctx, None, None, &presets.next_name(), None, TextInput::NO_ICON, false);
+        let save = Button::secondary(ctx, None, "Save Current", None, |ctx: &mut Context| ctx.trigger_event(PresetEvent::Save), None);
+        let rows = presets.presets.iter().enumerate().map(|(i, p)| PresetRow::new(ctx, i, p, presets.default.as_ref() == Some(&p.name))).collect();
+
+        let import = Button::secondary(ctx, None, "Import", None, |ctx: &mut Context| {
+            let text = ctx.hardware.paste();
+            match Preset::import(&text) {
+                Ok(preset) => update_presets(ctx, |p| p.insert(preset)),
+                Err(error) => ctx.trigger_event(PresetEvent::Open(Some(error))),
+            }
+        }, None);
+        let done = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(OpenSettingsEvent::Open));
+        PresetsBumper(
+            Column::new(24.0, Offset::Center, Size::Fit, Padding::default()), title, message,
+            PresetNameRow(Row::center(16.0), name, save), rows, PresetActions(Row::center(16.0), import, done)
+        )
+    }
+
+    /// The typed name, or the next free default name if nothing was typed.
+    pub fn name(&mut self, ctx: &mut Context) -> String {
+        let typed = self.3.1.value().trim().to_string();
+        match typed.is_empty() {
+            true => ctx.state().get_or_default::<Presets>().next_name(),
+            false => typed
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct PresetNameRow(Row, TextInput, Button);
+impl OnEvent for PresetNameRow {}
+
+#[derive(Debug, Component)]
+pub struct PresetActions(Row, Button, Button);
+impl OnEvent for PresetActions {}
+
+/// A saved preset: tap the name to apply it, or mark it as the startup default, export or delete it.
+#[derive(Debug, Component)]
+pub struct PresetRow(Row, Button, IconButton, IconButton, IconButton);
+impl OnEvent for PresetRow {}
+
+impl PresetRow {
+    pub fn new(ctx: &mut Context, index: usize, preset: &Preset, default: bool) -> Self {
+        let apply = Button::new(
+            ctx, None, None, Some(&preset.name), None,
+            ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, ButtonState::Default, Offset::Center,
+            move |ctx: &mut Context| ctx.trigger_event(PresetEvent::Apply(index)),
+            None,
+        );
+        let name = preset.name.clone();
+        let make_default = IconButtonPreset::new(ctx, "checkmark", default, move |ctx: &mut Context| {
+            let name = name.clone();
+            update_presets(ctx, move |p| p.default = (p.default.as_ref() != Some(&name)).then_some(name));
+        });
+        let exported = preset.clone();
+        let export = IconButton::ghost(ctx, "share", Box::new(move |ctx: &mut Context| {
+            ctx.hardware.share(&exported.export());
+        }));
+        let delete = IconButton::ghost(ctx, "delete", Box::new(move |ctx: &mut Context| update_presets(ctx, |p| p.remove(index))));
+        PresetRow(Row::new(12.0, Offset::Center, Size::Fit, Padding::default()), apply, make_default, export, delete)
+    }
+}
+
+/// Applies `change` to the saved filter settings.
+fn update_filters(ctx: &mut Context, change: impl FnOnce(&mut Filters)) {
+    let mut filters = ctx.state().get_or_default::<Filters>().clone();
+    change(&mut filters);
+    if filters != *ctx.state().get_or_default::<Filters>() {
+        filters.save();
+        ctx.state().set(filters);
+    }
+}
+
+/// Applies `change` to the filter settings in use, leaving them to be saved once a drag ends.
+fn adjust_filters(ctx: &mut Context, change: impl FnOnce(&mut Filters)) {
+    let mut filters = ctx.state().get_or_default::<Filters>().clone();
+    change(&mut filters);
+    ctx.state().set(filters);
+}
+
+/// The tone curves and then the active filter, taken from the state so they can be
+/// applied to frames being saved off the UI thread.
+#[derive(Clone, Debug)]
+struct Grade(Option<ImageSettings>, ToneCurves, Option<Arc<Lut>>, Filters);
+
+impl Grade {
+    fn new(ctx: &mut Context) -> Self {
+        // While comparing, the camera is at its defaults and the settings are applied here instead.
+        let live = ctx.state().get_or_default::<LiveComparison>().clone();
+        let tone = ctx.state().get_or_default::<ToneCurves>().clone();
+        let filters = ctx.state().get_or_default::<Filters>().clone();
+        let lut = filters.selected.as_deref().and_then(|name| ctx.state().get_or_default::<Luts>().get(name));
+        Grade(live.comparison.is_some().then_some(live.settings), tone, lut, filters)
+    }
+
+    fn apply(&self, image: RgbaImage) -> RgbaImage {
+        let image = match &self.0 {
+            Some(settings) => edit::process(image, settings),
+            None => image
+        };
+        let image = self.1.apply(image);
+        match &self.2 {
+            Some(lut) => lut.apply(&image, self.3.intensity, self.3.interpolation),
+            None => image
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct FiltersBumper(Column, Text, Option<Text>, FilterOptions, Slider, InterpolationOptions, FilterActions, #[skip] Filters);
+
+impl OnEvent for FiltersBumper {
     fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
-        if let Some(MouseEvent{state: MouseState::Pressed, position: Some(_)}) = event.downcast_ref::<MouseEvent>() {
-            ctx.hardware.haptic();
-            ctx.trigger_event(SelectImageEvent(self.2.to_string(), self.3));
-            ctx.trigger_event(NavigateEvent(1));
+        if let Some(MouseEvent { state: MouseState::Released | MouseState::LongPressReleased, .. }) = event.downcast_ref::<MouseEvent>() {
+            let filters = ctx.state().get_or_default::<Filters>();
+            if *filters != self.7 {
+                filters.save();
+                self.7 = filters.clone();
+            }
         }
         true
     }
 }
 
-impl ImageButton {
-    pub fn new(ctx: &mut Context, i: String, size: (f32, f32)) -> Self {
-        let image = EncodedImage::decode(ctx, &i.clone());
-        ImageButton(
-            Stack(Offset::Center, Offset::Center, Size::Static(64.0), Size::Static(64.0), Padding::default()), 
-            ExpandableImage::new(image, None), i.to_string(), size
+impl FiltersBumper {
+    pub fn new(ctx: &mut Context, message: Option<&str>) -> Self {
+        let filters = ctx.state().get_or_default::<Filters>().clone();
+        let font_size = ctx.theme.fonts.size;
+        let title = Text::new(ctx, "Filters", TextStyle::Heading, font_size.h5, Align::Center);
+        let message = message.map(|m| Text::new(ctx, m, TextStyle::Secondary, font_size.sm, Align::Center));
+        let intensity = Slider::new(ctx, filters.intensity, Some("Intensity"), None, |ctx: &mut Context, p: f32| {
+            adjust_filters(ctx, |f| f.intensity = p);
+        });
+        // Imports the contents of a .cube file copied to the clipboard.
+        let import = Button::secondary(ctx, None, "Import", None, |ctx: &mut Context| {
+            let text = ctx.hardware.paste();
+            let mut luts = ctx.state().get_or_default::<Luts>().clone();
+            let mut filters = ctx.state().get_or_default::<Filters>().clone();
+            match luts.import(&mut filters, &text) {
+                Ok(lut) => {
+                    filters.selected = Some(lut.name.clone());
+                    filters.save();
+                    ctx.state().set(filters);
+                    ctx.state().set(luts);
+                    ctx.trigger_event(OpenFiltersEvent(None));
+                },
+                Err(error) => ctx.trigger_event(OpenFiltersEvent(Some(error))),
+            }
+        }, None);
+        let done = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(OpenSettingsEvent::Open));
+        FiltersBumper(
+            Column::new(24.0, Offset::Center, Size::Fit, Padding::default()), title, message,
+            FilterOptions::new(ctx), intensity, InterpolationOptions::new(ctx), FilterActions(Row::center(16.0), import, done), filters
         )
     }
 }
 
 #[derive(Debug, Component)]
-pub struct AlbacoreCamera(Stack, ExpandableImage, #[skip] Option<Camera>, #[skip] Option<RgbaImage>);
+pub struct FilterActions(Row, Button, Button);
+impl OnEvent for FilterActions {}
 
-impl AlbacoreCamera {
+#[derive(Debug, Component)]
+pub struct InterpolationOptions(Row, Vec<Button>, #[skip] Interpolation);
+
+impl InterpolationOptions {
     pub fn new(ctx: &mut Context) -> Self {
-        let blank = ctx.theme.brand.illustrations.get("blank").unwrap();
-        
-        AlbacoreCamera(
-            Stack(Offset::Center,Offset::Center,Size::fill(),Size::fill(),Padding::default()),
-            ExpandableImage::new(blank, None), Camera::new_custom().ok(), None
-        )
+        let current = ctx.state().get_or_default::<Filters>().interpolation;
+        let buttons = Interpolation::ALL.into_iter().map(|interpolation| {
+            let state = if interpolation == current {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(interpolation.label()), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| update_filters(ctx, |f| f.interpolation = interpolation),
+                None,
+            )
+        }).collect();
+        InterpolationOptions(Row::center(8.0), buttons, current)
     }
+}
 
-    pub fn camera(&mut self) -> &mut Option<Camera> {&mut self.2}
+impl OnEvent for InterpolationOptions {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            let current = ctx.state().get_or_default::<Filters>().interpolation;
+            if current != self.2 {
+                self.2 = current;
+                self.1.iter_mut().zip(Interpolation::ALL).for_each(|(button, i)| {
+                    *button.status() = if i == current {ButtonState::Selected} else {ButtonState::Default};
+                    button.color(ctx);
+                });
+            }
+        }
+        true
+    }
 }
 
-impl OnEvent for AlbacoreCamera {
+#[derive(Debug, Component)]
+pub struct FilterOptions(Scroll, FilterOptionsContent);
+
+impl FilterOptions {
+    pub fn new(ctx: &mut Context) -> Self {
+        let width = Size::custom(move |_widths| (0.0, f32::MAX));
+        let height = Size::custom(move |heights: Vec<(f32, f32)>| (heights[0].0, heights[0].1));
+        let layout = Scroll::horizontal(Offset::Start, Offset::Start, width, height, Padding::default(), ScrollAnchor::Start);
+        FilterOptions(layout, FilterOptionsContent::new(ctx))
+    }
+}
+
+impl OnEvent for FilterOptions {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(AdjustScrollEvent::Horizontal(a)) = event.downcast_ref::<AdjustScrollEvent>() {
+            self.0.adjust_scroll(*a);
+        } else if let Some(MouseEvent { state: MouseState::Scroll(x, _), position: Some(_) }) = event.downcast_ref::<MouseEvent>() {
+            self.0.adjust_scroll(*x);
+        }
+        true
+    }
+}
+
+/// Thumbnails for every option are graded together on a worker, at most every `GRADE_INTERVAL`.
+type ThumbnailGrade = (Arc<RgbaImage>, Vec<Option<Arc<Lut>>>, Interpolation);
+
+#[derive(Debug, Component)]
+pub struct FilterOptionsContent(Row, Vec<FilterOption>, #[skip] Worker<ThumbnailGrade, Vec<RgbaImage>>, #[skip] Option<Instant>);
+
+impl FilterOptionsContent {
+    const GRADE_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(ctx: &mut Context) -> Self {
+        let luts = ctx.state().get_or_default::<Luts>().clone();
+        let options = std::iter::once(None).chain(luts.0.into_iter().map(Some)).map(|lut| FilterOption::new(ctx, lut)).collect();
+        let grader = Worker::latest(|(frame, luts, interpolation): ThumbnailGrade| {
+            luts.iter().map(|lut| match lut {
+                Some(lut) => lut.apply(&frame, 100.0, interpolation),
+                None => frame.as_ref().clone()
+            }).collect()
+        });
+        FilterOptionsContent(Row::center(8.0), options, grader, None)
+    }
+}
+
+impl OnEvent for FilterOptionsContent {
     fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
-        if let Some(TickEvent) = event.downcast_ref::<TickEvent>() {
-            if let Some(ref mut camera) = self.2 {
-                if let Some(raw_frame) = camera.get_frame() {
-                    self.3 = Some(raw_frame.clone());
-                    let image = ctx.assets.add_image(raw_frame);
-                    self.1.image().image = image;
-                }
+        if event.downcast_ref::<TickEvent>().is_some() {
+            for thumbnails in self.2.poll() {
+                self.1.iter_mut().zip(thumbnails).for_each(|(option, image)| option.set_thumbnail(ctx, image));
             }
-        } else if let Some(TakePhotoEvent) = event.downcast_ref::<TakePhotoEvent>() {
-            if let Some(rgba) = &self.3 {
-                let mut guard = ctx.get::<LensPlugin>();
-                let plugin = guard.get().0;
-                let image = EncodedImage::encode_rgba(rgba.clone());
-                let size = self.1.image().image.size();
-                plugin.request(LensRequest::SavePhoto(image, (size.0 as f32, size.1 as f32)));
+            // Only a thumbnail taken since the last grade is worth grading again.
+            let thumbnail = ctx.state().get_or_default::<LiveThumbnail>().0.clone().filter(|(taken, _)| {
+                self.3.is_none_or(|graded| graded.elapsed() >= Self::GRADE_INTERVAL && *taken > graded)
+            });
+            if let Some((_, frame)) = thumbnail.filter(|_| !self.2.is_busy()) {
+                self.3 = Some(Instant::now());
+                let interpolation = ctx.state().get_or_default::<Filters>().interpolation;
+                let luts = self.1.iter().map(|option| option.3.clone()).collect();
+                self.2.submit((frame, luts, interpolation));
+            }
+        }
+        true
+    }
+}
+
+/// A LUT, or no filter, shown on a thumbnail of the live frame. Tap to choose it.
+#[derive(Debug, Component)]
+pub struct FilterOption(Column, FilterThumbnail, Button, #[skip] Option<Arc<Lut>>, #[skip] bool);
+
+impl FilterOption {
+    pub fn new(ctx: &mut Context, lut: Option<Arc<Lut>>) -> Self {
+        let name = lut.as_ref().map(|l| l.name.clone());
+        let selected = ctx.state().get_or_default::<Filters>().selected == name;
+        let state = if selected {ButtonState::Selected} else {ButtonState::Default};
+        let label = name.clone().unwrap_or("None".to_string());
+        let button = Button::new(
+            ctx, None, None, Some(&label), None,
+            ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+            move |ctx: &mut Context| {
+                let name = name.clone();
+                ctx.hardware.haptic();
+                update_filters(ctx, |f| f.selected = name);
+            },
+            None,
+        );
+        FilterOption(Column::center(4.0), FilterThumbnail::new(ctx), button, lut, selected)
+    }
+
+    fn set_thumbnail(&mut self, ctx: &mut Context, image: RgbaImage) {
+        self.1.1.image().image = ctx.assets.add_image(image);
+    }
+}
+
+impl OnEvent for FilterOption {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            let selected = ctx.state().get_or_default::<Filters>().selected == self.3.as_ref().map(|l| l.name.clone());
+            if selected != self.4 {
+                self.4 = selected;
+                *self.2.status() = if selected {ButtonState::Selected} else {ButtonState::Default};
+                self.2.color(ctx);
             }
         }
         true
@@ -292,16 +925,1825 @@ impl OnEvent for AlbacoreCamera {
 }
 
 #[derive(Debug, Component)]
-pub struct SettingsButton(Stack, IconButton, #[skip] String);
+pub struct FilterThumbnail(Stack, ExpandableImage);
+impl OnEvent for FilterThumbnail {}
+
+impl FilterThumbnail {
+    pub fn new(ctx: &mut Context) -> Self {
+        let blank = ctx.theme.brand.illustrations.get("blank").unwrap();
+        let size = lut::THUMBNAIL_SIZE as f32;
+        FilterThumbnail(Stack(Offset::Center, Offset::Center, Size::Static(size), Size::Static(size), Padding::default()), ExpandableImage::new(blank, None))
+    }
+}
+
+/// Applies `change` to the saved green screen settings.
+fn update_chroma_key(ctx: &mut Context, change: impl FnOnce(&mut ChromaKey)) {
+    let mut settings = ctx.state().get_or_default::<ChromaKey>().clone();
+    change(&mut settings);
+    if settings != *ctx.state().get_or_default::<ChromaKey>() {
+        settings.save();
+        ctx.state().set(settings);
+    }
+}
+
+/// Applies `change` to the green screen settings in use, leaving them to be saved once a drag ends.
+fn adjust_chroma_key(ctx: &mut Context, change: impl FnOnce(&mut ChromaKey)) {
+    let mut settings = ctx.state().get_or_default::<ChromaKey>().clone();
+    change(&mut settings);
+    ctx.state().set(settings);
+}
+
+/// Tone curves and levels for the camera, applied to the preview and to everything captured.
+#[derive(Debug, Component)]
+pub struct ToneBumper(Column, Text, ToneEditor, Button, #[skip] ToneCurves, #[skip] bool);
+
+impl ToneBumper {
+    pub fn new(ctx: &mut Context) -> Self {
+        let curves = ctx.state().get_or_default::<ToneCurves>().clone();
+        let font_size = ctx.theme.fonts.size.h5;
+        let title = Text::new(ctx, "Tone", TextStyle::Heading, font_size, Align::Center);
+        let editor = ToneEditor::new(ctx, curves.clone(), Histogram::default(), true);
+        let done = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(OpenSettingsEvent::Open));
+        ToneBumper(Column::new(24.0, Offset::Center, Size::Fit, Padding::default()), title, editor, done, curves, false)
+    }
+
+    fn save(&mut self, ctx: &mut Context) {
+        let curves = ctx.state().get_or_default::<ToneCurves>();
+        if *curves != self.4 {
+            curves.save();
+            self.4 = curves.clone();
+        }
+    }
+}
+
+// Changes made while a finger is down are saved once it's lifted; a reset is saved straight away.
+impl OnEvent for ToneBumper {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(ToneEvent::Set(curves)) = event.downcast_ref::<ToneEvent>() {
+            ctx.state().set(curves.clone());
+            if !self.5 { self.save(ctx); }
+        } else if let Some(MouseEvent { state, .. }) = event.downcast_ref::<MouseEvent>() {
+            match state {
+                MouseState::Pressed => self.5 = true,
+                MouseState::Released | MouseState::LongPressReleased => {
+                    self.5 = false;
+                    self.save(ctx);
+                },
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+/// Per-channel curves and levels. Sends `ToneEvent::Set` with every change and leaves applying it to the page.
+#[derive(Debug, Component)]
+pub struct ToneEditor(Column, ToneChannels, CurveView, LevelsView, Button, #[skip] bool, #[skip] Option<Instant>);
+
+impl ToneEditor {
+    /// A `live` editor follows the camera's histogram; otherwise it keeps the one it was given.
+    pub fn new(ctx: &mut Context, curves: ToneCurves, histogram: Histogram, live: bool) -> Self {
+        let reset = Button::secondary(ctx, None, "Reset Tone", None, |ctx: &mut Context| ctx.trigger_event(ToneEvent::Set(ToneCurves::default())), None);
+        ToneEditor(
+            Column::new(16.0, Offset::Center, Size::Fit, Padding::default()), ToneChannels::new(ctx),
+            CurveView::new(ctx, curves.clone(), histogram.clone()), LevelsView::new(ctx, curves, histogram), reset, live, None
+        )
+    }
+}
+
+impl OnEvent for ToneEditor {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() && self.5 {
+            let LiveThumbnail(latest) = ctx.state().get_or_default::<LiveThumbnail>().clone();
+            if let Some((taken, frame)) = latest.filter(|(taken, _)| self.6 != Some(*taken)) {
+                self.6 = Some(taken);
+                let histogram = Histogram::of(&frame);
+                self.2.set_histogram(ctx, histogram.clone());
+                self.3.set_histogram(ctx, histogram);
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct ToneChannels(Row, Vec<Button>);
+
+impl ToneChannels {
+    pub fn new(ctx: &mut Context) -> Self {
+        let buttons = Channel::ALL.into_iter().map(|channel| {
+            let state = if channel == Channel::default() {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(channel.label()), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| ctx.trigger_event(ToneEvent::Channel(channel)),
+                None,
+            )
+        }).collect();
+        ToneChannels(Row::center(8.0), buttons)
+    }
+}
+
+impl OnEvent for ToneChannels {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(ToneEvent::Channel(selected)) = event.downcast_ref::<ToneEvent>() {
+            self.1.iter_mut().zip(Channel::ALL).for_each(|(button, channel)| {
+                *button.status() = if channel == *selected {ButtonState::Selected} else {ButtonState::Default};
+                button.color(ctx);
+            });
+        }
+        true
+    }
+}
+
+/// Tap to add a control point, drag to move one, double-tap to remove it.
+#[derive(Debug, Component)]
+pub struct CurveView(Stack, ExpandableImage, #[skip] ToneCurves, #[skip] Channel, #[skip] Histogram, #[skip] Option<usize>, #[skip] Option<(usize, Instant)>);
+
+impl CurveView {
+    const REACH: f32 = 0.06;
+    const DOUBLE_TAP: Duration = Duration::from_millis(300);
+
+    pub fn new(ctx: &mut Context, curves: ToneCurves, histogram: Histogram) -> Self {
+        let image = ctx.assets.add_image(curves::draw_curve(&curves, Channel::default(), &histogram));
+        let size = curves::VIEW_SIZE as f32;
+        CurveView(Stack::default(), ExpandableImage::new(image, Some((size, size))), curves, Channel::default(), histogram, None, None)
+    }
+
+    pub fn set_histogram(&mut self, ctx: &mut Context, histogram: Histogram) {
+        self.4 = histogram;
+        self.draw(ctx);
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        self.1.image().image = ctx.assets.add_image(curves::draw_curve(&self.2, self.3, &self.4));
+    }
+
+    // Input and output levels under a point on the view, from 0 to 1 with output going up.
+    fn level(&mut self, (x, y): (f32, f32)) -> Option<(f32, f32)> {
+        match self.1.image().shape {
+            ShapeType::Rectangle(_, (w, h)) if w > 0.0 && h > 0.0 => Some(((x / w).clamp(0.0, 1.0), 1.0 - (y / h).clamp(0.0, 1.0))),
+            _ => None
+        }
+    }
+
+    fn changed(&mut self, ctx: &mut Context) {
+        self.draw(ctx);
+        ctx.trigger_event(ToneEvent::Set(self.2.clone()));
+    }
+}
+
+impl OnEvent for CurveView {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent { state, position }) = event.downcast_ref::<MouseEvent>() {
+            let level = position.and_then(|p| self.level(p));
+            match (state, level) {
+                (MouseState::Pressed, Some(p)) => {
+                    let curve = self.2.curve_mut(self.3);
+                    match curve.nearest(p, Self::REACH) {
+                        Some(i) if self.6.take().is_some_and(|(last, at)| last == i && at.elapsed() < Self::DOUBLE_TAP) => {
+                            curve.remove(i);
+                            self.changed(ctx);
+                        },
+                        Some(i) => {
+                            self.5 = Some(i);
+                            self.6 = Some((i, Instant::now()));
+                        },
+                        None => if let Some(i) = curve.insert(p) {
+                            self.5 = Some(i);
+                            self.changed(ctx);
+                        }
+                    }
+                },
+                (MouseState::Moved | MouseState::Scroll(..), Some(p)) => if let Some(i) = self.5 {
+                    self.2.curve_mut(self.3).move_point(i, p);
+                    self.changed(ctx);
+                },
+                (MouseState::Released | MouseState::LongPressReleased, _) => self.5 = None,
+                _ => {}
+            }
+        } else if let Some(tone) = event.downcast_ref::<ToneEvent>() {
+            match tone {
+                ToneEvent::Channel(channel) => self.3 = *channel,
+                ToneEvent::Set(curves) => self.2 = curves.clone(),
+                ToneEvent::Level(..) => return true,
+            }
+            self.draw(ctx);
+        }
+        true
+    }
+}
+
+/// The luma histogram with sliders for the black, mid and white points.
+#[derive(Debug, Component)]
+pub struct LevelsView(Column, ExpandableImage, Slider, Slider, Slider, #[skip] ToneCurves, #[skip] Histogram);
+
+impl LevelsView {
+    pub fn new(ctx: &mut Context, curves: ToneCurves, histogram: Histogram) -> Self {
+        let levels = curves.levels;
+        let image = curves::draw_levels(&levels, &histogram);
+        let size = (image.width() as f32, image.height() as f32);
+        let image = ctx.assets.add_image(image);
+        let black = Slider::new(ctx, levels.black * 100.0, Some("Black Point"), None, |ctx: &mut Context, p: f32| {
+            ctx.trigger_event(ToneEvent::Level(LevelPoint::Black, p / 100.0))
+        });
+        let mid = Slider::new(ctx, Self::mid_position(levels.mid), Some("Midtones"), None, |ctx: &mut Context, p: f32| {
+            let mid = (Levels::MID_RANGE.0 * (Levels::MID_RANGE.1 / Levels::MID_RANGE.0).powf(p / 100.0) * 100.0).round() / 100.0;
+            ctx.trigger_event(ToneEvent::Level(LevelPoint::Mid, mid))
+        });
+        let white = Slider::new(ctx, levels.white * 100.0, Some("White Point"), None, |ctx: &mut Context, p: f32| {
+            ctx.trigger_event(ToneEvent::Level(LevelPoint::White, p / 100.0))
+        });
+        LevelsView(Column::new(8.0, Offset::Center, Size::Fit, Padding::default()), ExpandableImage::new(image, Some(size)), black, mid, white, curves, histogram)
+    }
+
+    // The mid slider is logarithmic so 1 sits in the middle.
+    fn mid_position(mid: f32) -> f32 {
+        let (lo, hi) = Levels::MID_RANGE;
+        (mid / lo).ln() / (hi / lo).ln() * 100.0
+    }
+
+    pub fn set_histogram(&mut self, ctx: &mut Context, histogram: Histogram) {
+        self.6 = histogram;
+        self.draw(ctx);
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        self.1.image().image = ctx.assets.add_image(curves::draw_levels(&self.5.levels, &self.6));
+    }
+}
+
+impl OnEvent for LevelsView {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        match event.downcast_ref::<ToneEvent>() {
+            Some(ToneEvent::Level(point, value)) => {
+                let mut curves = self.5.clone();
+                curves.levels.set(*point, *value);
+                ctx.trigger_event(ToneEvent::Set(curves));
+            },
+            Some(ToneEvent::Set(curves)) => {
+                let levels = curves.levels;
+                if levels != self.5.levels {
+                    self.2.set_value(levels.black * 100.0);
+                    self.3.set_value(Self::mid_position(levels.mid));
+                    self.4.set_value(levels.white * 100.0);
+                }
+                self.5 = curves.clone();
+                self.draw(ctx);
+            },
+            _ => {}
+        }
+        true
+    }
+}
+
+/// The sliders change the settings as they're dragged, and they're saved once let go.
+#[derive(Debug, Component)]
+pub struct ChromaKeyOptions(Column, Slider, Slider, BackgroundOptions, #[skip] ChromaKey);
+
+impl OnEvent for ChromaKeyOptions {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent { state: MouseState::Released | MouseState::LongPressReleased, .. }) = event.downcast_ref::<MouseEvent>() {
+            let settings = ctx.state().get_or_default::<ChromaKey>();
+            if *settings != self.4 {
+                settings.save();
+                self.4 = settings.clone();
+            }
+        }
+        true
+    }
+}
+
+impl ChromaKeyOptions {
+    pub fn new(ctx: &mut Context) -> Self {
+        let settings = ctx.state().get_or_default::<ChromaKey>().clone();
+        let tolerance = Slider::new(ctx, settings.tolerance, Some("Tolerance"), Some("Tap the viewfinder to pick the key color."), |ctx: &mut Context, p: f32| {
+            adjust_chroma_key(ctx, |s| s.tolerance = p);
+        });
+        let spill = Slider::new(ctx, settings.spill, Some("Spill Suppression"), None, |ctx: &mut Context, p: f32| {
+            adjust_chroma_key(ctx, |s| s.spill = p);
+        });
+        ChromaKeyOptions(Column::new(16.0, Offset::Start, Size::Fit, Padding::default()), tolerance, spill, BackgroundOptions::new(ctx), settings)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct BackgroundOptions(Scroll, BackgroundOptionsContent);
+
+impl BackgroundOptions {
+    pub fn new(ctx: &mut Context) -> Self {
+        let width = Size::custom(move |_widths| (0.0, f32::MAX));
+        let height = Size::custom(move |heights: Vec<(f32, f32)>| (heights[0].0.min(64.0), 64.0));
+        let layout = Scroll::horizontal(Offset::Start, Offset::Start, width, height, Padding::default(), ScrollAnchor::Start);
+        BackgroundOptions(layout, BackgroundOptionsContent::new(ctx))
+    }
+}
+
+impl OnEvent for BackgroundOptions {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(AdjustScrollEvent::Horizontal(a)) = event.downcast_ref::<AdjustScrollEvent>() {
+            self.0.adjust_scroll(*a);
+        } else if let Some(MouseEvent { state: MouseState::Scroll(x, _), position: Some(_) }) = event.downcast_ref::<MouseEvent>() {
+            self.0.adjust_scroll(*x);
+        }
+        true
+    }
+}
+
+/// The roll, newest first, a page at a time. A "More" button at the end brings in the next page.
+#[derive(Debug, Component)]
+pub struct BackgroundOptionsContent(Row, Vec<BackgroundOption>, Option<Button>);
+
+impl OnEvent for BackgroundOptionsContent {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<MoreBackgroundsEvent>().is_some() {
+            self.page(ctx);
+        }
+        true
+    }
+}
+
+impl BackgroundOptionsContent {
+    // Photos are decoded a page at a time to keep the sheet quick to open.
+    const PAGE: usize = 12;
+
+    pub fn new(ctx: &mut Context) -> Self {
+        let mut content = BackgroundOptionsContent(Row::center(8.0), Vec::new(), None);
+        content.page(ctx);
+        content
+    }
+
+    fn page(&mut self, ctx: &mut Context) {
+        let roll = ctx.state().get_or_default::<MyCameraRoll>().clone();
+        let mut photos = roll.visible().rev().skip(self.1.len());
+        photos.by_ref().take(Self::PAGE).for_each(|photo| self.1.push(BackgroundOption::new(ctx, photo)));
+        self.2 = photos.next().is_some().then(|| {
+            Button::secondary(ctx, None, "More", None, |ctx: &mut Context| ctx.trigger_event(MoreBackgroundsEvent), None)
+        });
+    }
+}
+
+/// A camera roll photo that becomes the green screen background when tapped.
+#[derive(Debug, Component)]
+pub struct BackgroundOption(Stack, ExpandableImage, #[skip] uuid::Uuid);
+
+impl BackgroundOption {
+    pub fn new(ctx: &mut Context, photo: &Photo) -> Self {
+        let image = EncodedImage::decode(ctx, photo.shown());
+        BackgroundOption(
+            Stack(Offset::Center, Offset::Center, Size::Static(64.0), Size::Static(64.0), Padding::default()),
+            ExpandableImage::new(image, None), photo.id
+        )
+    }
+}
+
+impl OnEvent for BackgroundOption {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent{state: MouseState::Pressed, position: Some(_)}) = event.downcast_ref::<MouseEvent>() {
+            ctx.hardware.haptic();
+            let id = self.2;
+            update_chroma_key(ctx, |s| s.background = Some(id));
+        }
+        true
+    }
+}
+
+/// The library as a timeline: a section per day, month or year whose header stays pinned
+/// while its photos scroll past, and a scrubber down the side once it's long enough to need one.
+#[derive(Debug, Component)]
+pub struct PhotoWrap(TimelineLayout, Vec<ImageButton>, Vec<SectionHeader>, SectionHeader, RoundedRectangle, RoundedRectangle, SectionHeader, Option<ExpandableText>, #[skip] Vec<String>, #[skip] usize);
+
+impl OnEvent for PhotoWrap {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            self.load_visible(ctx);
+        } else if let Some(MouseEvent{state, position}) = event.downcast_ref::<MouseEvent>() {
+            let mut timeline = self.0.0.lock().unwrap();
+            let (width, view) = timeline.size;
+            let max = timeline.geometry.max_scroll(view);
+            match (state, position) {
+                (MouseState::Pressed, Some((x, y))) if timeline.geometry.scrubbable(view) && *x >= width - timeline::SCRUBBER => {
+                    timeline.scrubbing = true;
+                    timeline.scroll = timeline.geometry.scrub(*y, view);
+                },
+                (MouseState::Moved | MouseState::Scroll(..), Some((_, y))) if timeline.scrubbing => timeline.scroll = timeline.geometry.scrub(*y, view),
+                (MouseState::Scroll(_, dy), Some(_)) => timeline.scroll = (timeline.scroll + dy).clamp(0.0, max),
+                (MouseState::Released | MouseState::LongPressReleased, _) => timeline.scrubbing = false,
+                _ => {}
+            }
+            let (section, scrubbing) = (timeline.geometry.sticky(timeline.scroll).0, timeline.scrubbing);
+            drop(timeline);
+            if section != self.9 {
+                if let Some(title) = self.8.get(section) {
+                    self.3.set(title);
+                    self.6.set(title);
+                }
+                self.9 = section;
+                if scrubbing { ctx.hardware.haptic(); }
+            }
+        }
+        true
+    }
+}
+
+impl PhotoWrap {
+    // Thumbnails are decoded once their section comes near the view, so large libraries open quickly.
+    fn load_visible(&mut self, ctx: &mut Context) {
+        let timeline = self.0.0.lock().unwrap();
+        let sections = timeline.geometry.visible(timeline.scroll, timeline.size.1);
+        let start = timeline.counts[..sections.start].iter().sum::<usize>();
+        let end = start + timeline.counts[sections].iter().sum::<usize>();
+        drop(timeline);
+        self.1[start..end].iter_mut().for_each(|button| button.load(ctx));
+    }
+
+    /// Redraws the thumbnails of photos rendered again since they were made, leaving the rest alone.
+    pub fn refresh(&mut self, ctx: &mut Context) {
+        let roll = ctx.state().get_or_default::<MyCameraRoll>();
+        let changed = self.1.iter().enumerate()
+            .filter_map(|(i, button)| roll.get(button.3.id).filter(|p| p.edited != button.3.edited).map(|p| (i, p.clone())))
+            .collect::<Vec<_>>();
+        changed.into_iter().for_each(|(i, photo)| self.1[i].update(ctx, photo));
+    }
+
+    /// `selection` holds the photos picked so far while the roll is being selected from.
+    pub fn new(ctx: &mut Context, low_quality: bool, selection: Option<&[uuid::Uuid]>) -> Self {
+        let text_size = ctx.theme.fonts.size.md;
+        let my_images: Vec<Photo> = ctx.state().get_or_default::<MyCameraRoll>().visible()
+            .filter(|p| !low_quality || p.is_low_quality()).cloned().collect();
+        let help = match low_quality {
+            true => "No low-quality photos to review.",
+            false => "Your camera roll is empty.\nTake a photo to get started."
+        };
+        let help_text = my_images.is_empty().then_some(ExpandableText::new(
+            ctx, help, TextStyle::Primary, text_size, Align::Center, None
+        ));
+
+        let sections = ctx.state().get_or_default::<RollView>().sections(my_images);
+        let titles = sections.iter().map(|s| s.title.clone()).collect::<Vec<_>>();
+        let layout = TimelineLayout::default();
+        layout.0.lock().unwrap().counts = sections.iter().map(|s| s.photos.len()).collect();
+
+        let my_photos = sections.into_iter().flat_map(|s| s.photos).map(|photo| {
+            let selected = selection.map(|ids| ids.contains(&photo.id));
+            let mut button = ImageButton::unloaded(photo);
+            if let Some(selected) = selected {
+                button.select(ctx, true, selected);
+            }
+            button
+        }).collect();
+        let headers = titles.iter().map(|title| SectionHeader::new(ctx, title, 0.0)).collect();
+        let first = titles.first().cloned().unwrap_or_default();
+        let pinned = SectionHeader::new(ctx, &first, 0.0);
+        let track = RoundedRectangle::new(0.0, 0.0, ctx.theme.colors.shades.transparent);
+        let knob = RoundedRectangle::new(0.0, 3.0, ctx.theme.colors.text.secondary);
+        let label = SectionHeader::new(ctx, &first, 16.0);
+
+        PhotoWrap(layout, my_photos, headers, pinned, track, knob, label, help_text, titles, 0)
+    }
+}
+
+/// A section title in the library, on a bar so it stays readable pinned over photos.
+#[derive(Debug, Component)]
+pub struct SectionHeader(Stack, RoundedRectangle, Bin<Stack, Text>);
+impl OnEvent for SectionHeader {}
+
+impl SectionHeader {
+    pub fn new(ctx: &mut Context, title: &str, radius: f32) -> Self {
+        let font_size = ctx.theme.fonts.size.h5;
+        let text = Text::new(ctx, title, TextStyle::Heading, font_size, Align::Left);
+        let layout = Stack(Offset::Start, Offset::Center, Size::Fit, Size::Fit, Padding(16.0, 0.0, 16.0, 0.0));
+        SectionHeader(Stack::fill(), RoundedRectangle::new(0.0, radius, ctx.theme.colors.background.primary), Bin(layout, text))
+    }
+
+    pub fn set(&mut self, title: &str) {
+        self.2.inner().text().spans[0].text = title.to_string();
+    }
+}
+
+/// A thumbnail that opens its photo, or while selecting, picks it and shows a ring.
+#[derive(Debug, Component)]
+pub struct ImageButton(Stack, Option<ExpandableImage>, Option<RoundedRectangle>, #[skip] Photo, #[skip] bool);
+impl OnEvent for ImageButton {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent{state: MouseState::Pressed, position: Some(_)}) = event.downcast_ref::<MouseEvent>() {
+            match (self.4, self.3.is_document()) {
+                // Edits can't be pasted onto documents, so they can't be picked.
+                (true, true) => {},
+                (true, false) => {
+                    ctx.hardware.haptic();
+                    ctx.trigger_event(RollSelectionEvent::Toggle(self.3.id));
+                },
+                (false, _) => {
+                    ctx.hardware.haptic();
+                    ctx.trigger_event(SelectImageEvent(self.3.clone()));
+                    ctx.trigger_event(NavigateEvent(1));
+                }
+            }
+        } else if let Some(selection) = event.downcast_ref::<RollSelectionEvent>() {
+            match selection {
+                RollSelectionEvent::Begin => self.select(ctx, true, false),
+                RollSelectionEvent::Toggle(id) if *id == self.3.id => self.select(ctx, true, self.2.is_none()),
+                RollSelectionEvent::All => self.select(ctx, true, !self.3.is_document()),
+                RollSelectionEvent::End => self.select(ctx, false, false),
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+impl ImageButton {
+    const RING: f32 = 3.0;
+
+    pub fn new(ctx: &mut Context, photo: Photo) -> Self {
+        let mut button = Self::unloaded(photo);
+        button.load(ctx);
+        button
+    }
+
+    /// A button that decodes its thumbnail on the first `load`.
+    pub fn unloaded(photo: Photo) -> Self {
+        ImageButton(
+            Stack(Offset::Center, Offset::Center, Size::Static(64.0), Size::Static(64.0), Padding::default()), 
+            None, None, photo, false
+        )
+    }
+
+    pub fn load(&mut self, ctx: &mut Context) {
+        if self.1.is_none() {
+            self.1 = Some(ExpandableImage::new(EncodedImage::decode(ctx, self.3.shown()), None));
+        }
+    }
+
+    pub fn select(&mut self, ctx: &mut Context, selecting: bool, selected: bool) {
+        self.4 = selecting;
+        self.2 = selected.then(|| RoundedRectangle::new(Self::RING, 4.0, ctx.theme.colors.brand.primary));
+    }
+
+    fn update(&mut self, ctx: &mut Context, photo: Photo) {
+        if let Some(image) = &mut self.1 {
+            image.image().image = EncodedImage::decode(ctx, photo.shown());
+        }
+        self.3 = photo;
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct AlbacoreCamera(Stack, ExpandableImage, #[skip] Option<Camera>, #[skip] FrameRing<Captured>, #[skip] Option<Instant>, #[skip] Option<Box<dyn FrameSource + Send>>, #[skip] ModeState, #[skip] AutoController, #[skip] Option<Instant>, #[skip] Worker<Finishing, Vec<Photo>>);
+
+impl AlbacoreCamera {
+    const HIGHLIGHT: Rgba<u8> = Rgba([19, 30, 255, 230]);
+    const RECORDING: Rgba<u8> = Rgba([235, 52, 52, 230]);
+    const COUNTDOWN: Rgba<u8> = Rgba([255, 255, 255, 220]);
+    const FLASH: Duration = Duration::from_millis(200);
+    const THUMBNAIL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(ctx: &mut Context) -> Self {
+        let blank = ctx.theme.brand.illustrations.get("blank").unwrap();
+        let camera = Camera::new_custom().ok();
+        let stills = camera.is_none().then(frames::fallback_source).flatten();
+        let mode = *ctx.state().get_or_default::<CaptureMode>();
+        
+        AlbacoreCamera(
+            Stack(Offset::Center,Offset::Center,Size::fill(),Size::fill(),Padding::default()),
+            ExpandableImage::new(blank, None), camera, FrameRing::new(RING_CAPACITY), None, stills, ModeState::new(ctx, mode),
+            AutoController::default(), None, Worker::new(|finish: Finishing| finish())
+        )
+    }
+
+    pub fn camera(&mut self) -> &mut Option<Camera> {&mut self.2}
+    pub fn auto(&mut self) -> &mut AutoController {&mut self.7}
+
+    // Runs the current mode's per-frame work and returns what the viewfinder should show.
+    fn preview(&mut self, ctx: &mut Context, frame: RgbaImage) -> RgbaImage {
+        match &mut self.6 {
+            ModeState::Photo => frame,
+            ModeState::Scan(scanner, codes) => {
+                if let Some(found) = scanner.poll().pop() {
+                    let texts = |c: &[ScannedCode]| c.iter().map(|c| c.text.clone()).collect::<Vec<_>>();
+                    if !found.is_empty() && texts(&found) != texts(codes) {
+                        ctx.hardware.haptic();
+                        ctx.trigger_event(CodesScannedEvent(found.clone()));
+                    }
+                    *codes = found;
+                }
+                scanner.offer(|| frame.clone());
+                let mut preview = frame;
+                codes.iter().for_each(|code| draw::rect(&mut preview, code.bounds, 6.0, Self::HIGHLIGHT));
+                preview
+            },
+            ModeState::Document(detector, quad) => {
+                if let Some(found) = detector.poll().pop() {
+                    *quad = found;
+                }
+                detector.offer(|| frame.clone());
+                let mut preview = frame;
+                if let Some(quad) = quad {
+                    draw::polygon(&mut preview, &quad.0, 6.0, Self::HIGHLIGHT);
+                    quad.0.iter().for_each(|(x, y)| draw::dot(&mut preview, *x, *y, 12.0, Self::HIGHLIGHT));
+                }
+                preview
+            },
+            ModeState::LongExposure(exposure) => {
+                let Some((stacker, started)) = exposure else { return frame };
+                stacker.push(&frame);
+                let settings = *ctx.state().get_or_default::<LongExposure>();
+                let mut preview = stacker.preview().cloned().unwrap_or_else(|| preview_size(&frame));
+                let (w, h) = (preview.width() as f32, preview.height() as f32);
+                let progress = (started.elapsed().as_secs_f32() / settings.duration).min(1.0);
+                draw::fill_rect(&mut preview, (0.0, h - 12.0, w * progress, 12.0), Self::HIGHLIGHT);
+                if progress >= 1.0 {
+                    self.finish_exposure(ctx);
+                }
+                preview
+            },
+            ModeState::PhotoBooth(session) => {
+                let Some(session) = session else { return frame };
+                let settings = ctx.state().get_or_default::<PhotoBooth>().clone();
+                if Instant::now() >= session.next {
+                    session.shots.push(frame.clone());
+                    session.next = Instant::now() + settings.interval();
+                    session.flash = Some(Instant::now());
+                    ctx.hardware.haptic();
+                }
+                let mut preview = frame;
+                let (w, h) = (preview.width() as f32, preview.height() as f32);
+                let progress = format!("{}/{}", session.shots.len(), settings.shots);
+                draw::text(&mut preview, &progress, (32.0, 32.0), 6.0, Self::COUNTDOWN);
+                match session.flash.map(|f| f.elapsed()).filter(|e| *e < Self::FLASH) {
+                    Some(elapsed) => {
+                        let alpha = 255.0 * (1.0 - elapsed.as_secs_f32() / Self::FLASH.as_secs_f32());
+                        draw::fill_rect(&mut preview, (0.0, 0.0, w, h), Rgba([255, 255, 255, alpha as u8]));
+                    },
+                    None if session.shots.len() < settings.shots => {
+                        let count = session.remaining().to_string();
+                        let scale = h / 4.0 / draw::text_height(1.0);
+                        let origin = ((w - draw::text_width(&count, scale)) / 2.0, (h - draw::text_height(scale)) / 2.0);
+                        draw::text(&mut preview, &count, origin, scale, Self::COUNTDOWN);
+                    },
+                    None => {}
+                }
+                if session.shots.len() >= settings.shots {
+                    self.finish_booth(ctx);
+                }
+                preview
+            },
+            ModeState::ChromaKey(backdrop) => {
+                let settings = ctx.state().get_or_default::<ChromaKey>().clone();
+                let (w, h) = frame.dimensions();
+                let width = chroma::PREVIEW_WIDTH.min(w);
+                let size = (width, ((h as f32 / w as f32) * width as f32).round().max(1.0) as u32);
+                let small = imageops::resize(&frame, size.0, size.1, FilterType::Nearest);
+                if backdrop.as_ref().map(|(id, b)| (*id, b.dimensions())) != settings.background.map(|id| (id, size)) {
+                    let roll = ctx.state().get_or_default::<MyCameraRoll>();
+                    *backdrop = settings.background.and_then(|id| roll.get(id)).map(|photo| {
+                        (photo.id, chroma::cover(&EncodedImage::decode_rgba(photo.shown()), size))
+                    });
+                }
+                let shown = match backdrop {
+                    Some((_, background)) => Backdrop::Image(background),
+                    None => Backdrop::Checkerboard
+                };
+                chroma::composite(&small, shown, &settings)
+            },
+            ModeState::StopMotion(layers) => {
+                let mut preview = frame;
+                stopmotion::onion_skin(&mut preview, layers);
+                preview
+            },
+            ModeState::Motion(monitor, armed, drag) => {
+                if *armed {
+                    monitor.push(&frame);
+                }
+                while let Some((photo, record)) = monitor.poll() {
+                    let mut album = ctx.state().get_or_default::<MotionAlbum>().clone();
+                    album.add(photo);
+                    ctx.state().set(album);
+                    let mut log = ctx.state().get_or_default::<MotionLog>().clone();
+                    log.record(record);
+                    ctx.state().set(log);
+                }
+
+                let mut preview = frame;
+                let (w, h) = (preview.width() as f32, preview.height() as f32);
+                let pixels = |(x, y, zw, zh): Zone| (x * w, y * h, zw * w, zh * h);
+                monitor.settings().zones.iter().for_each(|zone| draw::rect(&mut preview, pixels(*zone), 4.0, Self::HIGHLIGHT));
+                if let Some((start, end)) = drag {
+                    draw::rect(&mut preview, pixels(zone(*start, *end)), 4.0, Self::HIGHLIGHT);
+                }
+                if *armed {
+                    let color = if monitor.is_recording() {Self::RECORDING} else {Self::HIGHLIGHT};
+                    draw::rect(&mut preview, (0.0, 0.0, w, h), 16.0, color);
+                    draw::dot(&mut preview, 40.0, 40.0, 14.0, color);
+                }
+                preview
+            }
+        }
+    }
+
+    fn finish_exposure(&mut self, ctx: &mut Context) {
+        let ModeState::LongExposure(exposure) = &mut self.6 else { return };
+        let Some((stacker, started)) = exposure.take() else { return };
+        let settings = *ctx.state().get_or_default::<LongExposure>();
+        let duration = started.elapsed().as_secs_f32().min(settings.duration);
+        let stack = stacker.finish();
+        let grade = Grade::new(ctx);
+        let session = self.session(ctx);
+        self.9.submit(Box::new(move || {
+            let stack = stack.lock().unwrap();
+            let record = ExposureRecord { duration, mode: settings.mode, frames: stack.count() };
+            let Some(result) = stack.result() else { return Vec::new() };
+            let result = grade.apply(result);
+            let (width, height) = result.dimensions();
+            let mut photo = Photo::new(EncodedImage::encode_rgba(result), (width as f32, height as f32));
+            photo.capture = session;
+            photo.exposure = Some(record);
+            vec![photo]
+        }));
+    }
+
+    // Composes the shots and saves them to the roll, shots first so the composite lands last.
+    fn finish_booth(&mut self, ctx: &mut Context) {
+        let ModeState::PhotoBooth(session) = &mut self.6 else { return };
+        let Some(session) = session.take() else { return };
+        let settings = ctx.state().get_or_default::<PhotoBooth>().clone();
+        let caption = settings.caption_line(chrono::Local::now());
+        let grade = Grade::new(ctx);
+        let capture = self.session(ctx);
+        self.9.submit(Box::new(move || {
+            let shots = session.shots.into_iter().map(|shot| grade.apply(shot)).collect::<Vec<_>>();
+            let Some(card) = booth::compose(&shots, settings.layout, caption.as_deref()) else { return Vec::new() };
+            let (width, height) = card.dimensions();
+            let mut strip = Photo::new(EncodedImage::encode_rgba(card), (width as f32, height as f32));
+            strip.capture = capture;
+            let mut photos = shots.into_iter().map(|shot| {
+                let (width, height) = shot.dimensions();
+                let mut photo = Photo::new(EncodedImage::encode_rgba(shot), (width as f32, height as f32));
+                photo.strip = Some(strip.id);
+                photo.capture = strip.capture.clone();
+                photo
+            }).collect::<Vec<_>>();
+            strip.shots = photos.iter().map(|s| s.id).collect();
+            photos.push(strip);
+            photos
+        }));
+    }
+
+    // The settings a capture is taken with. While comparing these are the held ones, applied in software.
+    fn session(&mut self, ctx: &mut Context) -> Option<CameraSession> {
+        let live = ctx.state().get_or_default::<LiveComparison>().clone();
+        let settings = match live.comparison.is_some() {
+            true => live.settings,
+            false => self.2.as_ref()?.get_settings().ok()?
+        };
+        let mode = *ctx.state().get_or_default::<CaptureMode>();
+        Some(CameraSession::capture(&settings, mode, ctx.state().get_or_default::<LivePhotos>().0))
+    }
+
+    /// Maps a position on the viewfinder to a fraction of the frame.
+    fn normalize(&mut self, (x, y): (f32, f32)) -> (f32, f32) {
+        match self.1.image().shape {
+            ShapeType::Rectangle(_, (w, h)) if w > 0.0 && h > 0.0 => ((x / w).clamp(0.0, 1.0), (y / h).clamp(0.0, 1.0)),
+            _ => (0.0, 0.0)
+        }
+    }
+
+    fn capture(&mut self, ctx: &mut Context, pressed: Instant) {
+        let finish: Finish<Captured> = match &self.6 {
+            // Re-detect on the chosen frame since the live outline may lag a few frames behind.
+            ModeState::Document(_, live) => {
+                let live = *live;
+                Box::new(move |frame, _| {
+                    let quad = document::detect(&frame).or(live).unwrap_or_else(|| Quad::full(&frame));
+                    Captured::Page(EncodedImage::encode_rgba(document::warp(&frame, &quad)))
+                })
+            },
+            ModeState::StopMotion(_) => Box::new(|frame, _| Captured::Frame(frame)),
+            _ => {
+                // Green screen captures are keyed again at full resolution rather than saving the preview.
+                // With no background chosen the subject is saved cut out, on a transparent background.
+                let key = matches!(self.6, ModeState::ChromaKey(_)).then(|| {
+                    let settings = ctx.state().get_or_default::<ChromaKey>().clone();
+                    let background = settings.background.and_then(|id| ctx.state().get_or_default::<MyCameraRoll>().get(id).map(|b| b.shown().clone()));
+                    (settings, background)
+                });
+                let grade = Grade::new(ctx);
+                let session = self.session(ctx);
+                let live = ctx.state().get_or_default::<LivePhotos>().0;
+                Box::new(move |frame, clip| {
+                    let key = key.map(|(settings, background)| (settings, background.map(|b| EncodedImage::decode_rgba(&b))));
+                    let keyed = |image: &RgbaImage| match &key {
+                        Some((settings, Some(background))) => chroma::composite(image, Backdrop::Image(&chroma::cover(background, image.dimensions())), settings),
+                        Some((settings, None)) => chroma::composite(image, Backdrop::Transparent, settings),
+                        None => image.clone()
+                    };
+                    let rgba = grade.apply(keyed(&frame));
+                    let (width, height) = rgba.dimensions();
+                    let mut photo = Photo::new(EncodedImage::encode_rgba(rgba), (width as f32, height as f32));
+                    photo.capture = session;
+                    if live {
+                        photo.set_live(clip.iter().map(|f| grade.apply(keyed(f))).collect());
+                    }
+                    Captured::Photo(Box::new(photo))
+                })
+            }
+        };
+        self.3.capture(pressed, finish);
+    }
+
+    fn captured(&mut self, ctx: &mut Context, captured: Captured) {
+        match captured {
+            Captured::Photo(photo) => {
+                let mut guard = ctx.get::<LensPlugin>();
+                let plugin = guard.get().0;
+                plugin.request(LensRequest::SavePhoto(photo));
+            },
+            Captured::Page(page) => {
+                let mut draft = ctx.state().get_or_default::<DocumentDraft>().clone();
+                draft.pages.push(page);
+                ctx.state().set(draft);
+                ctx.trigger_event(DocumentPageAddedEvent);
+            },
+            Captured::Frame(frame) => {
+                let ModeState::StopMotion(layers) = &mut self.6 else { return };
+                let mut stop_motion = ctx.state().get_or_default::<StopMotion>().clone();
+                stop_motion.project().add(&frame);
+                stop_motion.save();
+                *layers = stop_motion.project().onion_frames();
+                ctx.state().set(stop_motion);
+                ctx.hardware.haptic();
+            }
+        }
+    }
+}
+
+/// Makes the photos for a long exposure or photo booth session once it's over.
+type Finishing = Box<dyn FnOnce() -> Vec<Photo> + Send>;
+
+/// What the frame ring hands back once a capture has been picked and made.
+#[derive(Debug)]
+enum Captured {
+    Photo(Box<Photo>),
+    /// An encoded document page.
+    Page(String),
+    /// A stop motion frame.
+    Frame(RgbaImage),
+}
+
+impl OnEvent for AlbacoreCamera {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(TickEvent) = event.downcast_ref::<TickEvent>() {
+            let frame = match (&mut self.2, &mut self.5) {
+                (Some(camera), _) => camera.next_frame(),
+                (None, Some(stills)) => stills.next_frame(),
+                (None, None) => None
+            };
+            if let Some(raw_frame) = frame {
+                if let Some(camera) = &mut self.2 {
+                    self.7.update(camera, &raw_frame, *ctx.state().get_or_default::<AutoAdjust>());
+                }
+                self.3.push(Instant::now(), raw_frame.clone());
+                if self.8.is_none_or(|t| t.elapsed() >= Self::THUMBNAIL_INTERVAL) {
+                    self.8 = Some(Instant::now());
+                    let size = lut::THUMBNAIL_SIZE;
+                    let thumbnail = imageops::resize(&cover_square(&raw_frame), size, size, FilterType::Nearest);
+                    ctx.state().set(LiveThumbnail(Some((Instant::now(), Arc::new(thumbnail)))));
+                }
+                let preview = self.preview(ctx, raw_frame);
+                // Graded after the mode has drawn on it, at preview size to keep the viewfinder smooth.
+                let filters = ctx.state().get_or_default::<Filters>().clone();
+                let tone = ctx.state().get_or_default::<ToneCurves>().clone();
+                let live = ctx.state().get_or_default::<LiveComparison>().clone();
+                let preview = match (live.comparison, filters.selected.is_some() || !tone.is_identity()) {
+                    // The camera is at its defaults, so the frame is the original and the settings are applied here.
+                    (Some(comparison), _) => {
+                        let original = preview_size(&preview);
+                        let adjusted = tone.apply(edit::process(original.clone(), &live.settings));
+                        let adjusted = ctx.state().get_or_default::<Luts>().grade(&filters, &adjusted).unwrap_or(adjusted);
+                        comparison.compose(&original, &adjusted)
+                    },
+                    (None, true) => {
+                        let small = tone.apply(preview_size(&preview));
+                        ctx.state().get_or_default::<Luts>().grade(&filters, &small).unwrap_or(small)
+                    },
+                    (None, false) => preview
+                };
+                let image = ctx.assets.add_image(preview);
+                self.1.image().image = image;
+            }
+            // A capture with nothing to pick from still frees the shutter.
+            for captured in self.3.poll() {
+                self.4 = None;
+                if let Some(captured) = captured {
+                    self.captured(ctx, captured);
+                }
+            }
+            for photos in self.9.poll() {
+                let mut guard = ctx.get::<LensPlugin>();
+                let plugin = guard.get().0;
+                photos.into_iter().for_each(|photo| plugin.request(LensRequest::SavePhoto(Box::new(photo))));
+            }
+        } else if let Some(TakePhotoEvent) = event.downcast_ref::<TakePhotoEvent>() {
+            // In Motion mode the shutter arms and disarms monitoring instead.
+            if let ModeState::Motion(monitor, armed, _) = &mut self.6 {
+                *armed = !*armed;
+                if !*armed { monitor.finish(); }
+            } else if let ModeState::LongExposure(exposure) = &mut self.6 {
+                // A second press ends the exposure early.
+                match exposure.is_some() {
+                    true => self.finish_exposure(ctx),
+                    false => {
+                        let mode = ctx.state().get_or_default::<LongExposure>().mode;
+                        *exposure = Some((Stacker::new(mode, lut::PREVIEW_WIDTH), Instant::now()));
+                    }
+                }
+            } else if let ModeState::PhotoBooth(session) = &mut self.6 {
+                // A second press cancels the sequence.
+                *session = match session.is_some() {
+                    true => None,
+                    false => Some(BoothSession::new(ctx.state().get_or_default::<PhotoBooth>())),
+                };
+            // A press while the last capture is still being made is ignored, since they'd pick from the same frames.
+            } else if self.4.is_none() && self.3.latest().is_some() {
+                let pressed = Instant::now();
+                self.4 = Some(pressed);
+                self.capture(ctx, pressed);
+            }
+        } else if let Some(SetCaptureModeEvent(mode)) = event.downcast_ref::<SetCaptureModeEvent>() {
+            if let ModeState::Motion(monitor, ..) = &mut self.6 {
+                monitor.finish();
+            }
+            self.6 = ModeState::new(ctx, *mode);
+        } else if event.downcast_ref::<MotionSettingsChangedEvent>().is_some() {
+            if let ModeState::Motion(monitor, ..) = &mut self.6 {
+                monitor.set_settings(ctx.state().get_or_default::<MotionSettings>().clone());
+            }
+        } else if let Some(MouseEvent { state, position }) = event.downcast_ref::<MouseEvent>() {
+            let point = position.map(|p| self.normalize(p));
+            // While split, dragging across the viewfinder moves the split and nothing else.
+            let mut live = ctx.state().get_or_default::<LiveComparison>().clone();
+            if let Some(comparison) = live.comparison.as_mut().filter(|c| c.mode == CompareMode::Split) {
+                match (state, point) {
+                    (MouseState::Pressed, Some(p)) => {
+                        live.dragging = true;
+                        comparison.split = p.0;
+                    },
+                    (MouseState::Moved | MouseState::Scroll(..), Some(p)) if live.dragging => comparison.split = p.0,
+                    (MouseState::Released | MouseState::LongPressReleased, _) => live.dragging = false,
+                    _ => return true
+                }
+                ctx.state().set(live);
+                return true;
+            }
+            // Zones are drawn by dragging across the viewfinder while monitoring is off.
+            // In green screen mode a tap picks the key color instead.
+            if let (ModeState::ChromaKey(_), MouseState::Pressed, Some(p)) = (&self.6, state, point) {
+                if let Some(frame) = self.3.latest() {
+                    let mut settings = ctx.state().get_or_default::<ChromaKey>().clone();
+                    settings.key = chroma::sample(&frame, p);
+                    settings.save();
+                    ctx.state().set(settings);
+                    ctx.hardware.haptic();
+                }
+            }
+            if let ModeState::Motion(monitor, false, drag) = &mut self.6 {
+                match (state, point) {
+                    (MouseState::Pressed, Some(p)) => *drag = Some((p, p)),
+                    (MouseState::Moved | MouseState::Scroll(..), Some(p)) => if let Some((_, end)) = drag { *end = p },
+                    (MouseState::Released | MouseState::LongPressReleased, _) => if let Some((start, end)) = drag.take() {
+                        let zone = zone(start, end);
+                        if zone.2 > MIN_ZONE && zone.3 > MIN_ZONE {
+                            let mut settings = monitor.settings().clone();
+                            settings.zones.push(zone);
+                            settings.save();
+                            monitor.set_settings(settings.clone());
+                            ctx.state().set(settings);
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+}
+
+const MIN_ZONE: f32 = 0.05;
+
+// `image` scaled down to the width the viewfinder grades at.
+fn preview_size(image: &RgbaImage) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    let width = lut::PREVIEW_WIDTH.min(w);
+    imageops::resize(image, width, ((h as f32 / w as f32) * width as f32).round().max(1.0) as u32, FilterType::Nearest)
+}
+
+// The largest centered square of `image`.
+fn cover_square(image: &RgbaImage) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    let side = w.min(h);
+    imageops::crop_imm(image, (w - side) / 2, (h - side) / 2, side, side).to_image()
+}
+
+fn zone(start: (f32, f32), end: (f32, f32)) -> Zone {
+    (start.0.min(end.0), start.1.min(end.1), (start.0 - end.0).abs(), (start.1 - end.1).abs())
+}
+
+/// The photo being viewed. Pinching, a double tap, the scroll wheel or + and - zoom in, dragging
+/// pans or swipes to the photos either side, and press and hold plays back the live clip, if any.
+#[derive(Debug, Component)]
+pub struct PhotoViewer(
+    Stack, ExpandableImage, #[skip] Vec<resources::Image>, #[skip] Option<(usize, Instant)>, #[skip] resources::Image, #[skip] Arc<RgbaImage>,
+    #[skip] Viewer, #[skip] [Option<Photo>; 2], #[skip] Preloader, #[skip] Option<(RgbaImage, [Option<RgbaImage>; 2])>
+);
+
+impl PhotoViewer {
+    /// `neighbours` are the photos before and after this one.
+    pub fn new(ctx: &mut Context, photo: &Photo, neighbours: [Option<Photo>; 2]) -> Self {
+        let mut decoded = ctx.state().get_or_default::<DecodedPhotos>().clone();
+        let full = decoded.get(photo).unwrap_or_else(|| Arc::new(EncodedImage::decode_rgba(photo.shown())));
+        decoded.insert(photo, full.clone());
+        let mut preloader = viewer::preloader();
+        neighbours.iter().flatten().filter(|p| decoded.get(p).is_none()).for_each(|p| preloader.submit((p.id, p.shown().clone())));
+        ctx.state().set(decoded);
+
+        let size = (full.width() as f32, full.height() as f32);
+        let still = ctx.assets.add_image((*full).clone());
+        let frames = photo.live_frames().into_iter().map(|f| ctx.assets.add_image(f)).collect();
+        let viewer = Viewer::new(1.0, (neighbours[0].is_some(), neighbours[1].is_some()));
+        PhotoViewer(Stack::default(), ExpandableImage::new(still.clone(), Some(size)), frames, None, still, full, viewer, neighbours, preloader, None)
+    }
+
+    // The size the photo is laid out at, once it has been.
+    fn view(&mut self) -> Option<(f32, f32)> {
+        match self.1.image().shape {
+            ShapeType::Rectangle(_, (w, h)) if w > 0.0 && h > 0.0 => Some((w, h)),
+            _ => None
+        }
+    }
+
+    fn normalize(&mut self, (x, y): (f32, f32)) -> (f32, f32) {
+        self.view().map_or((0.0, 0.0), |(w, h)| ((x / w).clamp(0.0, 1.0), (y / h).clamp(0.0, 1.0)))
+    }
+
+    // Draws the view as the gestures have left it, or the plain photo when it's at rest.
+    fn show(&mut self, ctx: &mut Context) {
+        if self.3.is_some() { return; }
+        let Some(view) = self.view() else { return };
+        if self.6.is_resting() {
+            self.9 = None;
+            self.1.image().image = self.4.clone();
+            return;
+        }
+        let size = viewer::render_size(view);
+        let image = match self.6.viewport.is_zoomed() {
+            true => viewer::render_view(&self.5, &self.6.viewport, size),
+            false => {
+                if self.9.as_ref().is_none_or(|(current, _)| current.dimensions() != size) {
+                    let mut decoded = ctx.state().get_or_default::<DecodedPhotos>().clone();
+                    let neighbours = self.7.each_ref().map(|p| p.as_ref().and_then(|p| decoded.get(p)).map(|image| viewer::slide(&image, size)));
+                    ctx.state().set(decoded);
+                    self.9 = Some((viewer::slide(&self.5, size), neighbours));
+                }
+                let (current, [previous, next]) = self.9.as_ref().unwrap();
+                viewer::render_swipe(current, previous.as_ref(), next.as_ref(), self.6.swipe)
+            }
+        };
+        self.1.image().image = ctx.assets.add_image(image);
+    }
+
+    fn step(&mut self, ctx: &mut Context, step: isize) {
+        let open = if step < 0 {self.6.edges.0} else {self.6.edges.1};
+        if open { ctx.trigger_event(ShowNeighbourEvent(step)); }
+    }
+}
+
+impl OnEvent for PhotoViewer {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        let now = Instant::now();
+        if let Some(MouseEvent { state, position }) = event.downcast_ref::<MouseEvent>() {
+            let point = position.map(|p| self.normalize(p));
+            let changed = match (state, point) {
+                (MouseState::Pressed, Some(p)) => {
+                    self.6.press(p, now);
+                    if !self.2.is_empty() && !self.6.viewport.is_zoomed() && !self.6.is_pinching() {
+                        self.3 = Some((0, now));
+                    }
+                    false
+                },
+                (MouseState::Moved | MouseState::Scroll(..), Some(p)) if self.6.is_pressed() => self.6.drag(p, matches!(state, MouseState::Scroll(..)), now),
+                (MouseState::Scroll(_, amount), Some(p)) => self.6.wheel(*amount, p, now),
+                (MouseState::Released | MouseState::LongPressReleased, _) => {
+                    if let Some(step) = self.6.release(now) { self.step(ctx, step); }
+                    // Playback ends with the press, so the view goes back to the photo.
+                    self.3.take().is_some() || !self.6.is_resting()
+                },
+                _ => false
+            };
+            if changed {
+                self.3 = None;
+                self.show(ctx);
+            }
+        } else if let Some(KeyboardEvent { key, state: KeyboardState::Pressed }) = event.downcast_ref::<KeyboardEvent>() {
+            match key {
+                Key::Named(NamedKey::ArrowLeft) => self.step(ctx, -1),
+                Key::Named(NamedKey::ArrowRight) => self.step(ctx, 1),
+                Key::Character(c) if ["+", "="].contains(&c.as_str()) => self.6.key_zoom(1),
+                Key::Character(c) if c == "-" => self.6.key_zoom(-1),
+                Key::Character(c) if c == "0" => self.6.key_zoom(0),
+                _ => return true
+            }
+            self.show(ctx);
+        } else if event.downcast_ref::<TickEvent>().is_some() {
+            let loaded = self.8.poll();
+            if !loaded.is_empty() {
+                let mut decoded = ctx.state().get_or_default::<DecodedPhotos>().clone();
+                for (id, image) in loaded {
+                    if let Some(photo) = self.7.iter().flatten().find(|p| p.id == id) {
+                        decoded.insert(photo, Arc::new(image));
+                    }
+                }
+                ctx.state().set(decoded);
+                self.9 = None;
+            }
+            if let Some(view) = self.view() {
+                self.6.actual = self.5.width() as f32 / viewer::render_size(view).0 as f32;
+            }
+            if let Some((i, shown)) = &mut self.3 {
+                if shown.elapsed() >= Duration::from_millis(33) {
+                    *i = (*i + 1) % self.2.len();
+                    *shown = Instant::now();
+                }
+                self.1.image().image = self.2[*i].clone();
+            } else if self.6.tick(now) {
+                self.show(ctx);
+            }
+        }
+        true
+    }
+}
+
+/// Sends a drag across the canvas from one point to another, as fractions of it, and whether it has ended.
+pub type DragAction = fn(&mut Context, (f32, f32), (f32, f32), bool);
+
+/// The photo being edited. Dragging across it frames a crop while the crop tool is open, or draws in markup.
+#[derive(Debug, Component)]
+pub struct EditCanvas(Stack, ExpandableImage, #[skip] (u32, u32), #[skip] Option<((f32, f32), (f32, f32))>, #[skip] DragAction);
+
+impl EditCanvas {
+    pub fn new(ctx: &mut Context, image: RgbaImage) -> Self {
+        Self::with_drag(ctx, image, |ctx, start, end, done| ctx.trigger_event(EditPhotoEvent::Drag(start, end, done)))
+    }
+
+    pub fn with_drag(ctx: &mut Context, image: RgbaImage, on_drag: DragAction) -> Self {
+        let size = image.dimensions();
+        let image = ctx.assets.add_image(image);
+        EditCanvas(Stack::default(), ExpandableImage::new(image, Some((size.0 as f32, size.1 as f32))), size, None, on_drag)
+    }
+
+    /// Swaps in a new render, rebuilding when its shape has changed.
+    pub fn set_image(&mut self, ctx: &mut Context, image: RgbaImage) {
+        match image.dimensions() == self.2 {
+            true => self.1.image().image = ctx.assets.add_image(image),
+            false => *self = EditCanvas::with_drag(ctx, image, self.4)
+        }
+    }
+
+    fn normalize(&mut self, (x, y): (f32, f32)) -> (f32, f32) {
+        match self.1.image().shape {
+            ShapeType::Rectangle(_, (w, h)) if w > 0.0 && h > 0.0 => ((x / w).clamp(0.0, 1.0), (y / h).clamp(0.0, 1.0)),
+            _ => (0.0, 0.0)
+        }
+    }
+}
+
+impl OnEvent for EditCanvas {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent { state, position }) = event.downcast_ref::<MouseEvent>() {
+            let point = position.map(|p| self.normalize(p));
+            match (state, point, &mut self.3) {
+                (MouseState::Pressed, Some(p), drag) => *drag = Some((p, p)),
+                (MouseState::Moved | MouseState::Scroll(..), Some(p), Some((start, end))) => {
+                    *end = p;
+                    (self.4)(ctx, *start, p, false);
+                },
+                (MouseState::Released | MouseState::LongPressReleased, _, drag) => if let Some((start, end)) = drag.take() {
+                    (self.4)(ctx, start, end, true);
+                },
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+/// A row of choices in the photo editor, each sending its event when tapped.
+#[derive(Debug, Component)]
+pub struct EditOptions(Row, Vec<Button>);
+impl OnEvent for EditOptions {}
+
+impl EditOptions {
+    pub fn new<E: Event + Clone + 'static>(ctx: &mut Context, options: Vec<(&str, bool, E)>) -> Self {
+        let buttons = options.into_iter().map(|(label, selected, event)| {
+            let state = if selected {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(label), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| ctx.trigger_event(event.clone()),
+                None,
+            )
+        }).collect();
+        EditOptions(Row::center(8.0), buttons)
+    }
+}
+
+/// One of the camera's adjustments, applied to the photo being edited.
+#[derive(Debug, Component)]
+pub struct AdjustmentSlider(Row, Slider, Text, #[skip] CameraSetting);
+
+impl AdjustmentSlider {
+    pub fn new(ctx: &mut Context, setting: CameraSetting, value: f32) -> Self {
+        let font_size = ctx.theme.fonts.size.md;
+        let slider = Slider::new(ctx, setting.slider_position(value), Some(setting.label()), None, slider_action(setting, |ctx, setting, value| {
+            ctx.trigger_event(EditPhotoEvent::Apply(EditOp::Adjust(setting, value)))
+        }));
+        let text = Text::new(ctx, &setting.format(value), TextStyle::Secondary, font_size, Align::Right);
+        AdjustmentSlider(Row::center(24.0), slider, text, setting)
+    }
+}
+
+impl OnEvent for AdjustmentSlider {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(EditPhotoEvent::Apply(EditOp::Adjust(setting, value))) = event.downcast_ref::<EditPhotoEvent>() {
+            if *setting == self.3 {
+                self.2.text().spans[0].text = setting.format(*value);
+            }
+        }
+        true
+    }
+}
+
+/// Fine rotation, in tenths of a degree, snapping to level.
+#[derive(Debug, Component)]
+pub struct StraightenSlider(Row, Slider, Text);
+
+impl StraightenSlider {
+    const SNAP: f32 = 0.5;
+
+    pub fn new(ctx: &mut Context, degrees: f32) -> Self {
+        let font_size = ctx.theme.fonts.size.md;
+        let start = (degrees + edit::MAX_STRAIGHTEN) / (2.0 * edit::MAX_STRAIGHTEN) * 100.0;
+        let slider = Slider::new(ctx, start, Some("Straighten"), None, |ctx: &mut Context, p: f32| {
+            let degrees = ((p / 100.0 * 2.0 - 1.0) * edit::MAX_STRAIGHTEN * 10.0).round() / 10.0;
+            let degrees = if degrees.abs() < Self::SNAP {0.0} else {degrees};
+            ctx.trigger_event(EditPhotoEvent::Apply(EditOp::Straighten(degrees)))
+        });
+        let text = Text::new(ctx, &format!("{degrees:+.1}°"), TextStyle::Secondary, font_size, Align::Right);
+        StraightenSlider(Row::center(24.0), slider, text)
+    }
+}
+
+impl OnEvent for StraightenSlider {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(EditPhotoEvent::Apply(EditOp::Straighten(degrees))) = event.downcast_ref::<EditPhotoEvent>() {
+            self.2.text().spans[0].text = format!("{degrees:+.1}°");
+        }
+        true
+    }
+}
+
+/// One setting of an effect in the stack.
+#[derive(Debug, Component)]
+pub struct EffectSlider(Row, Slider, Text, #[skip] usize, #[skip] Param, #[skip] usize);
+
+impl EffectSlider {
+    pub fn new(ctx: &mut Context, index: usize, effect: &Effect, param: usize) -> Self {
+        let spec = effect.kind.params()[param];
+        let value = effect.value(param);
+        let font_size = ctx.theme.fonts.size.md;
+        let slider = Slider::new(ctx, spec.position(value), Some(spec.label), None, move |ctx: &mut Context, p: f32| {
+            ctx.trigger_event(EffectEvent::Set(index, param, spec.value(p)))
+        });
+        let text = Text::new(ctx, &spec.format(value), TextStyle::Secondary, font_size, Align::Right);
+        EffectSlider(Row::center(24.0), slider, text, index, spec, param)
+    }
+}
+
+impl OnEvent for EffectSlider {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(EffectEvent::Set(index, param, value)) = event.downcast_ref::<EffectEvent>() {
+            if (*index, *param) == (self.3, self.5) {
+                self.2.text().spans[0].text = self.4.format(*value);
+            }
+        }
+        true
+    }
+}
+
+/// The edits made so far, in the order they apply, each with a button to drop it.
+#[derive(Debug, Component)]
+pub struct EditSteps(Column, Text, Vec<EditStep>);
+impl OnEvent for EditSteps {}
+
+impl EditSteps {
+    pub fn new(ctx: &mut Context, edits: &Edits) -> Self {
+        let font_size = ctx.theme.fonts.size.h5;
+        let title = match edits.is_empty() {
+            true => "No edits",
+            false => "Edits"
+        };
+        let title = Text::new(ctx, title, TextStyle::Heading, font_size, Align::Left);
+        let steps = edits.0.iter().enumerate().map(|(i, op)| EditStep::new(ctx, i, op)).collect();
+        EditSteps(Column::new(8.0, Offset::Start, Size::Fit, Padding::default()), title, steps)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct EditStep(Row, Text, IconButton);
+impl OnEvent for EditStep {}
+
+impl EditStep {
+    pub fn new(ctx: &mut Context, index: usize, op: &EditOp) -> Self {
+        let font_size = ctx.theme.fonts.size.md;
+        let label = Text::new(ctx, &op.label(), TextStyle::Primary, font_size, Align::Left);
+        let remove = IconButton::ghost(ctx, "close", Box::new(move |ctx: &mut Context| ctx.trigger_event(EditPhotoEvent::Remove(index))));
+        EditStep(Row::new(12.0, Offset::Center, Size::Fit, Padding::default()), label, remove)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct RetakeToast(Stack, ToastBubble, #[skip] Instant);
+
+impl RetakeToast {
+    const DURATION: Duration = Duration::from_secs(4);
+
+    pub fn new(ctx: &mut Context, photo: &Photo, issue: QualityIssue) -> Self {
+        let id = photo.id;
+        let retake = Button::secondary(ctx, None, "Retake", None, move |ctx: &mut Context| {
+            ctx.trigger_event(DeletePhotosEvent(vec![id]));
+            ctx.trigger_event(TakePhotoEvent);
+        }, None);
+        let content = ToastContent(Row::new(12.0, Offset::Center, Size::Fit, Padding(16.0, 12.0, 16.0, 12.0)), Alert::new(ctx, issue.prompt()), retake);
+        let color = ctx.theme.colors.background.secondary;
+        let width = Size::custom(|widths: Vec<(f32, f32)>| widths[1]);
+        let height = Size::custom(|heights: Vec<(f32, f32)>| heights[1]);
+        let bubble = ToastBubble(Stack(Offset::Center, Offset::Center, width, height, Padding::default()), RoundedRectangle::new(0.0, 16.0, color), content);
+        RetakeToast(Stack(Offset::Center, Offset::Start, Size::fill(), Size::fill(), Padding(16.0, 64.0, 16.0, 0.0)), bubble, Instant::now())
+    }
+
+    pub fn expired(&self) -> bool {
+        self.2.elapsed() >= Self::DURATION
+    }
+}
+
+impl OnEvent for RetakeToast {}
+
+#[derive(Debug, Component)]
+pub struct ToastBubble(Stack, RoundedRectangle, ToastContent);
+impl OnEvent for ToastBubble {}
+
+#[derive(Debug, Component)]
+pub struct ToastContent(Row, Alert, Button);
+impl OnEvent for ToastContent {}
+
+#[derive(Debug, Component)]
+pub struct ScanResultsBumper(Column, Vec<ScanResult>, ScanSheetActions);
+impl OnEvent for ScanResultsBumper {}
+
+impl ScanResultsBumper {
+    pub fn new(ctx: &mut Context, codes: &[ScannedCode]) -> Self {
+        let results = codes.iter().map(|code| ScanResult::new(ctx, code)).collect();
+        let history = Button::secondary(ctx, None, "History", None, |ctx: &mut Context| ctx.trigger_event(NavigateEvent(1)), None);
+        let done = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(CloseSheetEvent));
+        let actions = ScanSheetActions(Row::center(16.0), history, done);
+        ScanResultsBumper(Column::new(24.0, Offset::Center, Size::Fit, Padding::default()), results, actions)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct ScanSheetActions(Row, Button, Button);
+impl OnEvent for ScanSheetActions {}
+
+#[derive(Debug, Component)]
+pub struct ScanResult(Column, Text, ExpandableText, QuickActions);
+impl OnEvent for ScanResult {}
+
+impl ScanResult {
+    pub fn new(ctx: &mut Context, code: &ScannedCode) -> Self {
+        let font_size = ctx.theme.fonts.size;
+        let format = Text::new(ctx, &code.format, TextStyle::Secondary, font_size.sm, Align::Center);
+        let text = ExpandableText::new(ctx, &code.text, TextStyle::Heading, font_size.h5, Align::Center, Some(3));
+
+        let copied = code.text.clone();
+        let mut buttons = vec![Button::secondary(ctx, Some("copy"), "Copy", None, move |ctx: &mut Context| {
+            ctx.hardware.copy(copied.clone());
+        }, Some("Copied".to_string()))];
+        if code.is_link() {
+            let link = code.text.clone();
+            buttons.push(Button::secondary(ctx, Some("link"), "Open", None, move |ctx: &mut Context| {
+                open_link(ctx, &link);
+            }, None));
+        }
+        ScanResult(Column::new(8.0, Offset::Center, Size::Fit, Padding::default()), format, text, QuickActions::new(buttons))
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct DocumentBumper(Column, Text, ScanFilterOptions, DocumentActions);
+impl OnEvent for DocumentBumper {}
+
+impl DocumentBumper {
+    pub fn new(ctx: &mut Context) -> Self {
+        let draft = ctx.state().get_or_default::<DocumentDraft>().clone();
+        let count = draft.pages.len();
+        let font_size = ctx.theme.fonts.size.h5;
+        let label = format!("{} page{}", count, if count == 1 {""} else {"s"});
+        let title = Text::new(ctx, &label, TextStyle::Heading, font_size, Align::Center);
+
+        let discard = IconButton::ghost(ctx, "delete", Box::new(|ctx: &mut Context| ctx.trigger_event(DiscardDocumentEvent)));
+        let add = Button::secondary(ctx, Some("add"), "Add Page", None, |ctx: &mut Context| ctx.trigger_event(CloseSheetEvent), None);
+        let save = Button::primary(ctx, "Save", |ctx: &mut Context| ctx.trigger_event(SaveDocumentEvent));
+        let actions = DocumentActions(Row::center(16.0), discard, add, save);
+        DocumentBumper(Column::new(16.0, Offset::Center, Size::Fit, Padding::default()), title, ScanFilterOptions::new(ctx, draft.filter), actions)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct DocumentActions(Row, IconButton, Button, Button);
+impl OnEvent for DocumentActions {}
+
+#[derive(Debug, Component)]
+pub struct ScanFilterOptions(Row, Vec<Button>);
+
+impl ScanFilterOptions {
+    pub fn new(ctx: &mut Context, current: ScanFilter) -> Self {
+        let buttons = ScanFilter::ALL.into_iter().map(|filter| {
+            let state = if filter == current {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(filter.label()), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| {
+                    let mut draft = ctx.state().get_or_default::<DocumentDraft>().clone();
+                    draft.filter = filter;
+                    ctx.state().set(draft);
+                    ctx.trigger_event(SetScanFilterEvent(filter));
+                },
+                None,
+            )
+        }).collect();
+        ScanFilterOptions(Row::center(8.0), buttons)
+    }
+}
+
+impl OnEvent for ScanFilterOptions {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(SetScanFilterEvent(filter)) = event.downcast_ref::<SetScanFilterEvent>() {
+            self.1.iter_mut().zip(ScanFilter::ALL).for_each(|(button, f)| {
+                *button.status() = if f == *filter {ButtonState::Selected} else {ButtonState::Default};
+                button.color(ctx);
+            });
+        }
+        true
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct MotionBumper(Column, Text, Slider, MotionOptions, MotionActions);
+impl OnEvent for MotionBumper {}
+
+impl MotionBumper {
+    pub fn new(ctx: &mut Context) -> Self {
+        let settings = ctx.state().get_or_default::<MotionSettings>().clone();
+        let font_size = ctx.theme.fonts.size.h5;
+        let title = Text::new(ctx, "Motion Detection", TextStyle::Heading, font_size, Align::Center);
+        let description = match settings.zones.len() {
+            0 => "Watching the whole frame. Drag across the viewfinder to add a zone.".to_string(),
+            n => format!("Watching {} zone{}. Drag across the viewfinder to add more.", n, if n == 1 {""} else {"s"}),
+        };
+        let sensitivity = Slider::new(ctx, settings.sensitivity, Some("Sensitivity"), Some(&description), |ctx: &mut Context, value: f32| {
+            update_motion_settings(ctx, |s| s.sensitivity = value);
+        });
+        let options = MotionOptions::new(ctx, &settings);
+
+        let log = Button::secondary(ctx, None, "Event Log", None, |ctx: &mut Context| ctx.trigger_event(NavigateEvent(2)), None);
+        let clear = Button::secondary(ctx, None, "Clear Zones", None, |ctx: &mut Context| {
+            update_motion_settings(ctx, |s| s.zones.clear());
+            ctx.trigger_event(OpenMotionSettingsEvent);
+        }, None);
+        let done = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(CloseSheetEvent));
+        let actions = MotionActions(Row::center(16.0), log, clear, done);
+        MotionBumper(Column::new(24.0, Offset::Center, Size::Fit, Padding::default()), title, sensitivity, options, actions)
+    }
+}
+
+/// Applies `change` to the saved motion settings and tells the running monitor.
+fn update_motion_settings(ctx: &mut Context, change: impl FnOnce(&mut MotionSettings)) {
+    let mut settings = ctx.state().get_or_default::<MotionSettings>().clone();
+    change(&mut settings);
+    if settings != *ctx.state().get_or_default::<MotionSettings>() {
+        settings.save();
+        ctx.state().set(settings);
+        ctx.trigger_event(MotionSettingsChangedEvent);
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct MotionActions(Row, Button, Button, Button);
+impl OnEvent for MotionActions {}
+
+#[derive(Debug, Component)]
+pub struct MotionOptions(Row, Button, Button, Button);
+
+impl MotionOptions {
+    pub fn new(ctx: &mut Context, settings: &MotionSettings) -> Self {
+        let option = |ctx: &mut Context, label: &str, selected: bool, change: fn(&mut MotionSettings)| {
+            let state = if selected {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(label), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| update_motion_settings(ctx, change),
+                None,
+            )
+        };
+        let clips = option(ctx, "Clips", settings.clips, |s| s.clips = true);
+        let stills = option(ctx, "Stills", !settings.clips, |s| s.clips = false);
+        let headless = option(ctx, "Run in Background", settings.headless, |s| s.headless = !s.headless);
+        MotionOptions(Row::center(8.0), clips, stills, headless)
+    }
+}
+
+impl OnEvent for MotionOptions {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<MotionSettingsChangedEvent>().is_some() {
+            let settings = ctx.state().get_or_default::<MotionSettings>().clone();
+            for (button, selected) in [(&mut self.1, settings.clips), (&mut self.2, !settings.clips), (&mut self.3, settings.headless)] {
+                *button.status() = if selected {ButtonState::Selected} else {ButtonState::Default};
+                button.color(ctx);
+            }
+        }
+        true
+    }
+}
+
+/// Loops a stop motion project's frames at its frame rate.
+#[derive(Debug, Component)]
+pub struct AnimationPreview(Stack, ExpandableImage, #[skip] Vec<resources::Image>, #[skip] Duration, #[skip] (usize, Instant));
+
+impl AnimationPreview {
+    pub fn new(ctx: &mut Context, project: &Project) -> Self {
+        let frames = project.frames.iter().map(|f| Project::frame(f)).collect::<Vec<_>>();
+        let size = frames.first().map(|f| (f.width() as f32, f.height() as f32));
+        let frames: Vec<_> = frames.into_iter().map(|f| ctx.assets.add_image(f)).collect();
+        let first = frames.first().cloned().unwrap_or_else(|| ctx.theme.brand.illustrations.get("blank").unwrap());
+        AnimationPreview(Stack::default(), ExpandableImage::new(first, size), frames, project.frame_duration(), (0, Instant::now()))
+    }
+
+    pub fn set_frame_duration(&mut self, duration: Duration) { self.3 = duration; }
+}
+
+impl OnEvent for AnimationPreview {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() && !self.2.is_empty() {
+            let (i, shown) = &mut self.4;
+            if shown.elapsed() >= self.3 {
+                *i = (*i + 1) % self.2.len();
+                *shown = Instant::now();
+                self.1.image().image = self.2[*i].clone();
+            }
+        }
+        true
+    }
+}
+
+/// Changes the frame rate as it's dragged, and saves it once let go.
+#[derive(Debug, Component)]
+pub struct FrameRateSlider(Stack, Slider, #[skip] f32);
+
+impl OnEvent for FrameRateSlider {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(MouseEvent { state: MouseState::Released | MouseState::LongPressReleased, .. }) = event.downcast_ref::<MouseEvent>() {
+            let stop_motion = ctx.state().get_or_default::<StopMotion>();
+            let fps = stop_motion.active().fps;
+            if fps != self.2 {
+                self.2 = fps;
+                stop_motion.save();
+            }
+        }
+        true
+    }
+}
+
+impl FrameRateSlider {
+    pub fn new(ctx: &mut Context, fps: f32) -> Self {
+        let start = (fps - MIN_FPS) / (MAX_FPS - MIN_FPS) * 100.0;
+        let slider = Slider::new(ctx, start, Some("Frame Rate"), Some(&format!("{fps:.0} fps")), |ctx: &mut Context, p: f32| {
+            let fps = (MIN_FPS + (p / 100.0) * (MAX_FPS - MIN_FPS)).round();
+            let mut stop_motion = ctx.state().get_or_default::<StopMotion>().clone();
+            if stop_motion.project().fps != fps {
+                stop_motion.project().fps = fps;
+                ctx.state().set(stop_motion);
+            }
+        });
+        FrameRateSlider(Stack::default(), slider, fps)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct OnionOptions(Row, Text, Vec<Button>);
+impl OnEvent for OnionOptions {}
+
+impl OnionOptions {
+    pub fn new(ctx: &mut Context, current: usize) -> Self {
+        let font_size = ctx.theme.fonts.size.h5;
+        let label = Text::new(ctx, "Onion Skin", TextStyle::Heading, font_size, Align::Left);
+        let buttons = (0..=MAX_ONION).map(|layers| {
+            let state = if layers == current {ButtonState::Selected} else {ButtonState::Default};
+            let text = if layers == 0 {"Off".to_string()} else {layers.to_string()};
+            Button::new(
+                ctx, None, None, Some(&text), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| ctx.trigger_event(StopMotionEditEvent::Onion(layers)),
+                None,
+            )
+        }).collect();
+        OnionOptions(Row::center(8.0), label, buttons)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct ProjectOptions(Row, Vec<Button>);
+impl OnEvent for ProjectOptions {}
+
+impl ProjectOptions {
+    pub fn new(ctx: &mut Context, stop_motion: &StopMotion) -> Self {
+        let buttons = stop_motion.projects.iter().enumerate().map(|(i, project)| {
+            let state = if i == stop_motion.active {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(&project.name), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| ctx.trigger_event(StopMotionEditEvent::Select(i)),
+                None,
+            )
+        }).collect();
+        ProjectOptions(Row::center(8.0), buttons)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct FrameRow(Row, FrameThumbnail, Text, IconButton, IconButton, IconButton);
+impl OnEvent for FrameRow {}
+
+#[derive(Debug, Component)]
+pub struct FrameThumbnail(Stack, ExpandableImage);
+impl OnEvent for FrameThumbnail {}
+
+impl FrameRow {
+    pub fn new(ctx: &mut Context, index: usize, frame: &str) -> Self {
+        let image = ctx.assets.add_image(Project::frame(frame));
+        let font_size = ctx.theme.fonts.size.md;
+        let label = Text::new(ctx, &format!("Frame {}", index + 1), TextStyle::Primary, font_size, Align::Left);
+        let layout = Stack(Offset::Center, Offset::Center, Size::Static(64.0), Size::Static(64.0), Padding::default());
+        let thumbnail = FrameThumbnail(layout, ExpandableImage::new(image, None));
+        let earlier = IconButton::ghost(ctx, "up", Box::new(move |ctx: &mut Context| ctx.trigger_event(StopMotionEditEvent::Shift(index, -1))));
+        let later = IconButton::ghost(ctx, "down", Box::new(move |ctx: &mut Context| ctx.trigger_event(StopMotionEditEvent::Shift(index, 1))));
+        let delete = IconButton::ghost(ctx, "delete", Box::new(move |ctx: &mut Context| ctx.trigger_event(StopMotionEditEvent::Remove(index))));
+        FrameRow(Row::new(12.0, Offset::Center, Size::Fit, Padding::default()), thumbnail, label, earlier, later, delete)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct LongExposureBumper(Column, Text, ExposureOptions, ExposureOptions, Button);
+impl OnEvent for LongExposureBumper {}
+
+impl LongExposureBumper {
+    pub fn new(ctx: &mut Context) -> Self {
+        let settings = *ctx.state().get_or_default::<LongExposure>();
+        let font_size = ctx.theme.fonts.size.h5;
+        let title = Text::new(ctx, "Long Exposure", TextStyle::Heading, font_size, Align::Center);
+        let durations = DURATIONS.into_iter().map(|d| (format!("{d:.0}s"), d == settings.duration, Box::new(move |s: &mut LongExposure| s.duration = d) as ExposureChange)).collect();
+        let modes = BlendMode::ALL.into_iter().map(|m| (m.label().to_string(), m == settings.mode, Box::new(move |s: &mut LongExposure| s.mode = m) as ExposureChange)).collect();
+        let done = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(CloseSheetEvent));
+        LongExposureBumper(
+            Column::new(24.0, Offset::Center, Size::Fit, Padding::default()), title,
+            ExposureOptions::new(ctx, durations), ExposureOptions::new(ctx, modes), done
+        )
+    }
+}
+
+type ExposureChange = Box<dyn Fn(&mut LongExposure)>;
+
+/// A row of mutually exclusive choices that each adjust the long exposure settings.
+#[derive(Debug, Component)]
+pub struct ExposureOptions(Row, Vec<Button>);
+impl OnEvent for ExposureOptions {}
+
+impl ExposureOptions {
+    pub fn new(ctx: &mut Context, options: Vec<(String, bool, ExposureChange)>) -> Self {
+        let buttons = options.into_iter().map(|(label, selected, change)| {
+            let state = if selected {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(&label), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| {
+                    let mut settings = *ctx.state().get_or_default::<LongExposure>();
+                    change(&mut settings);
+                    settings.save();
+                    ctx.state().set(settings);
+                    ctx.trigger_event(OpenLongExposureEvent);
+                },
+                None,
+            )
+        }).collect();
+        ExposureOptions(Row::center(8.0), buttons)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct PhotoBoothBumper(Column, Text, BoothOptions, BoothOptions, BoothOptions, TextInput, Button, #[skip] String);
+
+impl PhotoBoothBumper {
+    pub fn new(ctx: &mut Context) -> Self {
+        let settings = ctx.state().get_or_default::<PhotoBooth>().clone();
+        let font_size = ctx.theme.fonts.size.h5;
+        let title = Text::new(ctx, "Photo Booth", TextStyle::Heading, font_size, Align::Center);
+        let shots = SHOT_COUNTS.into_iter().map(|n| (format!("{n} shots"), n == settings.shots, Box::new(move |s: &mut PhotoBooth| s.shots = n) as BoothChange)).collect();
+        let countdowns = COUNTDOWNS.into_iter().map(|c| (format!("{c}s"), c == settings.countdown, Box::new(move |s: &mut PhotoBooth| s.countdown = c) as BoothChange)).collect();
+        let mut layouts = BoothLayout::ALL.into_iter().map(|l| (l.label().to_string(), l == settings.layout, Box::new(move |s: &mut PhotoBooth| s.layout = l) as BoothChange)).collect::<Vec<_>>();
+        layouts.push(("Date".to_string(), settings.date, Box::new(|s: &mut PhotoBooth| s.date = !s.date)));
+        let caption = TextInput::new(ctx, Some(&settings.caption), None, "Caption", None, TextInput::NO_ICON, false);
+        let done = DoneButton::new(ctx, |ctx: &mut Context| ctx.trigger_event(CloseSheetEvent));
+        PhotoBoothBumper(
+            Column::new(24.0, Offset::Center, Size::Fit, Padding::default()), title,
+            BoothOptions::new(ctx, shots), BoothOptions::new(ctx, countdowns), BoothOptions::new(ctx, layouts),
+            caption, done, settings.caption
+        )
+    }
+}
+
+impl OnEvent for PhotoBoothBumper {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        // The caption is saved as it's typed since the option buttons rebuild the sheet.
+        if event.downcast_ref::<TickEvent>().is_some() && *self.5.value() != self.7 {
+            self.7 = self.5.value().clone();
+            let mut settings = ctx.state().get_or_default::<PhotoBooth>().clone();
+            settings.caption = self.7.clone();
+            settings.save();
+            ctx.state().set(settings);
+        }
+        true
+    }
+}
+
+type BoothChange = Box<dyn Fn(&mut PhotoBooth)>;
+
+/// A row of choices that each adjust the photo booth settings.
+#[derive(Debug, Component)]
+pub struct BoothOptions(Row, Vec<Button>);
+impl OnEvent for BoothOptions {}
+
+impl BoothOptions {
+    pub fn new(ctx: &mut Context, options: Vec<(String, bool, BoothChange)>) -> Self {
+        let buttons = options.into_iter().map(|(label, selected, change)| {
+            let state = if selected {ButtonState::Selected} else {ButtonState::Default};
+            Button::new(
+                ctx, None, None, Some(&label), None,
+                ButtonSize::Medium, ButtonWidth::Hug, ButtonStyle::Ghost, state, Offset::Center,
+                move |ctx: &mut Context| {
+                    let mut settings = ctx.state().get_or_default::<PhotoBooth>().clone();
+                    change(&mut settings);
+                    settings.save();
+                    ctx.state().set(settings);
+                    ctx.trigger_event(OpenPhotoBoothEvent);
+                },
+                None,
+            )
+        }).collect();
+        BoothOptions(Row::center(8.0), buttons)
+    }
+}
+
+/// The individual shots of a photo booth composite.
+#[derive(Debug, Component)]
+pub struct BoothShots(Row, Vec<ImageButton>);
+impl OnEvent for BoothShots {}
+
+impl BoothShots {
+    pub fn new(ctx: &mut Context, shots: Vec<Photo>) -> Self {
+        BoothShots(Row::center(8.0), shots.into_iter().map(|shot| ImageButton::new(ctx, shot)).collect())
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct SettingsButton(Stack, IconButton, #[skip] CameraSetting);
 impl OnEvent for SettingsButton {}
 
 impl SettingsButton {
-    pub fn new(id: String, button: IconButton) -> Self {
+    pub fn new(id: CameraSetting, button: IconButton) -> Self {
         SettingsButton(Stack::default(), button, id)
     }
 
-    pub fn id(&self) -> String {
-        self.2.clone()
+    pub fn id(&self) -> CameraSetting {
+        self.2
     }
 
     pub fn inner(&mut self) -> &mut IconButton {
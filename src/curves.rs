@@ -0,0 +1,422 @@
+use image::{Rgba, RgbaImage};
+use serde::{Serialize, Deserialize};
+
+use crate::draw;
+use crate::storage;
+
+// Control points are kept at least this far apart across, so the curve stays a function.
+const MIN_GAP: f32 = 0.02;
+const MAX_POINTS: usize = 16;
+pub const VIEW_SIZE: u32 = 256;
+const LEVELS_HEIGHT: u32 = 96;
+const BACKGROUND: Rgba<u8> = Rgba([24, 24, 24, 255]);
+const GRID: Rgba<u8> = Rgba([255, 255, 255, 40]);
+const BARS: Rgba<u8> = Rgba([255, 255, 255, 70]);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Channel {
+    /// All three channels together.
+    #[default]
+    Rgb,
+    Red,
+    Green,
+    Blue,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 4] = [Channel::Rgb, Channel::Red, Channel::Green, Channel::Blue];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Channel::Rgb => "RGB",
+            Channel::Red => "Red",
+            Channel::Green => "Green",
+            Channel::Blue => "Blue",
+        }
+    }
+
+    fn color(&self) -> Rgba<u8> {
+        match self {
+            Channel::Rgb => Rgba([255, 255, 255, 255]),
+            Channel::Red => Rgba([255, 80, 80, 255]),
+            Channel::Green => Rgba([80, 220, 100, 255]),
+            Channel::Blue => Rgba([90, 140, 255, 255]),
+        }
+    }
+}
+
+/// A tone curve through control points, with input and output from 0 to 1. The first
+/// and last points stay at the ends and only move up and down.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<(f32, f32)>")]
+pub struct Curve(pub Vec<(f32, f32)>);
+
+/// Points read from a file are put back in order, spaced apart and pinned to the ends,
+/// so a damaged curve can't break the editing below.
+impl From<Vec<(f32, f32)>> for Curve {
+    fn from(points: Vec<(f32, f32)>) -> Self {
+        let mut points = points.into_iter()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|(x, y)| (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)))
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (first, last) = (points.first().map_or(0.0, |p| p.1), points.last().map_or(1.0, |p| p.1));
+        let mut curve = vec![(0.0, first)];
+        for (x, y) in points.into_iter().filter(|(x, _)| (MIN_GAP..=1.0 - MIN_GAP).contains(x)) {
+            if curve.len() < MAX_POINTS - 1 && x - curve[curve.len() - 1].0 >= MIN_GAP {
+                curve.push((x, y));
+            }
+        }
+        curve.push((1.0, last));
+        Curve(curve)
+    }
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve(vec![(0.0, 0.0), (1.0, 1.0)])
+    }
+}
+
+impl Curve {
+    pub fn is_identity(&self) -> bool {
+        self.0.iter().all(|(x, y)| (x - y).abs() < 1e-4)
+    }
+
+    // Fritsch-Carlson tangents, which keep the curve from overshooting between points.
+    fn tangents(&self) -> Vec<f32> {
+        let points = &self.0;
+        let secants = points.windows(2).map(|w| (w[1].1 - w[0].1) / (w[1].0 - w[0].0).max(f32::EPSILON)).collect::<Vec<_>>();
+        let mut tangents = (0..points.len()).map(|i| match (i.checked_sub(1).map(|j| secants[j]), secants.get(i)) {
+            (Some(before), Some(after)) if before * after > 0.0 => (before + after) / 2.0,
+            (Some(_), Some(_)) => 0.0,
+            (Some(before), None) => before,
+            (None, Some(after)) => *after,
+            (None, None) => 1.0,
+        }).collect::<Vec<_>>();
+        for (i, secant) in secants.iter().enumerate() {
+            if *secant == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+            let (a, b) = (tangents[i] / secant, tangents[i + 1] / secant);
+            let length = a * a + b * b;
+            if length > 9.0 {
+                let scale = 3.0 / length.sqrt();
+                tangents[i] = scale * a * secant;
+                tangents[i + 1] = scale * b * secant;
+            }
+        }
+        tangents
+    }
+
+    /// Output levels for every 8-bit input.
+    pub fn table(&self) -> [f32; 256] {
+        let points = &self.0;
+        let tangents = self.tangents();
+        std::array::from_fn(|i| {
+            let x = i as f32 / 255.0;
+            let k = points.windows(2).position(|w| x <= w[1].0).unwrap_or(points.len().saturating_sub(2));
+            let ((x0, y0), (x1, y1)) = (points[k], points[(k + 1).min(points.len() - 1)]);
+            let h = (x1 - x0).max(f32::EPSILON);
+            let t = ((x - x0) / h).clamp(0.0, 1.0);
+            let (t2, t3) = (t * t, t * t * t);
+            let y = (2.0 * t3 - 3.0 * t2 + 1.0) * y0 + (t3 - 2.0 * t2 + t) * h * tangents[k]
+                + (-2.0 * t3 + 3.0 * t2) * y1 + (t3 - t2) * h * tangents[(k + 1).min(points.len() - 1)];
+            y.clamp(0.0, 1.0)
+        })
+    }
+
+    /// The point nearest `p`, if any is within `reach`.
+    pub fn nearest(&self, (x, y): (f32, f32), reach: f32) -> Option<usize> {
+        self.0.iter().enumerate()
+            .map(|(i, (px, py))| (i, ((px - x).powi(2) + (py - y).powi(2)).sqrt()))
+            .filter(|(_, distance)| *distance <= reach)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    /// Adds a point and returns where it went, unless it would crowd a neighbour.
+    pub fn insert(&mut self, (x, y): (f32, f32)) -> Option<usize> {
+        if self.0.len() >= MAX_POINTS || self.0.iter().any(|(px, _)| (px - x).abs() < MIN_GAP) { return None; }
+        let i = self.0.iter().position(|(px, _)| *px > x)?;
+        self.0.insert(i, (x, y.clamp(0.0, 1.0)));
+        Some(i)
+    }
+
+    /// Moves a point, keeping it between its neighbours.
+    pub fn move_point(&mut self, i: usize, (x, y): (f32, f32)) {
+        let last = self.0.len() - 1;
+        let x = match i {
+            0 => 0.0,
+            i if i == last => 1.0,
+            i => x.clamp(self.0[i - 1].0 + MIN_GAP, self.0[i + 1].0 - MIN_GAP),
+        };
+        self.0[i] = (x, y.clamp(0.0, 1.0));
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        if i > 0 && i < self.0.len() - 1 {
+            self.0.remove(i);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelPoint {
+    Black,
+    Mid,
+    White,
+}
+
+/// Input levels. Black and white are from 0 to 1; mid is a gamma, where above 1 brightens the midtones.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedLevels")]
+pub struct Levels {
+    pub black: f32,
+    pub mid: f32,
+    pub white: f32,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Levels { black: 0.0, mid: 1.0, white: 1.0 }
+    }
+}
+
+#[derive(Deserialize)]
+struct SavedLevels {
+    black: f32,
+    mid: f32,
+    white: f32,
+}
+
+/// Levels read from a file are brought back in range, keeping black below white.
+impl From<SavedLevels> for Levels {
+    fn from(saved: SavedLevels) -> Self {
+        let finite = |v: f32, default: f32| if v.is_finite() {v} else {default};
+        let black = finite(saved.black, 0.0).clamp(0.0, 1.0 - MIN_GAP);
+        Levels {
+            black,
+            mid: finite(saved.mid, 1.0).clamp(Self::MID_RANGE.0, Self::MID_RANGE.1),
+            white: finite(saved.white, 1.0).clamp(black + MIN_GAP, 1.0),
+        }
+    }
+}
+
+impl Levels {
+    pub const MID_RANGE: (f32, f32) = (0.1, 10.0);
+
+    pub fn set(&mut self, point: LevelPoint, value: f32) {
+        match point {
+            LevelPoint::Black => self.black = value.clamp(0.0, self.white - MIN_GAP),
+            LevelPoint::Mid => self.mid = value.clamp(Self::MID_RANGE.0, Self::MID_RANGE.1),
+            LevelPoint::White => self.white = value.clamp(self.black + MIN_GAP, 1.0),
+        }
+    }
+
+    fn map(&self, x: f32) -> f32 {
+        ((x - self.black) / (self.white - self.black).max(MIN_GAP)).clamp(0.0, 1.0).powf(1.0 / self.mid)
+    }
+
+    /// The input level that comes out as middle gray.
+    fn midpoint(&self) -> f32 {
+        self.black + (self.white - self.black) * 0.5_f32.powf(self.mid)
+    }
+}
+
+/// Levels, then the RGB curve, then each channel's own curve. Used as a capture-time
+/// adjustment and as a step in the photo editor.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToneCurves {
+    pub levels: Levels,
+    pub rgb: Curve,
+    pub red: Curve,
+    pub green: Curve,
+    pub blue: Curve,
+}
+
+impl ToneCurves {
+    const FILE: &'static str = "tone_curves.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn curve(&self, channel: Channel) -> &Curve {
+        match channel {
+            Channel::Rgb => &self.rgb,
+            Channel::Red => &self.red,
+            Channel::Green => &self.green,
+            Channel::Blue => &self.blue,
+        }
+    }
+
+    pub fn curve_mut(&mut self, channel: Channel) -> &mut Curve {
+        match channel {
+            Channel::Rgb => &mut self.rgb,
+            Channel::Red => &mut self.red,
+            Channel::Green => &mut self.green,
+            Channel::Blue => &mut self.blue,
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.levels == Levels::default() && Channel::ALL.iter().all(|c| self.curve(*c).is_identity())
+    }
+
+    /// Lookup tables for red, green and blue.
+    fn tables(&self) -> [[u8; 256]; 3] {
+        let rgb = self.rgb.table();
+        let lookup = |curve: &Curve| {
+            let table = curve.table();
+            let index = |v: f32| (v * 255.0).round() as usize;
+            std::array::from_fn(|i| (table[index(rgb[index(self.levels.map(i as f32 / 255.0))])] * 255.0).round() as u8)
+        };
+        [lookup(&self.red), lookup(&self.green), lookup(&self.blue)]
+    }
+
+    pub fn apply(&self, mut image: RgbaImage) -> RgbaImage {
+        if self.is_identity() { return image; }
+        let tables = self.tables();
+        image.pixels_mut().for_each(|p| (0..3).for_each(|c| p.0[c] = tables[c][p.0[c] as usize]));
+        image
+    }
+}
+
+/// How many pixels sit at each level, in luma and in each channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram([[u32; 256]; 4]);
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram([[0; 256]; 4])
+    }
+}
+
+impl Histogram {
+    pub fn of(image: &RgbaImage) -> Self {
+        let mut bins = [[0; 256]; 4];
+        image.pixels().for_each(|p| {
+            let [r, g, b, _] = p.0;
+            let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round() as usize;
+            bins[0][luma.min(255)] += 1;
+            (0..3).for_each(|c| bins[c + 1][p.0[c] as usize] += 1);
+        });
+        Histogram(bins)
+    }
+
+    fn bins(&self, channel: Channel) -> &[u32; 256] {
+        &self.0[Channel::ALL.iter().position(|c| *c == channel).unwrap_or(0)]
+    }
+
+    // Bars scaled to the tallest, leaving out the ends, which clipping can make tower over everything else.
+    fn draw(&self, image: &mut RgbaImage, channel: Channel) {
+        let bins = self.bins(channel);
+        let tallest = bins[1..255].iter().max().copied().unwrap_or(0).max(1) as f32;
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let bar = width / 256.0;
+        bins.iter().enumerate().for_each(|(i, count)| {
+            let h = (*count as f32 / tallest).min(1.0) * height;
+            draw::fill_rect(image, (i as f32 * bar, height - h, bar.max(1.0), h), BARS);
+        });
+    }
+}
+
+/// The curve for `channel` over its histogram, with its control points.
+pub fn draw_curve(curves: &ToneCurves, channel: Channel, histogram: &Histogram) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(VIEW_SIZE, VIEW_SIZE, BACKGROUND);
+    let size = VIEW_SIZE as f32 - 1.0;
+    histogram.draw(&mut image, channel);
+    (1..4).map(|i| i as f32 * size / 4.0).for_each(|at| {
+        draw::line(&mut image, (at, 0.0), (at, size), 1.0, GRID);
+        draw::line(&mut image, (0.0, at), (size, at), 1.0, GRID);
+    });
+    draw::line(&mut image, (0.0, size), (size, 0.0), 1.0, GRID);
+
+    let curve = curves.curve(channel);
+    let color = channel.color();
+    let table = curve.table();
+    table.windows(2).enumerate().for_each(|(i, w)| {
+        let x = i as f32 * size / 255.0;
+        draw::line(&mut image, (x, (1.0 - w[0]) * size), (x + size / 255.0, (1.0 - w[1]) * size), 2.0, color);
+    });
+    curve.0.iter().for_each(|(x, y)| draw::dot(&mut image, x * size, (1.0 - y) * size, 5.0, color));
+    image
+}
+
+/// The luma histogram with the black, mid and white points marked along the bottom.
+pub fn draw_levels(levels: &Levels, histogram: &Histogram) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(VIEW_SIZE, LEVELS_HEIGHT, BACKGROUND);
+    histogram.draw(&mut image, Channel::Rgb);
+    let (size, bottom) = (VIEW_SIZE as f32 - 1.0, LEVELS_HEIGHT as f32 - 1.0);
+    let markers = [(levels.black, Rgba([0, 0, 0, 255])), (levels.midpoint(), Rgba([128, 128, 128, 255])), (levels.white, Rgba([255, 255, 255, 255]))];
+    markers.into_iter().for_each(|(at, color)| {
+        let x = at * size;
+        draw::line(&mut image, (x, 0.0), (x, bottom), 1.0, GRID);
+        draw::dot(&mut image, x, bottom - 6.0, 7.0, Rgba([200, 200, 200, 255]));
+        draw::dot(&mut image, x, bottom - 6.0, 5.0, color);
+    });
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(json: &str) -> Curve {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn saved_curves_are_repaired() {
+        assert_eq!(curve("[]"), Curve::default());
+        assert_eq!(curve("[[0.5, 0.7]]"), Curve(vec![(0.0, 0.7), (0.5, 0.7), (1.0, 0.7)]));
+        // Out of order, out of range and crowded points.
+        let repaired = curve("[[1.0, 1.0], [0.5, 0.8], [0.0, -0.5], [0.505, 0.2], [2.0, 1.0]]");
+        assert_eq!(repaired, Curve(vec![(0.0, 0.0), (0.5, 0.8), (1.0, 1.0)]));
+        let crowded = serde_json::to_string(&(0..40).map(|i| (i as f32 / 40.0, 0.5)).collect::<Vec<_>>()).unwrap();
+        assert_eq!(curve(&crowded).0.len(), MAX_POINTS);
+        // A good curve is read as it was written.
+        let good = Curve(vec![(0.0, 0.1), (0.25, 0.3), (1.0, 0.9)]);
+        assert_eq!(curve(&serde_json::to_string(&good).unwrap()), good);
+    }
+
+    #[test]
+    fn repaired_curves_can_be_edited() {
+        let mut repaired = curve("[[0.7, 0.2], [0.3, 0.9]]");
+        repaired.move_point(1, (0.9, 0.5));
+        repaired.remove(1);
+        assert_eq!(repaired.0.len(), 3);
+        assert!(repaired.table().iter().all(|y| (0.0..=1.0).contains(y)));
+    }
+
+    #[test]
+    fn saved_levels_are_repaired() {
+        let levels: Levels = serde_json::from_str(r#"{"black": 0.9, "mid": 50.0, "white": 0.2}"#).unwrap();
+        assert_eq!(levels.mid, Levels::MID_RANGE.1);
+        assert!(levels.black + MIN_GAP <= levels.white, "{levels:?}");
+        let mut levels: Levels = serde_json::from_str(r#"{"black": 1.5, "mid": 1.0, "white": 1.0}"#).unwrap();
+        levels.set(LevelPoint::Black, 0.5);
+        levels.set(LevelPoint::White, 0.0);
+        assert_eq!(levels.black, 0.5);
+        assert_eq!(levels.white, 0.5 + MIN_GAP);
+    }
+
+    #[test]
+    fn curves_follow_their_points() {
+        let table = Curve::default().table();
+        assert!(table.iter().enumerate().all(|(i, y)| (y - i as f32 / 255.0).abs() < 1e-4));
+        let mut lifted = Curve::default();
+        let i = lifted.insert((0.5, 0.75)).unwrap();
+        assert!(lifted.insert((0.51, 0.2)).is_none());
+        assert!((lifted.table()[128] - 0.75).abs() < 0.01);
+        lifted.remove(i);
+        assert!(lifted.is_identity());
+    }
+}
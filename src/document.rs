@@ -0,0 +1,399 @@
+use std::io::Write;
+
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::{self, FilterType};
+use serde::{Serialize, Deserialize};
+
+use crate::frames::luma;
+use crate::worker::Worker;
+
+const DETECT_WIDTH: u32 = 320;
+const MIN_AREA: f32 = 0.1;
+const MAX_AREA: f32 = 0.98;
+const MAX_SIDE: f32 = 3000.0;
+const BACKGROUND_BLOCK: u32 = 24;
+// A4 width in points; every page is scaled to it and keeps its own aspect ratio.
+const PAGE_WIDTH: f32 = 595.0;
+
+/// Pages captured in Document mode that haven't been saved yet. Pages are kept
+/// unfiltered so the filter can change until the document is saved.
+#[derive(Clone, Debug, Default)]
+pub struct DocumentDraft {
+    pub pages: Vec<String>,
+    pub filter: ScanFilter,
+}
+
+/// Corners of a document in frame pixels: top left, top right, bottom right, bottom left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quad(pub [(f32, f32); 4]);
+
+impl Quad {
+    pub fn full(image: &RgbaImage) -> Self {
+        let (w, h) = (image.width() as f32, image.height() as f32);
+        Quad([(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanFilter {
+    #[default]
+    Color,
+    Grayscale,
+    BlackWhite,
+}
+
+impl ScanFilter {
+    pub const ALL: [ScanFilter; 3] = [ScanFilter::Color, ScanFilter::Grayscale, ScanFilter::BlackWhite];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScanFilter::Color => "Color",
+            ScanFilter::Grayscale => "Grayscale",
+            ScanFilter::BlackWhite => "B/W",
+        }
+    }
+
+    /// Evens out lighting so the paper reads as white, then applies the filter.
+    pub fn apply(&self, page: &RgbaImage) -> RgbaImage {
+        let gray = imageops::grayscale(page);
+        let background = background(&gray);
+        let flat = |v: u8, bg: u8| ((v as f32 / bg.max(1) as f32) * 255.0).min(255.0) as u8;
+        match self {
+            ScanFilter::Color => {
+                let mut out = page.clone();
+                for (x, y, p) in out.enumerate_pixels_mut() {
+                    let bg = background.get_pixel(x, y).0[0];
+                    for c in 0..3 { p.0[c] = flat(p.0[c], bg); }
+                }
+                stretch(&mut out);
+                out
+            },
+            ScanFilter::Grayscale | ScanFilter::BlackWhite => {
+                let mut flattened = GrayImage::from_fn(gray.width(), gray.height(), |x, y| {
+                    Luma([flat(gray.get_pixel(x, y).0[0], background.get_pixel(x, y).0[0])])
+                });
+                if *self == ScanFilter::BlackWhite {
+                    let t = otsu(&flattened);
+                    flattened.pixels_mut().for_each(|p| p.0[0] = if p.0[0] > t {255} else {0});
+                }
+                RgbaImage::from_fn(gray.width(), gray.height(), |x, y| {
+                    let v = flattened.get_pixel(x, y).0[0];
+                    Rgba([v, v, v, 255])
+                })
+            }
+        }
+    }
+}
+
+/// Finds documents on a worker thread, offered frames as the scanner is.
+pub type Detector = Worker<RgbaImage, Option<Quad>>;
+
+pub fn detector() -> Detector {
+    Worker::new(|frame: RgbaImage| detect(&frame))
+}
+
+/// Finds the largest bright quadrilateral in the frame: the frame is thresholded,
+/// the largest bright region kept, and four corners fitted to its convex hull.
+pub fn detect(frame: &RgbaImage) -> Option<Quad> {
+    let gray = imageops::blur(&luma(frame, DETECT_WIDTH), 1.5);
+    let (w, h) = gray.dimensions();
+    let scale = frame.width() as f32 / w as f32;
+    let threshold = otsu(&gray);
+    let region = largest_region(&gray, threshold)?;
+
+    // The hull of each row's outermost pixels is the hull of the whole region.
+    let mut edges = Vec::new();
+    for (y, span) in region.iter().enumerate() {
+        if let Some((x0, x1)) = span {
+            edges.push((*x0 as f32, y as f32));
+            edges.push((*x1 as f32, y as f32));
+        }
+    }
+    let hull = convex_hull(edges);
+    if hull.len() < 4 {
+        return None;
+    }
+
+    let corners = fit_quad(&hull);
+    let fraction = area(&corners) / (w * h) as f32;
+    if !(MIN_AREA..=MAX_AREA).contains(&fraction) {
+        return None;
+    }
+    Some(Quad(corners.map(|(x, y)| ((x + 0.5) * scale, (y + 0.5) * scale))))
+}
+
+/// Maps the quad onto a flat rectangle sized by its longest opposite edges.
+pub fn warp(frame: &RgbaImage, quad: &Quad) -> RgbaImage {
+    let [tl, tr, br, bl] = quad.0;
+    let length = |a: (f32, f32), b: (f32, f32)| ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+    let width = length(tl, tr).max(length(bl, br)).clamp(1.0, MAX_SIDE).round();
+    let height = length(tl, bl).max(length(tr, br)).clamp(1.0, MAX_SIDE).round();
+
+    let rect = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+    let Some(h) = homography(rect, quad.0) else { return frame.clone() };
+    RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+        let (u, v) = (x as f64 + 0.5, y as f64 + 0.5);
+        let d = h[6] * u + h[7] * v + 1.0;
+        let sx = (h[0] * u + h[1] * v + h[2]) / d;
+        let sy = (h[3] * u + h[4] * v + h[5]) / d;
+        bilinear(frame, sx as f32 - 0.5, sy as f32 - 0.5)
+    })
+}
+
+/// A minimal PDF with one JPEG page per image.
+pub fn pdf(pages: &[RgbaImage]) -> Vec<u8> {
+    let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::new();
+    let count = pages.len();
+    // Objects: 1 catalog, 2 page tree, then page, content and image for each page.
+    let page_ids = (0..count).map(|i| 3 + i * 3).collect::<Vec<_>>();
+
+    let mut object = |out: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(out.len());
+        writeln!(out, "{} 0 obj", offsets.len()).unwrap();
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    };
+
+    object(&mut out, b"<< /Type /Catalog /Pages 2 0 R >>");
+    let kids = page_ids.iter().map(|id| format!("{id} 0 R")).collect::<Vec<_>>().join(" ");
+    object(&mut out, format!("<< /Type /Pages /Kids [{kids}] /Count {count} >>").as_bytes());
+
+    for (page, id) in pages.iter().zip(&page_ids) {
+        let (w, h) = page.dimensions();
+        let page_height = PAGE_WIDTH * h as f32 / w as f32;
+        let mut jpeg = Vec::new();
+        let rgb = image::DynamicImage::ImageRgba8(page.clone()).to_rgb8();
+        JpegEncoder::new_with_quality(&mut jpeg, 85).encode_image(&rgb).expect("Could not encode page");
+
+        object(&mut out, format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {page_height:.2}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+            id + 2, id + 1
+        ).as_bytes());
+        let draw = format!("q {PAGE_WIDTH} 0 0 {page_height:.2} 0 0 cm /Im0 Do Q");
+        object(&mut out, format!("<< /Length {} >>\nstream\n{draw}\nendstream", draw.len()).as_bytes());
+        let mut image = format!(
+            "<< /Type /XObject /Subtype /Image /Width {w} /Height {h} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            jpeg.len()
+        ).into_bytes();
+        image.extend_from_slice(&jpeg);
+        image.extend_from_slice(b"\nendstream");
+        object(&mut out, &image);
+    }
+
+    let xref = out.len();
+    write!(out, "xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).unwrap();
+    offsets.iter().for_each(|o| writeln!(out, "{o:010} 00000 n ").unwrap());
+    write!(out, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n", offsets.len() + 1).unwrap();
+    out
+}
+
+fn otsu(gray: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    gray.pixels().for_each(|p| histogram[p.0[0] as usize] += 1);
+    let total = gray.pixels().len() as f64;
+    let sum = histogram.iter().enumerate().map(|(i, n)| i as f64 * *n as f64).sum::<f64>();
+    let (mut weight, mut sum_below, mut best, mut threshold) = (0.0, 0.0, 0.0, 0);
+    for (i, n) in histogram.iter().enumerate() {
+        weight += *n as f64;
+        if weight == 0.0 || weight == total { continue; }
+        sum_below += i as f64 * *n as f64;
+        let mean_below = sum_below / weight;
+        let mean_above = (sum - sum_below) / (total - weight);
+        let between = weight * (total - weight) * (mean_below - mean_above).powi(2);
+        if between > best {
+            best = between;
+            threshold = i as u8;
+        }
+    }
+    threshold
+}
+
+/// The largest 4-connected region brighter than `threshold`, as each row's
+/// leftmost and rightmost pixel.
+fn largest_region(gray: &GrayImage, threshold: u8) -> Option<Vec<Option<(u32, u32)>>> {
+    let (w, h) = gray.dimensions();
+    let index = |x: u32, y: u32| (y * w + x) as usize;
+    let mut labels = vec![0u32; (w * h) as usize];
+    let (mut best, mut best_size, mut next) = (0, 0, 1);
+    for y in 0..h {
+        for x in 0..w {
+            if labels[index(x, y)] != 0 || gray.get_pixel(x, y).0[0] <= threshold { continue; }
+            let mut stack = vec![(x, y)];
+            let mut size = 0;
+            labels[index(x, y)] = next;
+            while let Some((px, py)) = stack.pop() {
+                size += 1;
+                let neighbours = [(px.wrapping_sub(1), py), (px + 1, py), (px, py.wrapping_sub(1)), (px, py + 1)];
+                for (nx, ny) in neighbours {
+                    if nx < w && ny < h && labels[index(nx, ny)] == 0 && gray.get_pixel(nx, ny).0[0] > threshold {
+                        labels[index(nx, ny)] = next;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            if size > best_size {
+                best_size = size;
+                best = next;
+            }
+            next += 1;
+        }
+    }
+    (best != 0).then(|| (0..h).map(|y| {
+        let mut xs = (0..w).filter(|x| labels[index(*x, y)] == best);
+        let first = xs.next()?;
+        Some((first, xs.next_back().unwrap_or(first)))
+    }).collect())
+}
+
+fn cross(o: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+// Andrew's monotone chain.
+fn convex_hull(mut points: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let mut hull: Vec<(f32, f32)> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &(f32, f32)>> = match pass {
+            0 => Box::new(points.iter()),
+            _ => Box::new(points.iter().rev()),
+        };
+        for p in ordered {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+fn area(points: &[(f32, f32)]) -> f32 {
+    (0..points.len()).map(|i| {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        a.0 * b.1 - b.0 * a.1
+    }).sum::<f32>().abs() / 2.0
+}
+
+/// Starts from the diagonal extremes and moves one corner at a time to the hull
+/// point that grows the quad most, which also handles documents held at an angle.
+fn fit_quad(hull: &[(f32, f32)]) -> [(f32, f32); 4] {
+    let extreme = |key: fn(&(f32, f32)) -> f32| *hull.iter().max_by(|a, b| key(a).total_cmp(&key(b))).unwrap();
+    let mut corners = [
+        extreme(|p| -(p.0 + p.1)),
+        extreme(|p| p.0 - p.1),
+        extreme(|p| p.0 + p.1),
+        extreme(|p| p.1 - p.0),
+    ];
+    for _ in 0..3 {
+        for i in 0..4 {
+            corners[i] = *hull.iter().max_by(|a, b| {
+                let with = |p: (f32, f32)| { let mut c = corners; c[i] = p; area(&c) };
+                with(**a).total_cmp(&with(**b))
+            }).unwrap();
+        }
+    }
+    corners
+}
+
+/// Solves for the projective transform taking each `from` point to its `to` point.
+fn homography(from: [(f32, f32); 4], to: [(f32, f32); 4]) -> Option<[f64; 8]> {
+    let mut m = [[0.0f64; 9]; 8];
+    for (i, ((x, y), (u, v))) in from.into_iter().zip(to).enumerate() {
+        let (x, y, u, v) = (x as f64, y as f64, u as f64, v as f64);
+        m[i * 2] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+        m[i * 2 + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+    }
+    for col in 0..8 {
+        let pivot = (col..8).max_by(|a, b| m[*a][col].abs().total_cmp(&m[*b][col].abs()))?;
+        if m[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+        m.swap(col, pivot);
+        for row in 0..8 {
+            if row != col {
+                let (pivot, f) = (m[col], m[row][col] / m[col][col]);
+                m[row].iter_mut().zip(pivot).skip(col).for_each(|(v, p)| *v -= f * p);
+            }
+        }
+    }
+    Some(std::array::from_fn(|i| m[i][8] / m[i][i]))
+}
+
+fn bilinear(image: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let (w, h) = (image.width() as i64, image.height() as i64);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let px = |x: i64, y: i64| image.get_pixel(x.clamp(0, w - 1) as u32, y.clamp(0, h - 1) as u32).0;
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let (a, b, c, d) = (px(x0, y0), px(x0 + 1, y0), px(x0, y0 + 1), px(x0 + 1, y0 + 1));
+    Rgba(std::array::from_fn(|i| {
+        let top = a[i] as f32 * (1.0 - fx) + b[i] as f32 * fx;
+        let bottom = c[i] as f32 * (1.0 - fx) + d[i] as f32 * fx;
+        (top * (1.0 - fy) + bottom * fy).round() as u8
+    }))
+}
+
+/// Paper brightness across the page: the brightest pixel in each block, smoothed
+/// and scaled back up, so ink doesn't drag the estimate down.
+fn background(gray: &GrayImage) -> GrayImage {
+    let (w, h) = gray.dimensions();
+    let (bw, bh) = (w.div_ceil(BACKGROUND_BLOCK), h.div_ceil(BACKGROUND_BLOCK));
+    let blocks = GrayImage::from_fn(bw, bh, |bx, by| {
+        let (x0, y0) = (bx * BACKGROUND_BLOCK, by * BACKGROUND_BLOCK);
+        let max = (y0..(y0 + BACKGROUND_BLOCK).min(h))
+            .flat_map(|y| (x0..(x0 + BACKGROUND_BLOCK).min(w)).map(move |x| (x, y)))
+            .map(|(x, y)| gray.get_pixel(x, y).0[0])
+            .max().unwrap_or(255);
+        Luma([max])
+    });
+    imageops::resize(&imageops::blur(&blocks, 1.0), w, h, FilterType::Triangle)
+}
+
+// Stretches each channel so its 1st and 99th percentiles span the full range.
+fn stretch(image: &mut RgbaImage) {
+    let n = image.pixels().len() as u64;
+    for c in 0..3 {
+        let mut histogram = [0u64; 256];
+        image.pixels().for_each(|p| histogram[p.0[c] as usize] += 1);
+        let percentile = |q: f64| {
+            let target = (n as f64 * q) as u64;
+            let mut seen = 0;
+            histogram.iter().position(|count| { seen += count; seen > target }).unwrap_or(255) as f32
+        };
+        let (lo, hi) = (percentile(0.01), percentile(0.99));
+        if hi - lo < 1.0 { continue; }
+        image.pixels_mut().for_each(|p| p.0[c] = (((p.0[c] as f32 - lo) / (hi - lo)) * 255.0).clamp(0.0, 255.0) as u8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::draw;
+    use crate::frames::{FrameSource, StillFrames};
+    use crate::worker::wait;
+
+    #[test]
+    fn document_detected_in_still_frames() {
+        let mut frame = RgbaImage::from_pixel(640, 480, Rgba([30, 30, 30, 255]));
+        draw::fill_rect(&mut frame, (160.0, 100.0, 320.0, 280.0), Rgba([240, 240, 240, 255]));
+        let mut stills = StillFrames::new(vec![frame]);
+        let mut detector = detector();
+        detector.offer(|| stills.next_frame().unwrap());
+        let quad = wait(|| detector.poll().pop()).expect("no document found");
+        let expected = [(160.0, 100.0), (480.0, 100.0), (480.0, 380.0), (160.0, 380.0)];
+        for (corner, (x, y)) in quad.0.iter().zip(expected) {
+            assert!((corner.0 - x).abs() < 12.0 && (corner.1 - y).abs() < 12.0, "{corner:?} is far from {:?}", (x, y));
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use image::{Rgba, RgbaImage};
+
+// Overlays are burned into the preview frame before it is uploaded, which keeps
+// them pixel-aligned with the image no matter how the viewfinder is scaled.
+
+pub fn blend(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let p = image.get_pixel_mut(x as u32, y as u32);
+    let a = color.0[3] as u32;
+    for c in 0..3 {
+        p.0[c] = ((color.0[c] as u32 * a + p.0[c] as u32 * (255 - a)) / 255) as u8;
+    }
+}
+
+pub fn dot(image: &mut RgbaImage, x: f32, y: f32, radius: f32, color: Rgba<u8>) {
+    let r = radius.max(0.5);
+    let (x0, x1) = ((x - r).floor() as i64, (x + r).ceil() as i64);
+    let (y0, y1) = ((y - r).floor() as i64, (y + r).ceil() as i64);
+    for py in y0..=y1 {
+        for px in x0..=x1 {
+            let (dx, dy) = (px as f32 - x, py as f32 - y);
+            if dx * dx + dy * dy <= r * r {
+                blend(image, px, py, color);
+            }
+        }
+    }
+}
+
+pub fn line(image: &mut RgbaImage, from: (f32, f32), to: (f32, f32), width: f32, color: Rgba<u8>) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    let step = (width / 3.0).max(0.5);
+    let steps = (length / step).ceil().max(1.0) as usize;
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        dot(image, from.0 + dx * t, from.1 + dy * t, width / 2.0, color);
+    }
+}
+
+pub fn polygon(image: &mut RgbaImage, points: &[(f32, f32)], width: f32, color: Rgba<u8>) {
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        line(image, *a, b, width, color);
+    }
+}
+
+pub fn rect(image: &mut RgbaImage, (x, y, w, h): (f32, f32, f32, f32), width: f32, color: Rgba<u8>) {
+    polygon(image, &[(x, y), (x + w, y), (x + w, y + h), (x, y + h)], width, color);
+}
+
+pub fn fill_rect(image: &mut RgbaImage, (x, y, w, h): (f32, f32, f32, f32), color: Rgba<u8>) {
+    for py in y.round() as i64..(y + h).round() as i64 {
+        for px in x.round() as i64..(x + w).round() as i64 {
+            blend(image, px, py, color);
+        }
+    }
+}
+
+// 5x7 bitmap font for burned-in captions. Each row is 5 bits, most significant on the left.
+// Lowercase letters draw as uppercase; anything else unknown draws as '?'.
+const GLYPHS: &[(char, [u8; 7])] = &[
+    (' ', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    ('/', [0b00001, 0b00010, 0b00010, 0b00100, 0b01000, 0b01000, 0b10000]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+    ('\'', [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('&', [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101]),
+    ('#', [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010]),
+];
+
+fn glyph(c: char) -> [u8; 7] {
+    let c = c.to_ascii_uppercase();
+    GLYPHS.iter().find(|(g, _)| *g == c).or_else(|| GLYPHS.iter().find(|(g, _)| *g == '?')).unwrap().1
+}
+
+/// Width in pixels of `text` drawn at `scale` pixels per font dot.
+pub fn text_width(text: &str, scale: f32) -> f32 {
+    let n = text.chars().count() as f32;
+    if n == 0.0 { 0.0 } else { (n * 6.0 - 1.0) * scale }
+}
+
+pub fn text_height(scale: f32) -> f32 {
+    7.0 * scale
+}
+
+/// Draws `text` with its top left corner at `origin`.
+pub fn text(image: &mut RgbaImage, text: &str, origin: (f32, f32), scale: f32, color: Rgba<u8>) {
+    for (i, c) in text.chars().enumerate() {
+        let x0 = origin.0 + i as f32 * 6.0 * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..5 {
+                if bits & (0b10000 >> col) != 0 {
+                    let (x, y) = (x0 + col as f32 * scale, origin.1 + row as f32 * scale);
+                    fill_rect(image, (x, y, scale, scale), color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::BTreeMap;
+
+use image::{Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
+use pelican_ui::hardware::ImageSettings;
+use pelican_ui_std::EncodedImage;
+use serde::{Serialize, Deserialize};
+
+use crate::settings::CameraSetting;
+use crate::curves::{Histogram, ToneCurves};
+use crate::effects::EffectStack;
+use crate::service::Photo;
+use crate::draw;
+use crate::worker::Worker;
+
+// The editor previews on a copy no larger than this; saving renders the original.
+pub const PROXY_SIZE: u32 = 1280;
+// Histograms only need a rough count, so they're taken from a much smaller copy.
+const HISTOGRAM_SIZE: u32 = 160;
+pub const MAX_STRAIGHTEN: f32 = 45.0;
+// Crops narrower or shorter than this share of the image are ignored.
+const MIN_CROP: f32 = 0.05;
+const OUTSIDE_CROP: Rgba<u8> = Rgba([0, 0, 0, 150]);
+const CROP_EDGE: Rgba<u8> = Rgba([255, 255, 255, 230]);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EditTool {
+    Crop,
+    Rotate,
+    #[default]
+    Adjust,
+    Tone,
+    Effects,
+}
+
+impl EditTool {
+    pub const ALL: [EditTool; 5] = [EditTool::Crop, EditTool::Rotate, EditTool::Adjust, EditTool::Tone, EditTool::Effects];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditTool::Crop => "Crop",
+            EditTool::Rotate => "Rotate",
+            EditTool::Adjust => "Adjust",
+            EditTool::Tone => "Tone",
+            EditTool::Effects => "Effects",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AspectRatio {
+    #[default]
+    Free,
+    Square,
+    FourThree,
+    ThreeTwo,
+    SixteenNine,
+}
+
+impl AspectRatio {
+    pub const ALL: [AspectRatio; 5] = [AspectRatio::Free, AspectRatio::Square, AspectRatio::FourThree, AspectRatio::ThreeTwo, AspectRatio::SixteenNine];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AspectRatio::Free => "Free",
+            AspectRatio::Square => "1:1",
+            AspectRatio::FourThree => "4:3",
+            AspectRatio::ThreeTwo => "3:2",
+            AspectRatio::SixteenNine => "16:9",
+        }
+    }
+
+    /// Long side over short side; the drag decides which way round.
+    fn ratio(&self) -> Option<f32> {
+        match self {
+            AspectRatio::Free => None,
+            AspectRatio::Square => Some(1.0),
+            AspectRatio::FourThree => Some(4.0 / 3.0),
+            AspectRatio::ThreeTwo => Some(3.0 / 2.0),
+            AspectRatio::SixteenNine => Some(16.0 / 9.0),
+        }
+    }
+
+    /// The crop for a drag from `start` to `end`, both fractions of an image of `size`.
+    /// Fixed ratios keep the corner where the drag started and shrink the longer side to fit.
+    pub fn crop(&self, start: (f32, f32), end: (f32, f32), (width, height): (u32, u32)) -> Option<EditOp> {
+        let (dx, dy) = ((end.0 - start.0) * width as f32, (end.1 - start.1) * height as f32);
+        let (mut w, mut h) = (dx.abs(), dy.abs());
+        if let Some(ratio) = self.ratio() {
+            let target = if w >= h {ratio} else {1.0 / ratio};
+            match w / h.max(f32::EPSILON) > target {
+                true => w = h * target,
+                false => h = w / target,
+            }
+        }
+        let (w, h) = (w / width as f32, h / height as f32);
+        let x = if dx < 0.0 {start.0 - w} else {start.0};
+        let y = if dy < 0.0 {start.1 - h} else {start.1};
+        (w >= MIN_CROP && h >= MIN_CROP).then_some(EditOp::Crop(x, y, w, h))
+    }
+}
+
+/// One step of an edit, working on the result of the steps before it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EditOp {
+    /// x, y, width and height as fractions of the image.
+    Crop(f32, f32, f32, f32),
+    /// Quarter turns clockwise.
+    Rotate(u8),
+    /// Degrees clockwise. The image is enlarged just enough to hide the corners.
+    Straighten(f32),
+    Flip(Axis),
+    Adjust(CameraSetting, f32),
+    Tone(ToneCurves),
+    /// Laid over the finished frame, after the crop, wherever it sits among the steps.
+    Effects(EffectStack),
+}
+
+impl EditOp {
+    pub fn label(&self) -> String {
+        match self {
+            EditOp::Crop(..) => "Crop".to_string(),
+            EditOp::Rotate(turns) => format!("Rotate {}°", *turns as u32 * 90),
+            EditOp::Straighten(degrees) => format!("Straighten {degrees:+.1}°"),
+            EditOp::Flip(Axis::Horizontal) => "Flip Horizontal".to_string(),
+            EditOp::Flip(Axis::Vertical) => "Flip Vertical".to_string(),
+            EditOp::Adjust(setting, value) => format!("{} {}", setting.label(), setting.format(*value)),
+            EditOp::Tone(_) => "Tone Curve".to_string(),
+            EditOp::Effects(effects) => format!("Effects: {}", effects.label()),
+        }
+    }
+
+    /// Whether the step changes how the photo looks rather than how it's framed,
+    /// and so makes sense on other photos too.
+    pub fn is_look(&self) -> bool {
+        matches!(self, EditOp::Adjust(..) | EditOp::Tone(_) | EditOp::Effects(_))
+    }
+
+    // Where a crop made before this step ends up after it.
+    fn carry(&self, (x, y, w, h): (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+        match self {
+            EditOp::Rotate(turns) => (0..turns % 4).fold((x, y, w, h), |(x, y, w, h), _| (1.0 - y - h, x, h, w)),
+            EditOp::Flip(Axis::Horizontal) => (1.0 - x - w, y, w, h),
+            EditOp::Flip(Axis::Vertical) => (x, 1.0 - y - h, w, h),
+            _ => (x, y, w, h)
+        }
+    }
+
+    // Where a point, as fractions of an image of `size`, lands after this step, and the image's size after it.
+    fn place(&self, (x, y): (f32, f32), (w, h): (f32, f32)) -> ((f32, f32), (f32, f32)) {
+        match self {
+            EditOp::Crop(cx, cy, cw, ch) => (((x - cx) / cw, (y - cy) / ch), (w * cw, h * ch)),
+            EditOp::Rotate(turns) => (0..turns % 4).fold(((x, y), (w, h)), |((x, y), (w, h)), _| ((1.0 - y, x), (h, w))),
+            EditOp::Straighten(degrees) => (straightened((x, y), (w, h), *degrees, false), (w, h)),
+            EditOp::Flip(Axis::Horizontal) => ((1.0 - x, y), (w, h)),
+            EditOp::Flip(Axis::Vertical) => ((x, 1.0 - y), (w, h)),
+            _ => ((x, y), (w, h))
+        }
+    }
+
+    // Where a point after this step was before it, on an image of `size` before it.
+    fn unplace(&self, (x, y): (f32, f32), size: (f32, f32)) -> (f32, f32) {
+        match self {
+            EditOp::Crop(cx, cy, cw, ch) => (cx + x * cw, cy + y * ch),
+            EditOp::Rotate(turns) => (0..turns % 4).fold((x, y), |(x, y), _| (y, 1.0 - x)),
+            EditOp::Straighten(degrees) => straightened((x, y), size, *degrees, true),
+            EditOp::Flip(Axis::Horizontal) => (1.0 - x, y),
+            EditOp::Flip(Axis::Vertical) => (x, 1.0 - y),
+            _ => (x, y)
+        }
+    }
+
+    fn transform(&self, image: RgbaImage) -> RgbaImage {
+        match self {
+            EditOp::Crop(x, y, w, h) => {
+                let (width, height) = (image.width() as f32, image.height() as f32);
+                let left = ((x.clamp(0.0, 1.0) * width) as u32).min(image.width() - 1);
+                let top = ((y.clamp(0.0, 1.0) * height) as u32).min(image.height() - 1);
+                let w = ((w * width) as u32).clamp(1, image.width() - left);
+                let h = ((h * height) as u32).clamp(1, image.height() - top);
+                imageops::crop_imm(&image, left, top, w, h).to_image()
+            },
+            EditOp::Rotate(turns) => match turns % 4 {
+                1 => imageops::rotate90(&image),
+                2 => imageops::rotate180(&image),
+                3 => imageops::rotate270(&image),
+                _ => image
+            },
+            EditOp::Straighten(degrees) => straighten(&image, *degrees),
+            EditOp::Flip(Axis::Horizontal) => imageops::flip_horizontal(&image),
+            EditOp::Flip(Axis::Vertical) => imageops::flip_vertical(&image),
+            EditOp::Tone(curves) => curves.apply(image),
+            EditOp::Effects(effects) => effects.apply(image),
+            EditOp::Adjust(..) => image
+        }
+    }
+}
+
+/// The edits made to a photo, in the order they apply. The original pixels are never touched.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Edits(pub Vec<EditOp>);
+
+impl Edits {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds a step. Turns and flips stack up, cancelling out where they meet one just made;
+    /// a straighten, tone curve, effect stack or adjustment replaces the earlier one in place, and goes away when neutral.
+    pub fn push(&mut self, op: EditOp) {
+        match (&op, self.0.last()) {
+            (EditOp::Rotate(turns), Some(EditOp::Rotate(last))) => {
+                let turns = (turns + last) % 4;
+                self.0.pop();
+                if turns != 0 { self.0.push(EditOp::Rotate(turns)) }
+            },
+            (EditOp::Flip(axis), Some(EditOp::Flip(last))) if axis == last => { self.0.pop(); },
+            (EditOp::Rotate(_) | EditOp::Flip(_), _) => self.0.push(op),
+            (EditOp::Crop(..), _) => {
+                self.0.retain(|o| !matches!(o, EditOp::Crop(..)));
+                self.0.push(op);
+            },
+            (EditOp::Straighten(degrees), _) => {
+                let neutral = *degrees == 0.0;
+                let existing = self.0.iter().position(|o| matches!(o, EditOp::Straighten(_)));
+                self.replace(existing, op, neutral);
+            },
+            (EditOp::Tone(curves), _) => {
+                let neutral = curves.is_identity();
+                let existing = self.0.iter().position(|o| matches!(o, EditOp::Tone(_)));
+                self.replace(existing, op, neutral);
+            },
+            (EditOp::Effects(effects), _) => {
+                let neutral = effects.is_empty();
+                let existing = self.0.iter().position(|o| matches!(o, EditOp::Effects(_)));
+                self.replace(existing, op, neutral);
+            },
+            (EditOp::Adjust(setting, value), _) => {
+                let (setting, neutral) = (*setting, *value == setting.spec().default);
+                let existing = self.0.iter().position(|o| matches!(o, EditOp::Adjust(s, _) if *s == setting));
+                self.replace(existing, op, neutral);
+            },
+        }
+    }
+
+    fn replace(&mut self, existing: Option<usize>, op: EditOp, neutral: bool) {
+        match (existing, neutral) {
+            (Some(i), true) => { self.0.remove(i); },
+            (Some(i), false) => self.0[i] = op,
+            (None, false) => self.0.push(op),
+            (None, true) => {}
+        }
+    }
+
+    /// Just the adjustments, tone curve and effects.
+    pub fn look(&self) -> Edits {
+        Edits(self.0.iter().filter(|o| o.is_look()).cloned().collect())
+    }
+
+    /// Just the crop, turns, straightening and flips.
+    pub fn framing(&self) -> Edits {
+        Edits(self.0.iter().filter(|o| !o.is_look()).cloned().collect())
+    }
+
+    /// These edits with the look of `other` in place of their own, keeping the crop, turns and flips.
+    pub fn with_look(&self, other: &Edits) -> Edits {
+        let mut edits = self.framing();
+        other.look().0.into_iter().for_each(|op| edits.push(op));
+        edits
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        if i < self.0.len() { self.0.remove(i); }
+    }
+
+    pub fn crop(&self) -> Option<EditOp> {
+        self.0.iter().find(|o| matches!(o, EditOp::Crop(..))).cloned()
+    }
+
+    /// Moves the crop to the end, carried through any turns and flips made after it,
+    /// so it can be redrawn over everything else.
+    pub fn crop_last(&mut self) {
+        let Some(i) = self.0.iter().position(|o| matches!(o, EditOp::Crop(..))) else { return };
+        let EditOp::Crop(x, y, w, h) = self.0.remove(i) else { return };
+        let (x, y, w, h) = self.0[i..].iter().fold((x, y, w, h), |rect, op| op.carry(rect));
+        self.0.push(EditOp::Crop(x, y, w, h));
+    }
+
+    /// The same edits without the crop.
+    pub fn uncropped(&self) -> Edits {
+        Edits(self.0.iter().filter(|o| !matches!(o, EditOp::Crop(..))).cloned().collect())
+    }
+
+    /// The same edits up to, and not including, the tone curve. Effects come after it when rendered, so they're left out.
+    pub fn before_tone(&self) -> Edits {
+        Edits(self.0.iter().take_while(|o| !matches!(o, EditOp::Tone(_))).filter(|o| !matches!(o, EditOp::Effects(_))).cloned().collect())
+    }
+
+    pub fn tone(&self) -> ToneCurves {
+        self.0.iter().find_map(|o| match o {
+            EditOp::Tone(curves) => Some(curves.clone()),
+            _ => None
+        }).unwrap_or_default()
+    }
+
+    pub fn effects(&self) -> EffectStack {
+        self.0.iter().find_map(|o| match o {
+            EditOp::Effects(effects) => Some(effects.clone()),
+            _ => None
+        }).unwrap_or_default()
+    }
+
+    pub fn value(&self, setting: CameraSetting) -> f32 {
+        self.0.iter().find_map(|o| match o {
+            EditOp::Adjust(s, value) if *s == setting => Some(*value),
+            _ => None
+        }).unwrap_or(setting.spec().default)
+    }
+
+    pub fn straighten(&self) -> f32 {
+        self.0.iter().find_map(|o| match o {
+            EditOp::Straighten(degrees) => Some(*degrees),
+            _ => None
+        }).unwrap_or(0.0)
+    }
+
+    /// Where a point on an original of `size`, as fractions of it, lands on the render.
+    pub fn place(&self, point: (f32, f32), size: (f32, f32)) -> (f32, f32) {
+        self.0.iter().fold((point, size), |(point, size), op| op.place(point, size)).0
+    }
+
+    /// Where a point on the render, as fractions of it, comes from on an original of `size`.
+    pub fn source(&self, point: (f32, f32), size: (f32, f32)) -> (f32, f32) {
+        let sizes = self.0.iter().scan(size, |size, op| {
+            let before = *size;
+            *size = op.place((0.0, 0.0), before).1;
+            Some(before)
+        }).collect::<Vec<_>>();
+        self.0.iter().zip(sizes).rev().fold(point, |point, (op, size)| op.unplace(point, size))
+    }
+
+    /// Applies every step to a copy of `original`.
+    pub fn render(&self, original: &RgbaImage) -> RgbaImage {
+        let mut image = original.clone();
+        // Neighbouring adjustments are applied together, in the camera's own order.
+        let mut pending = BTreeMap::new();
+        for op in &self.0 {
+            match op {
+                EditOp::Adjust(setting, value) => {
+                    pending.insert(*setting, *value);
+                    continue;
+                },
+                EditOp::Effects(_) => continue,
+                _ => {}
+            }
+            if !pending.is_empty() {
+                image = adjust(image, &std::mem::take(&mut pending));
+            }
+            image = op.transform(image);
+        }
+        let image = match pending.is_empty() {
+            true => image,
+            false => adjust(image, &pending)
+        };
+        self.effects().apply(image)
+    }
+}
+
+/// Edits copied from one photo to be pasted on others. Only their look is kept.
+#[derive(Clone, Debug, Default)]
+pub struct CopiedEdits(pub Option<Edits>);
+
+/// `image` shrunk to fit within `PROXY_SIZE`.
+pub fn proxy(image: &RgbaImage) -> RgbaImage {
+    shrink(image, PROXY_SIZE)
+}
+
+fn shrink(image: &RgbaImage, size: u32) -> RgbaImage {
+    let scale = size as f32 / image.width().max(image.height()) as f32;
+    match scale < 1.0 {
+        true => imageops::resize(image, (image.width() as f32 * scale) as u32, (image.height() as f32 * scale) as u32, FilterType::Triangle),
+        false => image.clone()
+    }
+}
+
+fn settings(values: &BTreeMap<CameraSetting, f32>) -> ImageSettings {
+    let mut settings = ImageSettings::default();
+    values.iter().for_each(|(setting, value)| setting.set(&mut settings, *value));
+    settings
+}
+
+fn adjust(image: RgbaImage, values: &BTreeMap<CameraSetting, f32>) -> RgbaImage {
+    process(image, &settings(values))
+}
+
+/// Mirrors the camera's own processing so an edit looks the same as shooting with the setting.
+pub fn process(mut image: RgbaImage, settings: &ImageSettings) -> RgbaImage {
+    let gains = match settings.temperature != 6500.0 {
+        true => settings.temperature_to_rgb_multipliers(),
+        false => [settings.white_balance_r, settings.white_balance_g, settings.white_balance_b]
+    };
+    let exposure = 2.0_f32.powf(settings.exposure);
+    let brightness = settings.brightness as f32;
+    let contrast = 1.0 + settings.contrast;
+    let saturation = 1.0 + settings.saturation;
+    let gamma = (settings.gamma != 2.2).then(|| 1.0 / settings.gamma);
+
+    image.pixels_mut().for_each(|p| {
+        let mut rgb: [f32; 3] = std::array::from_fn(|c| (p.0[c] as f32 * gains[c]).clamp(0.0, 255.0));
+        rgb = rgb.map(|c| (c * exposure).clamp(0.0, 255.0));
+        rgb = rgb.map(|c| (c + brightness).clamp(0.0, 255.0));
+        rgb = rgb.map(|c| ((c - 128.0) * contrast + 128.0).clamp(0.0, 255.0));
+        let gray = 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+        rgb = rgb.map(|c| (gray + (c - gray) * saturation).clamp(0.0, 255.0));
+        if let Some(inverse) = gamma {
+            rgb = rgb.map(|c| (255.0 * (c / 255.0).powf(inverse)).clamp(0.0, 255.0));
+        }
+        (0..3).for_each(|c| p.0[c] = rgb[c] as u8);
+    });
+    image
+}
+
+fn bilinear(image: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let (max_x, max_y) = (image.width() - 1, image.height() - 1);
+    let (x, y) = (x.clamp(0.0, max_x as f32), y.clamp(0.0, max_y as f32));
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(max_x), (y0 + 1).min(max_y));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let [a, b, c, d] = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)].map(|(x, y)| image.get_pixel(x, y).0.map(|v| v as f32));
+    Rgba(std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * fx;
+        let bottom = c[i] + (d[i] - c[i]) * fx;
+        (top + (bottom - top) * fy).round() as u8
+    }))
+}
+
+fn straighten(image: &RgbaImage, degrees: f32) -> RgbaImage {
+    let degrees = degrees.clamp(-MAX_STRAIGHTEN, MAX_STRAIGHTEN);
+    if degrees == 0.0 { return image.clone(); }
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    // Just enough zoom for the turned image to cover the whole frame.
+    let scale = cos + sin.abs() * (width / height).max(height / width);
+    let (cx, cy) = (width / 2.0, height / 2.0);
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        let sx = cx + (dx * cos + dy * sin) / scale - 0.5;
+        let sy = cy + (dy * cos - dx * sin) / scale - 0.5;
+        bilinear(image, sx, sy)
+    })
+}
+
+// A point, as fractions of an image of `size`, turned as `straighten` turns the pixels, or turned back.
+fn straightened((x, y): (f32, f32), (w, h): (f32, f32), degrees: f32, back: bool) -> (f32, f32) {
+    let degrees = degrees.clamp(-MAX_STRAIGHTEN, MAX_STRAIGHTEN);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let scale = cos + sin.abs() * (w / h).max(h / w);
+    let (dx, dy) = ((x - 0.5) * w, (y - 0.5) * h);
+    let (dx, dy) = match back {
+        true => ((dx * cos + dy * sin) / scale, (dy * cos - dx * sin) / scale),
+        false => (scale * (dx * cos - dy * sin), scale * (dx * sin + dy * cos)),
+    };
+    (0.5 + dx / w, 0.5 + dy / h)
+}
+
+/// A render of the preview, and the histogram copy with the first one.
+type Rendered = (RgbaImage, Option<RgbaImage>);
+
+/// A photo open in the editor. Changes are previewed on a reduced copy and only
+/// reach the photo when saved.
+#[derive(Debug)]
+pub struct EditSession {
+    pub photo: Photo,
+    pub edits: Edits,
+    pub tool: EditTool,
+    pub ratio: AspectRatio,
+    pub adjusting: CameraSetting,
+    /// The effect whose sliders are showing.
+    pub effect: usize,
+    /// A crop still being dragged out.
+    pub draft: Option<EditOp>,
+    /// The latest render of the reduced copy.
+    pub preview: Option<RgbaImage>,
+    /// A much smaller copy for the tone histogram, once the photo has been decoded.
+    small: Option<RgbaImage>,
+    worker: Worker<Edits, Rendered>,
+}
+
+impl EditSession {
+    pub fn new(photo: Photo) -> Self {
+        // The photo is only decoded on the worker, the first time it renders.
+        let encoded = photo.image.clone();
+        let mut decoded = None;
+        let worker = Worker::latest(move |edits: Edits| {
+            let first = decoded.is_none();
+            let proxy = decoded.get_or_insert_with(|| proxy(&EncodedImage::decode_rgba(&encoded)));
+            (edits.render(proxy), first.then(|| shrink(proxy, HISTOGRAM_SIZE)))
+        });
+        let edits = photo.edits.clone();
+        let mut session = EditSession {
+            photo, edits, tool: EditTool::default(), ratio: AspectRatio::default(), adjusting: CameraSetting::ALL[0], effect: 0,
+            draft: None, preview: None, small: None, worker
+        };
+        session.refresh();
+        session
+    }
+
+    /// Starts rendering the preview. While cropping it's drawn without the crop so the whole frame can be chosen from.
+    pub fn refresh(&mut self) {
+        let edits = match self.tool {
+            EditTool::Crop => self.edits.uncropped(),
+            _ => self.edits.clone()
+        };
+        self.worker.submit(edits);
+    }
+
+    /// Picks up a finished render, returning whether there was one.
+    pub fn poll(&mut self) -> bool {
+        let Some((rendered, small)) = self.worker.poll().pop() else { return false };
+        self.preview = Some(rendered);
+        if small.is_some() {
+            self.small = small;
+        }
+        true
+    }
+
+    /// A blank stand-in the size of the preview, shown until the first render arrives.
+    pub fn placeholder(&self) -> RgbaImage {
+        let (width, height) = self.photo.size;
+        let scale = (PROXY_SIZE as f32 / width.max(height)).min(1.0);
+        RgbaImage::new(((width * scale) as u32).max(1), ((height * scale) as u32).max(1))
+    }
+
+    /// The preview as it should be shown, with the crop marked out while cropping.
+    pub fn shown(&self) -> Option<RgbaImage> {
+        let mut image = self.preview.clone()?;
+        if self.tool != EditTool::Crop { return Some(image); }
+        if let Some(EditOp::Crop(x, y, w, h)) = self.draft.clone().or(self.edits.crop()) {
+            let (width, height) = (image.width() as f32, image.height() as f32);
+            let (left, top, right, bottom) = (x * width, y * height, (x + w) * width, (y + h) * height);
+            draw::fill_rect(&mut image, (0.0, 0.0, width, top), OUTSIDE_CROP);
+            draw::fill_rect(&mut image, (0.0, bottom, width, height - bottom), OUTSIDE_CROP);
+            draw::fill_rect(&mut image, (0.0, top, left, bottom - top), OUTSIDE_CROP);
+            draw::fill_rect(&mut image, (right, top, width - right, bottom - top), OUTSIDE_CROP);
+            draw::rect(&mut image, (left, top, right - left, bottom - top), 2.0, CROP_EDGE);
+        }
+        Some(image)
+    }
+
+    /// Histogram of what the tone curve works on, so levels are set against the photo as it reaches them.
+    pub fn tone_histogram(&self) -> Histogram {
+        self.small.as_ref().map(|small| Histogram::of(&self.edits.before_tone().render(small))).unwrap_or_default()
+    }
+
+    /// Frames a crop from a drag across the preview, in the chosen ratio.
+    pub fn crop(&self, start: (f32, f32), end: (f32, f32)) -> Option<EditOp> {
+        self.ratio.crop(start, end, self.preview.as_ref()?.dimensions())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_come_back_from_the_render_where_they_were_placed() {
+        let edits = Edits(vec![
+            EditOp::Rotate(1), EditOp::Straighten(12.0), EditOp::Crop(0.1, 0.2, 0.6, 0.5), EditOp::Flip(Axis::Horizontal),
+        ]);
+        let size = (4000.0, 3000.0);
+        for point in [(0.5, 0.5), (0.3, 0.6), (0.45, 0.4)] {
+            let (x, y) = edits.source(edits.place(point, size), size);
+            assert!((x - point.0).abs() < 1e-4 && (y - point.1).abs() < 1e-4, "{point:?} came back as {:?}", (x, y));
+        }
+    }
+
+    #[test]
+    fn placed_points_follow_the_pixels() {
+        // A dot rendered through the edits should land where its point is placed.
+        let size = (120, 80);
+        let point = (0.25, 0.3);
+        let mut original = RgbaImage::new(size.0, size.1);
+        draw::dot(&mut original, point.0 * size.0 as f32, point.1 * size.1 as f32, 3.0, Rgba([255, 255, 255, 255]));
+        let edits = Edits(vec![EditOp::Rotate(1), EditOp::Crop(0.1, 0.1, 0.8, 0.8), EditOp::Straighten(5.0), EditOp::Flip(Axis::Vertical)]);
+        let render = edits.render(&original);
+        let (x, y) = edits.place(point, (size.0 as f32, size.1 as f32));
+        let pixel = render.get_pixel((x * render.width() as f32) as u32, (y * render.height() as f32) as u32);
+        assert!(pixel.0[0] > 128, "{:?} is dark at {:?}", pixel, (x, y));
+    }
+}
@@ -0,0 +1,384 @@
+use image::{Rgba, RgbaImage};
+use serde::{Serialize, Deserialize};
+
+// Sizes are given per thousand pixels of the image's long edge, so a proxy previews
+// the same look the full resolution render gets.
+const PER_MILLE: f32 = 1000.0;
+// Clarity is local contrast over a wide radius, in the same units.
+const CLARITY_RADIUS: f32 = 20.0;
+// How far past its edge a vignette or tilt-shift band takes to fade in, as a share of the frame.
+const VIGNETTE_FEATHER: f32 = 0.5;
+const TILT_SHIFT_FEATHER: f32 = 0.15;
+// Grain at full strength moves a pixel by up to this many levels.
+const GRAIN_LEVELS: f32 = 64.0;
+
+/// A slider an effect is set with.
+#[derive(Clone, Copy, Debug)]
+pub struct Param {
+    pub label: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+}
+
+impl Param {
+    const fn new(label: &'static str, min: f32, max: f32, default: f32) -> Self {
+        Param { label, min, max, default }
+    }
+
+    pub fn position(&self, value: f32) -> f32 {
+        (value - self.min) / (self.max - self.min) * 100.0
+    }
+
+    pub fn value(&self, position: f32) -> f32 {
+        self.min + (self.max - self.min) * position.clamp(0.0, 100.0) / 100.0
+    }
+
+    pub fn format(&self, value: f32) -> String {
+        match self.max - self.min > 10.0 {
+            true => format!("{value:.0}"),
+            false => format!("{value:.1}")
+        }
+    }
+}
+
+const VIGNETTE: [Param; 3] = [
+    Param::new("Amount", -100.0, 100.0, -40.0),
+    Param::new("Midpoint", 0.0, 100.0, 50.0),
+    Param::new("Roundness", -100.0, 100.0, 0.0),
+];
+
+const GRAIN: [Param; 2] = [
+    Param::new("Size", 0.5, 5.0, 1.0),
+    Param::new("Strength", 0.0, 100.0, 30.0),
+];
+
+const SHARPEN: [Param; 3] = [
+    Param::new("Amount", 0.0, 300.0, 80.0),
+    Param::new("Radius", 0.2, 5.0, 1.0),
+    Param::new("Threshold", 0.0, 50.0, 2.0),
+];
+
+const BLUR: [Param; 1] = [
+    Param::new("Radius", 0.5, 50.0, 5.0),
+];
+
+const TILT_SHIFT: [Param; 3] = [
+    Param::new("Position", 0.0, 100.0, 50.0),
+    Param::new("Focus Width", 0.0, 100.0, 25.0),
+    Param::new("Blur", 0.5, 50.0, 8.0),
+];
+
+const CLARITY: [Param; 1] = [
+    Param::new("Amount", -100.0, 100.0, 30.0),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectKind {
+    Vignette,
+    Grain,
+    Sharpen,
+    Blur,
+    TiltShift,
+    Clarity,
+}
+
+impl EffectKind {
+    pub const ALL: [EffectKind; 6] = [EffectKind::Vignette, EffectKind::Grain, EffectKind::Sharpen, EffectKind::Blur, EffectKind::TiltShift, EffectKind::Clarity];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EffectKind::Vignette => "Vignette",
+            EffectKind::Grain => "Grain",
+            EffectKind::Sharpen => "Sharpen",
+            EffectKind::Blur => "Blur",
+            EffectKind::TiltShift => "Tilt-Shift",
+            EffectKind::Clarity => "Clarity",
+        }
+    }
+
+    pub fn params(&self) -> &'static [Param] {
+        match self {
+            EffectKind::Vignette => &VIGNETTE,
+            EffectKind::Grain => &GRAIN,
+            EffectKind::Sharpen => &SHARPEN,
+            EffectKind::Blur => &BLUR,
+            EffectKind::TiltShift => &TILT_SHIFT,
+            EffectKind::Clarity => &CLARITY,
+        }
+    }
+}
+
+/// One effect with its settings. Grain keeps its seed so the same photo always gets the same grain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Effect {
+    pub kind: EffectKind,
+    pub values: Vec<f32>,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Effect {
+    pub fn new(kind: EffectKind) -> Self {
+        Effect { kind, values: kind.params().iter().map(|p| p.default).collect(), seed: new_seed() }
+    }
+
+    pub fn value(&self, param: usize) -> f32 {
+        let spec = self.kind.params()[param];
+        self.values.get(param).copied().unwrap_or(spec.default).clamp(spec.min, spec.max)
+    }
+
+    pub fn set(&mut self, param: usize, value: f32) {
+        let Some(spec) = self.kind.params().get(param) else { return };
+        self.values.resize(self.kind.params().len(), 0.0);
+        self.values[param] = value.clamp(spec.min, spec.max);
+    }
+
+    pub fn reseed(&mut self) {
+        self.seed = new_seed();
+    }
+
+    pub fn apply(&self, image: RgbaImage) -> RgbaImage {
+        let scale = image.width().max(image.height()) as f32 / PER_MILLE;
+        let v = |i: usize| self.value(i);
+        match self.kind {
+            EffectKind::Vignette => vignette(image, v(0) / 100.0, v(1) / 100.0, v(2) / 100.0),
+            EffectKind::Grain => grain(image, (v(0) * scale).max(1.0), v(1) / 100.0, self.seed),
+            EffectKind::Sharpen => {
+                let blurred = gaussian(&image, v(1) * scale);
+                unsharp(image, &blurred, |_| v(0) / 100.0, v(2))
+            },
+            EffectKind::Blur => {
+                let blurred = gaussian(&image, v(0) * scale);
+                mix(image, &blurred, |_, _| 1.0)
+            },
+            EffectKind::TiltShift => {
+                let blurred = gaussian(&image, v(2) * scale);
+                let (center, half) = (v(0) / 100.0, v(1) / 200.0);
+                let height = image.height() as f32;
+                mix(image, &blurred, |_, y| smoothstep(half, half + TILT_SHIFT_FEATHER, ((y as f32 + 0.5) / height - center).abs()))
+            },
+            EffectKind::Clarity => {
+                let blurred = gaussian(&image, CLARITY_RADIUS * scale);
+                // Mostly the midtones, so shadows and highlights don't block up.
+                unsharp(image, &blurred, |luma| v(0) / 100.0 * (1.0 - (2.0 * luma / 255.0 - 1.0).powi(2)), 0.0)
+            },
+        }
+    }
+}
+
+/// Effects in the order they're applied, each working on the result of the ones before it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EffectStack(pub Vec<Effect>);
+
+impl EffectStack {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn label(&self) -> String {
+        self.0.iter().map(|e| e.kind.label()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Moves the effect at `i` one place earlier.
+    pub fn raise(&mut self, i: usize) {
+        if i > 0 && i < self.0.len() { self.0.swap(i - 1, i); }
+    }
+
+    pub fn apply(&self, image: RgbaImage) -> RgbaImage {
+        self.0.iter().fold(image, |image, effect| effect.apply(image))
+    }
+}
+
+fn new_seed() -> u64 {
+    uuid::Uuid::new_v4().as_u64_pair().0
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0).max(f32::EPSILON)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn luma(rgb: [f32; 3]) -> f32 {
+    0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2]
+}
+
+fn rgb(p: &Rgba<u8>) -> [f32; 3] {
+    [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32]
+}
+
+fn store(p: &mut Rgba<u8>, rgb: [f32; 3]) {
+    (0..3).for_each(|c| p.0[c] = rgb[c].round().clamp(0.0, 255.0) as u8);
+}
+
+/// Darkens the corners for a negative `amount` or lightens them for a positive one.
+/// `roundness` runs from following the frame's shape at -1 to a circle at 1.
+fn vignette(mut image: RgbaImage, amount: f32, midpoint: f32, roundness: f32) -> RgbaImage {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let round = (roundness + 1.0) / 2.0;
+    // A circle measures both axes against the long side.
+    let (sx, sy) = (1.0 + (width / width.max(height) - 1.0) * round, 1.0 + (height / width.max(height) - 1.0) * round);
+    let power = 2.0 + (1.0 - round) * 4.0;
+    image.enumerate_pixels_mut().for_each(|(x, y, p)| {
+        let u = ((x as f32 + 0.5) / width * 2.0 - 1.0).abs() * sx;
+        let v = ((y as f32 + 0.5) / height * 2.0 - 1.0).abs() * sy;
+        let distance = (u.powf(power) + v.powf(power)).powf(1.0 / power);
+        let t = smoothstep(midpoint, midpoint + VIGNETTE_FEATHER, distance) * amount.abs();
+        let color = rgb(p).map(|c| match amount < 0.0 {
+            true => c * (1.0 - t),
+            false => c + (255.0 - c) * t
+        });
+        store(p, color);
+    });
+    image
+}
+
+fn hash(seed: u64, x: u32, y: u32) -> f32 {
+    // splitmix64 over the seed and cell, mapped to -1..1.
+    let mut z = seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+/// Monochrome value noise on a grid of `size` pixel cells, strongest in the midtones.
+fn grain(mut image: RgbaImage, size: f32, strength: f32, seed: u64) -> RgbaImage {
+    if strength <= 0.0 { return image; }
+    image.enumerate_pixels_mut().for_each(|(x, y, p)| {
+        let (gx, gy) = (x as f32 / size, y as f32 / size);
+        let (cx, cy) = (gx.floor() as u32, gy.floor() as u32);
+        let (fx, fy) = (smoothstep(0.0, 1.0, gx.fract()), smoothstep(0.0, 1.0, gy.fract()));
+        let top = hash(seed, cx, cy) * (1.0 - fx) + hash(seed, cx + 1, cy) * fx;
+        let bottom = hash(seed, cx, cy + 1) * (1.0 - fx) + hash(seed, cx + 1, cy + 1) * fx;
+        let noise = top * (1.0 - fy) + bottom * fy;
+        let color = rgb(p);
+        let weight = 1.0 - 0.5 * (2.0 * luma(color) / 255.0 - 1.0).powi(2);
+        let offset = noise * strength * GRAIN_LEVELS * weight;
+        store(p, color.map(|c| c + offset));
+    });
+    image
+}
+
+/// Adds back the detail `blurred` lost, by `amount` for a pixel's luma, where the difference is above `threshold` levels.
+fn unsharp(mut image: RgbaImage, blurred: &[[f32; 3]], amount: impl Fn(f32) -> f32, threshold: f32) -> RgbaImage {
+    image.pixels_mut().zip(blurred).for_each(|(p, soft)| {
+        let color = rgb(p);
+        let l = luma(color);
+        // Working on luma alone keeps color fringes out of the edges.
+        let detail = l - luma(*soft);
+        if detail.abs() <= threshold { return; }
+        let boost = detail * amount(l);
+        store(p, color.map(|c| c + boost));
+    });
+    image
+}
+
+/// Blends toward `other` by the weight `at` gives each pixel.
+fn mix(mut image: RgbaImage, other: &[[f32; 3]], at: impl Fn(u32, u32) -> f32) -> RgbaImage {
+    let width = image.width() as usize;
+    image.enumerate_pixels_mut().for_each(|(x, y, p)| {
+        let t = at(x, y);
+        if t <= 0.0 { return; }
+        let (color, target) = (rgb(p), other[y as usize * width + x as usize]);
+        store(p, std::array::from_fn(|c| color[c] + (target[c] - color[c]) * t));
+    });
+    image
+}
+
+/// Approximates a Gaussian blur with three box blurs, so the cost doesn't grow with `sigma`.
+fn gaussian(image: &RgbaImage, sigma: f32) -> Vec<[f32; 3]> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut pixels = image.pixels().map(rgb).collect::<Vec<_>>();
+    if sigma < 0.5 { return pixels; }
+    for size in boxes(sigma, 3) {
+        let radius = (size - 1) / 2;
+        pixels = box_blur(&pixels, width, height, radius, true);
+        pixels = box_blur(&pixels, width, height, radius, false);
+    }
+    pixels
+}
+
+// Box widths whose repeated passes add up to a Gaussian of `sigma`.
+fn boxes(sigma: f32, passes: usize) -> Vec<usize> {
+    let n = passes as f32;
+    let ideal = (12.0 * sigma * sigma / n + 1.0).sqrt();
+    let mut lower = ideal.floor() as usize;
+    if lower.is_multiple_of(2) { lower -= 1; }
+    let l = lower as f32;
+    let m = ((12.0 * sigma * sigma - n * l * l - 4.0 * n * l - 3.0 * n) / (-4.0 * l - 4.0)).round() as usize;
+    (0..passes).map(|i| if i < m {lower} else {lower + 2}).collect()
+}
+
+fn box_blur(pixels: &[[f32; 3]], width: usize, height: usize, radius: usize, horizontal: bool) -> Vec<[f32; 3]> {
+    if radius == 0 { return pixels.to_vec(); }
+    let (lines, length) = if horizontal {(height, width)} else {(width, height)};
+    let at = |line: usize, i: usize| if horizontal {line * width + i} else {i * width + line};
+    let span = (2 * radius + 1) as f32;
+    let mut out = vec![[0.0; 3]; pixels.len()];
+    for line in 0..lines {
+        // The running sum clamps at the edges, repeating the first and last pixels.
+        let sample = |i: isize| pixels[at(line, i.clamp(0, length as isize - 1) as usize)];
+        let mut sum = [0.0; 3];
+        (-(radius as isize)..=radius as isize).for_each(|i| (0..3).for_each(|c| sum[c] += sample(i)[c]));
+        for i in 0..length {
+            out[at(line, i)] = sum.map(|s| s / span);
+            let (enter, leave) = (sample(i as isize + radius as isize + 1), sample(i as isize - radius as isize));
+            (0..3).for_each(|c| sum[c] += enter[c] - leave[c]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(size: u32) -> RgbaImage {
+        RgbaImage::from_pixel(size, size, Rgba([128, 128, 128, 255]))
+    }
+
+    fn grain_with(seed: u64) -> RgbaImage {
+        Effect { seed, ..Effect::new(EffectKind::Grain) }.apply(gray(64))
+    }
+
+    #[test]
+    fn grain_follows_its_seed() {
+        assert_eq!(grain_with(7), grain_with(7));
+        assert_ne!(grain_with(7), grain_with(8));
+        assert_ne!(grain_with(7), gray(64));
+    }
+
+    #[test]
+    fn box_blur_averages_its_neighbours() {
+        // A single bright pixel spreads evenly over the three across it.
+        let mut pixels = vec![[0.0; 3]; 5];
+        pixels[2] = [90.0; 3];
+        let blurred = box_blur(&pixels, 5, 1, 1, true);
+        assert_eq!(blurred.iter().map(|p| p[0]).collect::<Vec<_>>(), [0.0, 30.0, 30.0, 30.0, 0.0]);
+        // Vertically a single row is untouched, as its edges repeat.
+        assert_eq!(box_blur(&pixels, 5, 1, 1, false), pixels);
+    }
+
+    #[test]
+    fn gaussian_blur_keeps_flat_areas_and_softens_edges() {
+        assert!(gaussian(&gray(16), 3.0).iter().all(|p| p.iter().all(|c| (c - 128.0).abs() < 1e-3)));
+        let edge = RgbaImage::from_fn(32, 1, |x, _| if x < 16 {Rgba([0, 0, 0, 255])} else {Rgba([255, 255, 255, 255])});
+        let blurred = gaussian(&edge, 2.0);
+        assert!(blurred.windows(2).all(|w| w[0][0] <= w[1][0] + 1e-3));
+        assert!(blurred[15][0] > 0.0 && blurred[16][0] < 255.0);
+        assert!(blurred[0][0] < 1e-3 && blurred[31][0] > 254.999);
+        // Too small a blur to matter leaves the pixels as they are.
+        assert_eq!(gaussian(&edge, 0.2), edge.pixels().map(rgb).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn boxes_add_up_to_the_sigma() {
+        for sigma in [1.0, 2.5, 8.0, 30.0] {
+            let widths = boxes(sigma, 3);
+            assert!(widths.iter().all(|w| w % 2 == 1));
+            let variance = widths.iter().map(|w| ((w * w) as f32 - 1.0) / 12.0).sum::<f32>();
+            assert!((variance.sqrt() - sigma).abs() < 0.5 + sigma * 0.1, "{sigma}: {widths:?}");
+        }
+    }
+}
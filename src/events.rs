@@ -2,6 +2,36 @@ use pelican_ui::events::Event;
 use pelican_ui::{resources, Context};
 use pelican_ui::drawable::Image;
 
+use uuid::Uuid;
+
+use crate::service::Photo;
+use crate::modes::CaptureMode;
+use crate::scanner::ScannedCode;
+use crate::document::ScanFilter;
+use crate::settings::CameraSetting;
+use crate::edit::{AspectRatio, EditOp, EditTool};
+use crate::curves::{Channel, LevelPoint, ToneCurves};
+use crate::effects::EffectKind;
+use crate::markup::MarkupTool;
+use crate::compare::CompareMode;
+use crate::timeline::{RollSort, RollGrouping};
+
+/// Changes to the active stop motion project, applied by `StopMotionPage`.
+#[derive(Debug, Clone)]
+pub enum StopMotionEditEvent {
+    Shift(usize, isize),
+    Remove(usize),
+    Onion(usize),
+    Select(usize),
+    NewProject,
+}
+
+impl Event for StopMotionEditEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TakePhotoEvent;
 
@@ -12,7 +42,7 @@ impl Event for TakePhotoEvent {
 }
 
 #[derive(Debug, Clone)]
-pub struct SelectImageEvent(pub String, pub (f32, f32));
+pub struct SelectImageEvent(pub Photo);
 
 impl Event for SelectImageEvent {
     fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
@@ -44,7 +74,7 @@ impl Event for OpenSettingsEvent {
 }
 
 #[derive(Debug, Clone)]
-pub struct SettingsSelect(pub String);
+pub struct SettingsSelect(pub CameraSetting);
 
 impl Event for SettingsSelect {
     fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
@@ -52,21 +82,354 @@ impl Event for SettingsSelect {
     }
 }
 
+/// Sets a camera setting to a value in its own units.
 #[derive(Debug, Clone)]
-pub enum SetCameraSetting {
-    Brightness(f32),
-    Contrast(f32),
-    Saturation(f32),
-    Gamma(f32),
-    Exposure(f32),
-    Temperature(f32),
-    WhiteBalanceR(f32),
-    WhiteBalanceG(f32),
-    WhiteBalanceB(f32),
-}
+pub struct SetCameraSetting(pub CameraSetting, pub f32);
 
 impl Event for SetCameraSetting {
     fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
         children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct DeletePhotosEvent(pub Vec<Uuid>);
+
+impl Event for DeletePhotosEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReviewLowQualityEvent(pub bool);
+
+impl Event for ReviewLowQualityEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SetCaptureModeEvent(pub CaptureMode);
+
+impl Event for SetCaptureModeEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CodesScannedEvent(pub Vec<ScannedCode>);
+
+impl Event for CodesScannedEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CloseSheetEvent;
+
+impl Event for CloseSheetEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentPageAddedEvent;
+
+impl Event for DocumentPageAddedEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SetScanFilterEvent(pub ScanFilter);
+
+impl Event for SetScanFilterEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveDocumentEvent;
+
+impl Event for SaveDocumentEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscardDocumentEvent;
+
+impl Event for DiscardDocumentEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenMotionSettingsEvent;
+
+impl Event for OpenMotionSettingsEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MotionSettingsChangedEvent;
+
+impl Event for MotionSettingsChangedEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenLongExposureEvent;
+
+impl Event for OpenLongExposureEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenPhotoBoothEvent;
+
+impl Event for OpenPhotoBoothEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Camera presets. Saving and applying need the camera, so `CameraHome` handles them.
+#[derive(Debug, Clone)]
+pub enum PresetEvent {
+    /// Shows the presets sheet, with an optional message such as an import error.
+    Open(Option<String>),
+    /// Saves the current settings under the name typed into the presets sheet.
+    Save,
+    Apply(usize),
+}
+
+impl Event for PresetEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Puts every camera setting, the capture mode and live photos back to their defaults.
+#[derive(Debug, Clone)]
+pub struct ResetSettingsEvent;
+
+impl Event for ResetSettingsEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Shows the filters sheet, with an optional message such as an import error.
+#[derive(Debug, Clone)]
+pub struct OpenFiltersEvent(pub Option<String>);
+
+impl Event for OpenFiltersEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum HistoryEvent {
+    Undo,
+    Redo,
+    /// Shows the camera with every setting at its default while held.
+    Compare(bool),
+}
+
+impl Event for HistoryEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Moves the photo viewer this many photos along, back for negative.
+#[derive(Debug, Clone)]
+pub struct ShowNeighbourEvent(pub isize);
+
+impl Event for ShowNeighbourEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// The before and after view, on the viewfinder or a photo.
+#[derive(Debug, Clone)]
+pub enum CompareEvent {
+    /// Opens the view in a mode, or closes it with `None`.
+    Mode(Option<CompareMode>),
+    /// Moves the split, as a fraction of the width.
+    Split(f32),
+    /// Shows just the original while held.
+    Hold(bool),
+}
+
+impl Event for CompareEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// How the library is sorted and grouped.
+#[derive(Debug, Clone)]
+pub enum RollViewEvent {
+    Sort(RollSort),
+    Grouping(RollGrouping),
+}
+
+impl Event for RollViewEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// The info sheet on a photo.
+#[derive(Debug, Clone)]
+pub enum InfoEvent {
+    Open,
+    /// Copies the sheet as text, with the title and caption as currently typed.
+    Copy,
+    /// Saves the title and caption and closes the sheet.
+    Done,
+    Cancel,
+}
+
+impl Event for InfoEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Changes made in the photo editor, applied by `EditPhoto`.
+#[derive(Debug, Clone)]
+pub enum EditPhotoEvent {
+    Tool(EditTool),
+    Ratio(AspectRatio),
+    /// A drag across the photo from one point to another, as fractions of it, and whether it has ended.
+    Drag((f32, f32), (f32, f32), bool),
+    Apply(EditOp),
+    /// Drops the step at this position.
+    Remove(usize),
+    /// Drops every step, back to the original photo.
+    Revert,
+    /// Copies the look of the edits, to paste onto other photos.
+    Copy,
+    Paste,
+    Save,
+}
+
+impl Event for EditPhotoEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Shows the tone curve and levels sheet.
+#[derive(Debug, Clone)]
+pub struct OpenToneEvent;
+
+impl Event for OpenToneEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Changes from the tone editor. Only `Set` carries the result; whoever shows the editor applies it.
+#[derive(Debug, Clone)]
+pub enum ToneEvent {
+    Channel(Channel),
+    Level(LevelPoint, f32),
+    Set(ToneCurves),
+}
+
+impl Event for ToneEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Changes to the photo editor's effect stack, by position in the stack.
+#[derive(Debug, Clone)]
+pub enum EffectEvent {
+    Add(EffectKind),
+    Select(usize),
+    /// Effect, parameter and its new value.
+    Set(usize, usize, f32),
+    /// Moves an effect one place earlier, so it's applied sooner.
+    Raise(usize),
+    Remove(usize),
+    Reseed(usize),
+}
+
+impl Event for EffectEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Drawing annotations over a photo in `ViewPhoto`.
+#[derive(Debug, Clone)]
+pub enum MarkupEvent {
+    Open,
+    Tool(MarkupTool),
+    /// Indexes into the markup colors and widths.
+    Color(usize),
+    Width(usize),
+    /// A drag across the photo from one point to another, as fractions of it, and whether it has ended.
+    Drag((f32, f32), (f32, f32), bool),
+    Undo,
+    Redo,
+    Clear,
+    Cancel,
+    Done,
+}
+
+impl Event for MarkupEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Picking several photos in the camera roll, and pasting copied edits onto them.
+#[derive(Debug, Clone)]
+pub enum RollSelectionEvent {
+    Begin,
+    Toggle(Uuid),
+    All,
+    End,
+    PasteEdits,
+    /// Stops a paste that's still working through the photos.
+    CancelPaste,
+}
+
+impl Event for RollSelectionEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
+
+/// Shows the next page of the roll in the green screen background picker.
+#[derive(Debug, Clone)]
+pub struct MoreBackgroundsEvent;
+
+impl Event for MoreBackgroundsEvent {
+    fn pass(self: Box<Self>, _ctx: &mut Context, children: Vec<((f32, f32), (f32, f32))>) -> Vec<Option<Box<dyn Event>>> {
+        children.into_iter().map(|_| Some(self.clone() as Box<dyn Event>)).collect()
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::{Arc, Mutex};
+
+use image::{Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
+use serde::{Serialize, Deserialize};
+
+use crate::storage;
+use crate::worker::Worker;
+
+pub const DURATIONS: [f32; 6] = [1.0, 2.0, 4.0, 8.0, 15.0, 30.0];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendMode {
+    /// Averages frames: smooths water and removes people passing through.
+    #[default]
+    Mean,
+    /// Keeps the brightest value per pixel: light trails and star trails.
+    Lighten,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 2] = [BlendMode::Mean, BlendMode::Lighten];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BlendMode::Mean => "Mean",
+            BlendMode::Lighten => "Lighten",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LongExposure {
+    /// Seconds of frames to combine.
+    pub duration: f32,
+    pub mode: BlendMode,
+}
+
+impl Default for LongExposure {
+    fn default() -> Self {
+        LongExposure { duration: 4.0, mode: BlendMode::Mean }
+    }
+}
+
+impl LongExposure {
+    const FILE: &'static str = "long_exposure.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+}
+
+/// What a long exposure photo was made from, saved alongside it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ExposureRecord {
+    pub duration: f32,
+    pub mode: BlendMode,
+    pub frames: usize,
+}
+
+impl ExposureRecord {
+    pub fn label(&self) -> String {
+        format!("Long exposure · {:.0}s · {} · {} frames", self.duration, self.mode.label(), self.frames)
+    }
+}
+
+/// Running combination of every frame pushed so far.
+#[derive(Debug)]
+pub struct FrameStack {
+    mode: BlendMode,
+    size: (u32, u32),
+    sum: Vec<u32>,
+    max: Vec<u8>,
+    count: usize,
+}
+
+impl FrameStack {
+    pub fn new(mode: BlendMode) -> Self {
+        FrameStack { mode, size: (0, 0), sum: Vec::new(), max: Vec::new(), count: 0 }
+    }
+
+    pub fn count(&self) -> usize { self.count }
+
+    /// Frames that don't match the size of the first one are skipped.
+    pub fn push(&mut self, frame: &RgbaImage) {
+        if self.count == 0 {
+            self.size = frame.dimensions();
+            self.sum = vec![0; frame.as_raw().len()];
+            self.max = vec![0; frame.as_raw().len()];
+        } else if frame.dimensions() != self.size {
+            return;
+        }
+        match self.mode {
+            BlendMode::Mean => self.sum.iter_mut().zip(frame.as_raw()).for_each(|(s, v)| *s += *v as u32),
+            BlendMode::Lighten => self.max.iter_mut().zip(frame.as_raw()).for_each(|(m, v)| *m = (*m).max(*v)),
+        }
+        self.count += 1;
+    }
+
+    pub fn result(&self) -> Option<RgbaImage> {
+        if self.count == 0 {
+            return None;
+        }
+        let raw = match self.mode {
+            BlendMode::Mean => self.sum.iter().map(|s| ((*s as f32 / self.count as f32).round()) as u8).collect(),
+            BlendMode::Lighten => self.max.clone(),
+        };
+        let mut image = RgbaImage::from_raw(self.size.0, self.size.1, raw)?;
+        image.pixels_mut().for_each(|p: &mut Rgba<u8>| p.0[3] = 255);
+        Some(image)
+    }
+}
+
+/// Stacks an exposure's frames on a worker thread, alongside a copy at preview size
+/// that's handed back after each frame for the viewfinder. Frames that arrive while
+/// the last one is still being stacked are skipped.
+#[derive(Debug)]
+pub struct Stacker {
+    worker: Worker<RgbaImage, Option<RgbaImage>>,
+    stack: Arc<Mutex<FrameStack>>,
+    preview: Option<RgbaImage>,
+}
+
+impl Stacker {
+    pub fn new(mode: BlendMode, preview_width: u32) -> Self {
+        let stack = Arc::new(Mutex::new(FrameStack::new(mode)));
+        let full = stack.clone();
+        let mut small = FrameStack::new(mode);
+        let worker = Worker::new(move |frame: RgbaImage| {
+            let (w, h) = frame.dimensions();
+            let width = preview_width.min(w);
+            small.push(&imageops::resize(&frame, width, ((h as f32 / w as f32) * width as f32).round().max(1.0) as u32, FilterType::Nearest));
+            full.lock().unwrap().push(&frame);
+            small.result()
+        });
+        Stacker { worker, stack, preview: None }
+    }
+
+    pub fn push(&mut self, frame: &RgbaImage) {
+        self.worker.offer(|| frame.clone());
+    }
+
+    /// The newest preview-size result, if any frame has been stacked.
+    pub fn preview(&mut self) -> Option<&RgbaImage> {
+        if let Some(preview) = self.worker.poll().into_iter().flatten().last() {
+            self.preview = Some(preview);
+        }
+        self.preview.as_ref()
+    }
+
+    /// The full-size stack, to develop once the exposure is over.
+    pub fn finish(self) -> Arc<Mutex<FrameStack>> {
+        self.stack
+    }
+}
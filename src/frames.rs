@@ -0,0 +1,330 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use image::{GrayImage, RgbaImage};
+use image::imageops::{self, FilterType};
+use pelican_ui::hardware::Camera;
+
+use crate::worker::Worker;
+
+// Frames are scored on a downscaled luma copy so sensor noise at full
+// resolution doesn't dominate the Laplacian response.
+const SHARPNESS_WIDTH: u32 = 480;
+const LIVE_WIDTH: u32 = 360;
+
+pub const RING_CAPACITY: usize = 24;
+pub const PRE_ROLL: Duration = Duration::from_millis(400);
+pub const POST_ROLL: Duration = Duration::from_millis(150);
+
+#[derive(Clone, Debug, Default)]
+pub struct LivePhotos(pub bool);
+
+/// Anything that can hand the camera pipeline a fresh frame. `Camera` is the live
+/// source; `StillFrames` replays fixed images so scanning and detection can be
+/// driven without hardware.
+pub trait FrameSource: fmt::Debug {
+    fn next_frame(&mut self) -> Option<RgbaImage>;
+}
+
+impl FrameSource for Camera {
+    fn next_frame(&mut self) -> Option<RgbaImage> {
+        self.get_frame()
+    }
+}
+
+/// What to read frames from when there's no camera. Only builds with the
+/// `still-frames` feature have anything, the images in `ALBACORE_STILL_FRAMES`.
+pub fn fallback_source() -> Option<Box<dyn FrameSource + Send>> {
+    #[cfg(feature = "still-frames")]
+    return StillFrames::from_env().map(|s| Box::new(s) as Box<dyn FrameSource + Send>);
+    #[cfg(not(feature = "still-frames"))]
+    None
+}
+
+#[cfg(any(test, feature = "still-frames"))]
+#[derive(Debug, Default)]
+pub struct StillFrames(VecDeque<RgbaImage>);
+
+#[cfg(any(test, feature = "still-frames"))]
+impl StillFrames {
+    pub fn new(frames: Vec<RgbaImage>) -> Self {
+        StillFrames(frames.into())
+    }
+
+    #[cfg(feature = "still-frames")]
+    pub fn open<P: AsRef<std::path::Path>>(paths: &[P]) -> image::ImageResult<Self> {
+        let frames = paths.iter().map(|p| image::open(p).map(|i| i.to_rgba8())).collect::<Result<Vec<_>, _>>()?;
+        Ok(StillFrames::new(frames))
+    }
+
+    /// Comma separated image paths in `ALBACORE_STILL_FRAMES`.
+    #[cfg(feature = "still-frames")]
+    pub fn from_env() -> Option<Self> {
+        let paths = std::env::var("ALBACORE_STILL_FRAMES").ok()?;
+        let paths = paths.split(',').map(str::trim).filter(|p| !p.is_empty()).collect::<Vec<_>>();
+        StillFrames::open(&paths).ok().filter(|s| !s.0.is_empty())
+    }
+}
+
+// Replays its frames in a loop, like a camera pointed at a slideshow.
+#[cfg(any(test, feature = "still-frames"))]
+impl FrameSource for StillFrames {
+    fn next_frame(&mut self) -> Option<RgbaImage> {
+        let frame = self.0.pop_front()?;
+        self.0.push_back(frame.clone());
+        Some(frame)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimedFrame(pub Instant, pub RgbaImage);
+
+/// Makes a capture out of the sharpest frame around a press, at full size, and the
+/// live clip around it, at clip size. Runs on the ring's thread.
+pub type Finish<T> = Box<dyn FnOnce(RgbaImage, Vec<RgbaImage>) -> T + Send>;
+
+enum RingRequest<T> {
+    Frame(Instant, RgbaImage),
+    Capture(Instant, Finish<T>),
+    /// Finishes the captures whose post-roll has passed, for when frames stop coming.
+    Deadline(Instant),
+}
+
+/// The last few moments of frames, kept on a worker thread so scoring, picking and
+/// encoding a capture stay off the UI thread. Every frame is kept at clip size, but
+/// only those sharper than every frame since are kept at full size. Frames that arrive
+/// while the last one is still being scored are dropped, so a slow thread can't fall behind.
+#[derive(Debug)]
+pub struct FrameRing<T> {
+    worker: Worker<RingRequest<T>, Vec<Option<T>>>,
+    latest: Arc<Mutex<Option<RgbaImage>>>,
+    /// When each capture still being made was pressed, oldest first.
+    waiting: VecDeque<Instant>,
+}
+
+impl<T: Send + 'static> FrameRing<T> {
+    pub fn new(capacity: usize) -> Self {
+        let latest = Arc::new(Mutex::new(None));
+        let mut frames = Frames { clip: VecDeque::with_capacity(capacity), capacity, sharpest: VecDeque::new(), captures: Vec::new(), latest: latest.clone() };
+        FrameRing { worker: Worker::new(move |request| frames.handle(request)), latest, waiting: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, time: Instant, frame: RgbaImage) {
+        self.worker.offer(|| RingRequest::Frame(time, frame));
+    }
+
+    /// A clip-size copy of the newest frame.
+    pub fn latest(&self) -> Option<RgbaImage> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// Picks from `PRE_ROLL` before `pressed` to `POST_ROLL` after it, so `finish` runs once
+    /// the post-roll is over.
+    pub fn capture(&mut self, pressed: Instant, finish: Finish<T>) {
+        self.waiting.push_back(pressed);
+        self.worker.submit(RingRequest::Capture(pressed, finish));
+    }
+
+    /// Finished captures, oldest first. `None` is a capture that had no frames to pick from.
+    pub fn poll(&mut self) -> Vec<Option<T>> {
+        let finished = self.worker.poll().into_iter().flatten().collect::<Vec<_>>();
+        self.waiting.drain(..finished.len().min(self.waiting.len()));
+        let now = Instant::now();
+        if self.waiting.front().is_some_and(|pressed| now > *pressed + POST_ROLL) {
+            self.worker.offer(|| RingRequest::Deadline(now));
+        }
+        finished
+    }
+}
+
+// The ring's side of the thread.
+struct Frames<T> {
+    clip: VecDeque<TimedFrame>,
+    capacity: usize,
+    /// Full-size frames with their scores, which fall from front to back, so the first
+    /// frame at or after a time is the sharpest since then.
+    sharpest: VecDeque<(f64, TimedFrame)>,
+    captures: Vec<(Instant, Finish<T>)>,
+    latest: Arc<Mutex<Option<RgbaImage>>>,
+}
+
+impl<T> Frames<T> {
+    fn handle(&mut self, request: RingRequest<T>) -> Vec<Option<T>> {
+        match request {
+            RingRequest::Capture(pressed, finish) => {
+                self.captures.push((pressed, finish));
+                Vec::new()
+            },
+            RingRequest::Deadline(now) => self.finish_due(now),
+            RingRequest::Frame(time, frame) => {
+                // Captures are due once a frame lands past their post-roll, before that frame is counted.
+                let finished = self.finish_due(time);
+                self.push(time, frame);
+                finished
+            }
+        }
+    }
+
+    fn finish_due(&mut self, now: Instant) -> Vec<Option<T>> {
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.captures).into_iter()
+            .partition(|(pressed, _)| now > *pressed + POST_ROLL);
+        self.captures = waiting;
+        due.into_iter().map(|(pressed, finish)| self.finish(pressed, finish)).collect()
+    }
+
+    fn push(&mut self, time: Instant, frame: RgbaImage) {
+        let small = live_frame(&frame);
+        *self.latest.lock().unwrap() = Some(small.clone());
+        let score = sharpness(&frame);
+        while self.sharpest.back().is_some_and(|(s, _)| *s <= score) {
+            self.sharpest.pop_back();
+        }
+        self.sharpest.push_back((score, TimedFrame(time, frame)));
+        if self.clip.len() == self.capacity {
+            self.clip.pop_front();
+        }
+        self.clip.push_back(TimedFrame(time, small));
+
+        // Nothing older than the pre-roll, or than the start of a capture still waiting, can be picked.
+        let start = |t: Instant| t.checked_sub(PRE_ROLL).unwrap_or(t);
+        let oldest = self.captures.iter().map(|(pressed, _)| start(*pressed)).fold(start(time), Instant::min);
+        self.clip.retain(|f| f.0 >= oldest);
+        while self.sharpest.len() > 1 && self.sharpest.front().is_some_and(|(_, f)| f.0 < oldest) {
+            self.sharpest.pop_front();
+        }
+    }
+
+    /// Falls back to the newest frame when nothing landed in the window.
+    fn finish(&self, pressed: Instant, finish: Finish<T>) -> Option<T> {
+        let start = pressed.checked_sub(PRE_ROLL).unwrap_or(pressed);
+        let sharpest = self.sharpest.iter().find(|(_, f)| f.0 >= start).or(self.sharpest.back())?;
+        let clip = self.clip.iter().filter(|f| f.0 >= start).map(|f| f.1.clone()).collect();
+        Some(finish(sharpest.1.1.clone(), clip))
+    }
+}
+
+pub fn luma(image: &RgbaImage, width: u32) -> GrayImage {
+    let (w, h) = image.dimensions();
+    let gray = imageops::grayscale(image);
+    if w <= width {
+        return gray;
+    }
+    let height = ((h as f32 / w as f32) * width as f32).round().max(1.0) as u32;
+    imageops::resize(&gray, width, height, FilterType::Triangle)
+}
+
+/// Variance of the 4-neighbour Laplacian. Higher means more edge energy, i.e. sharper.
+pub fn sharpness(image: &RgbaImage) -> f64 {
+    laplacian_variance(&luma(image, SHARPNESS_WIDTH))
+}
+
+pub fn laplacian_variance(gray: &GrayImage) -> f64 {
+    let (w, h) = gray.dimensions();
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+    let px = |x: u32, y: u32| gray.get_pixel(x, y).0[0] as f64;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let l = px(x, y - 1) + px(x - 1, y) + px(x + 1, y) + px(x, y + 1) - 4.0 * px(x, y);
+            sum += l;
+            sum_sq += l * l;
+        }
+    }
+    let n = ((w - 2) * (h - 2)) as f64;
+    let mean = sum / n;
+    sum_sq / n - mean * mean
+}
+
+pub fn live_frame(image: &RgbaImage) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    if w <= LIVE_WIDTH {
+        return image.clone();
+    }
+    let height = ((h as f32 / w as f32) * LIVE_WIDTH as f32).round().max(1.0) as u32;
+    imageops::resize(image, LIVE_WIDTH, height, FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    use crate::worker::wait;
+
+    // Frames pushed while the ring is busy are dropped, so let each one in before the next.
+    fn settle<T: Send + 'static>(ring: &mut FrameRing<T>) {
+        wait(|| {
+            ring.worker.poll();
+            (!ring.worker.is_busy()).then_some(())
+        });
+    }
+
+    fn checkerboard(size: u32, cell: u32) -> RgbaImage {
+        RgbaImage::from_fn(size, size, |x, y| match (x / cell + y / cell) % 2 {
+            0 => Rgba([255, 255, 255, 255]),
+            _ => Rgba([0, 0, 0, 255])
+        })
+    }
+
+    #[test]
+    fn still_frames_loop() {
+        let (a, b) = (checkerboard(8, 1), checkerboard(8, 2));
+        let mut stills = StillFrames::new(vec![a.clone(), b.clone()]);
+        let frames = (0..5).map(|_| stills.next_frame().unwrap()).collect::<Vec<_>>();
+        assert_eq!(frames, vec![a.clone(), b.clone(), a.clone(), b, a]);
+        assert_eq!(StillFrames::default().next_frame(), None);
+    }
+
+    #[test]
+    fn ring_picks_the_sharpest_frame_around_the_press() {
+        let sharp = checkerboard(480, 4);
+        let soft = imageops::blur(&sharp, 2.0);
+        let softer = imageops::blur(&sharp, 4.0);
+        // The sharpest frame overall comes after the post-roll, too late to be picked.
+        let mut stills = StillFrames::new(vec![soft.clone(), softer.clone(), sharp.clone(), softer.clone(), soft.clone()]);
+        let late = checkerboard(480, 2);
+
+        let mut ring = FrameRing::new(RING_CAPACITY);
+        let start = Instant::now() + Duration::from_secs(1);
+        let step = Duration::from_millis(50);
+        let pressed = start + step * 2;
+        for i in 0..5 {
+            ring.push(start + step * i, stills.next_frame().unwrap());
+            settle(&mut ring);
+        }
+        ring.capture(pressed, Box::new(|frame, clip| (frame, clip)));
+        settle(&mut ring);
+        ring.push(pressed + POST_ROLL + step, late);
+
+        let (frame, clip) = wait(|| ring.poll().pop().flatten());
+        assert_eq!(frame, sharp);
+        assert_eq!(clip.len(), 5);
+        assert!(clip.iter().all(|f| f.width() == LIVE_WIDTH));
+        assert_eq!(ring.latest().map(|f| f.width()), Some(LIVE_WIDTH));
+    }
+
+    #[test]
+    fn ring_finishes_captures_when_frames_stop() {
+        let mut ring = FrameRing::new(RING_CAPACITY);
+        ring.push(Instant::now(), checkerboard(64, 4));
+        settle(&mut ring);
+        ring.capture(Instant::now(), Box::new(|frame, _| frame));
+        ring.capture(Instant::now(), Box::new(|frame, _| frame));
+        let mut finished = Vec::new();
+        wait(|| {
+            finished.extend(ring.poll());
+            (finished.len() == 2).then_some(())
+        });
+        assert!(finished.iter().all(|f| f.as_ref() == Some(&checkerboard(64, 4))));
+
+        // With no frames at all there's nothing to pick, but the capture still ends.
+        let mut empty = FrameRing::new(RING_CAPACITY);
+        empty.capture(Instant::now(), Box::new(|frame, _| frame));
+        assert_eq!(wait(|| empty.poll().pop()), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use pelican_ui::hardware::{Camera, ImageSettings};
+
+use crate::settings::CameraSetting;
+
+// A slider drag arrives as a stream of changes; ones to the same setting this
+// close together are folded into a single step.
+const MERGE: Duration = Duration::from_millis(750);
+const LIMIT: usize = 100;
+
+/// One undoable change: each setting it touched with its value before and after.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub changes: Vec<(CameraSetting, f32, f32)>,
+}
+
+impl Step {
+    fn apply(&self, camera: &mut Camera, after: bool) {
+        CameraSetting::apply_all(camera, self.changes.iter().map(|(setting, before, later)| (*setting, if after {*later} else {*before})));
+    }
+}
+
+/// Undo and redo for camera settings. Kept for the life of the app, not saved.
+#[derive(Clone, Debug, Default)]
+pub struct SettingsHistory {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    /// When the last step was recorded, while it can still take more changes.
+    open: Option<Instant>,
+}
+
+impl SettingsHistory {
+    /// Records whatever differs between `before` and `after` as a step.
+    pub fn record(&mut self, before: &ImageSettings, after: &ImageSettings) {
+        let changes = CameraSetting::ALL.into_iter()
+            .map(|s| (s, s.get(before), s.get(after)))
+            .filter(|(_, b, a)| b != a)
+            .collect::<Vec<_>>();
+        if changes.is_empty() { return; }
+        self.redo.clear();
+
+        let open = self.open.replace(Instant::now()).is_some_and(|at| at.elapsed() < MERGE);
+        if let ([(setting, _, after)], Some(last), true) = (changes.as_slice(), self.undo.last_mut(), open) {
+            if let [(last_setting, _, last_after)] = last.changes.as_mut_slice() {
+                if last_setting == setting {
+                    *last_after = *after;
+                    return;
+                }
+            }
+        }
+        self.undo.push(Step { changes });
+        if self.undo.len() > LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool { !self.undo.is_empty() }
+    pub fn can_redo(&self) -> bool { !self.redo.is_empty() }
+
+    /// Puts the camera back to before the last step and returns it.
+    pub fn undo(&mut self, camera: &mut Camera) -> Option<Step> {
+        self.back().inspect(|step| step.apply(camera, false))
+    }
+
+    pub fn redo(&mut self, camera: &mut Camera) -> Option<Step> {
+        self.forward().inspect(|step| step.apply(camera, true))
+    }
+
+    fn back(&mut self) -> Option<Step> {
+        let step = self.undo.pop()?;
+        self.open = None;
+        self.redo.push(step.clone());
+        Some(step)
+    }
+
+    fn forward(&mut self) -> Option<Step> {
+        let step = self.redo.pop()?;
+        self.open = None;
+        self.undo.push(step.clone());
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(setting: CameraSetting, value: f32) -> ImageSettings {
+        let mut settings = ImageSettings::default();
+        setting.set(&mut settings, value);
+        settings
+    }
+
+    fn record(history: &mut SettingsHistory, setting: CameraSetting, from: f32, to: f32) {
+        history.record(&with(setting, from), &with(setting, to));
+    }
+
+    #[test]
+    fn a_drag_is_one_step() {
+        let mut history = SettingsHistory::default();
+        (0..10).for_each(|i| record(&mut history, CameraSetting::Exposure, i as f32 * 0.1, (i + 1) as f32 * 0.1));
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].changes, [(CameraSetting::Exposure, 0.0, 1.0)]);
+
+        // Another setting, or the same one after an undo, starts a new step.
+        record(&mut history, CameraSetting::Contrast, 0.0, 0.5);
+        assert_eq!(history.undo.len(), 2);
+        history.back();
+        record(&mut history, CameraSetting::Contrast, 0.0, 0.2);
+        record(&mut history, CameraSetting::Contrast, 0.2, 0.3);
+        assert_eq!(history.undo.len(), 2);
+        history.back();
+        history.back();
+        record(&mut history, CameraSetting::Exposure, 0.0, 0.5);
+        assert_eq!(history.undo.len(), 1);
+    }
+
+    #[test]
+    fn unchanged_settings_are_not_recorded() {
+        let mut history = SettingsHistory::default();
+        history.record(&ImageSettings::default(), &ImageSettings::default());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn undo_and_redo_move_steps_across() {
+        let mut history = SettingsHistory::default();
+        record(&mut history, CameraSetting::Exposure, 0.0, 1.0);
+        record(&mut history, CameraSetting::Contrast, 0.0, 0.5);
+        assert_eq!(history.back().unwrap().changes, [(CameraSetting::Contrast, 0.0, 0.5)]);
+        assert!(history.can_redo());
+        assert_eq!(history.forward().unwrap().changes, [(CameraSetting::Contrast, 0.0, 0.5)]);
+        assert!(history.forward().is_none());
+
+        // A new change drops whatever could have been redone.
+        history.back();
+        record(&mut history, CameraSetting::Saturation, 0.0, 0.5);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn only_the_latest_steps_are_kept() {
+        let mut history = SettingsHistory::default();
+        for i in 0..=LIMIT {
+            // Alternating settings so no two steps merge.
+            let setting = if i % 2 == 0 {CameraSetting::Exposure} else {CameraSetting::Contrast};
+            record(&mut history, setting, 0.0, 0.5);
+        }
+        assert_eq!(history.undo.len(), LIMIT);
+        assert_eq!(history.undo[0].changes[0].0, CameraSetting::Contrast);
+    }
+}
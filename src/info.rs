@@ -0,0 +1,109 @@
+use chrono::Local;
+
+use crate::service::{Album, Photo};
+
+/// A heading and its rows, shown in the info sheet and copied as text.
+pub type Section = (&'static str, Vec<(String, String)>);
+
+/// Photos are stored as base64, so the format is read from the first few characters.
+pub fn format(image: &str) -> &'static str {
+    match image {
+        i if i.starts_with("iVBOR") => "PNG",
+        i if i.starts_with("/9j/") => "JPEG",
+        _ => "Unknown"
+    }
+}
+
+/// The size of base64 data once decoded.
+pub fn file_size(image: &str) -> usize {
+    let padding = image.bytes().rev().take_while(|b| *b == b'=').count();
+    (image.len() / 4 * 3).saturating_sub(padding)
+}
+
+pub fn bytes(size: usize) -> String {
+    match size {
+        s if s >= 1 << 20 => format!("{:.1} MB", s as f32 / (1 << 20) as f32),
+        s if s >= 1 << 10 => format!("{:.0} KB", s as f32 / (1 << 10) as f32),
+        s => format!("{s} bytes")
+    }
+}
+
+/// Where the photo shows up: the album it's kept in, then the groups it falls under.
+pub fn albums(photo: &Photo, album: Album) -> Vec<&'static str> {
+    let mut albums = vec![match (album, photo.strip.is_some()) {
+        (Album::Motion, _) => "Motion Captures",
+        (Album::Roll, true) => "Photo Booth Strip",
+        (Album::Roll, false) => "Camera Roll",
+    }];
+    let groups = [
+        (photo.is_document(), "Documents"),
+        (photo.is_booth_strip(), "Photo Booth"),
+        (!photo.live.is_empty(), "Live Photos"),
+        (photo.exposure.is_some(), "Long Exposures"),
+        (photo.is_low_quality(), "Low Quality"),
+        (!photo.edits.is_empty() || !photo.annotations.is_empty(), "Edited"),
+    ];
+    albums.extend(groups.into_iter().filter(|(member, _)| *member).map(|(_, name)| name));
+    albums
+}
+
+pub fn sections(photo: &Photo, album: Album) -> Vec<Section> {
+    let date = photo.date.with_timezone(&Local);
+    let mut capture = vec![
+        ("Date".to_string(), date.format("%b %-d, %Y").to_string()),
+        ("Time".to_string(), date.format("%-I:%M:%S %p").to_string()),
+    ];
+    if let Some(session) = &photo.capture {
+        capture.push(("Mode".to_string(), session.mode.label().to_string()));
+        capture.push(("Live Photo".to_string(), if session.live {"On"} else {"Off"}.to_string()));
+    }
+    if let Some(exposure) = photo.exposure {
+        capture.push(("Exposure".to_string(), exposure.label()));
+    }
+
+    let mut file = vec![
+        ("Dimensions".to_string(), format!("{} × {}", photo.size.0 as u32, photo.size.1 as u32)),
+        ("Format".to_string(), format(&photo.image).to_string()),
+        ("File Size".to_string(), bytes(file_size(&photo.image))),
+    ];
+    if let Some(edited) = &photo.edited {
+        file.push(("Edited Copy".to_string(), bytes(file_size(edited))));
+    }
+    if photo.is_document() {
+        file.push(("Pages".to_string(), photo.all_pages().count().to_string()));
+    }
+
+    // Captures from before settings were recorded, and motion captures, have none.
+    let settings = match &photo.capture {
+        Some(session) => session.values.iter().map(|(setting, value)| (setting.label().to_string(), setting.format(*value))).collect(),
+        None => vec![("Settings".to_string(), "Not recorded".to_string())]
+    };
+
+    let mut history = photo.edits.0.iter().enumerate().map(|(i, op)| ((i + 1).to_string(), op.label())).collect::<Vec<_>>();
+    if !photo.annotations.is_empty() {
+        let marks = photo.annotations.0.len();
+        history.push(("Markup".to_string(), format!("{} mark{}", marks, if marks == 1 {""} else {"s"})));
+    }
+    if history.is_empty() {
+        history.push(("Edits".to_string(), "None".to_string()));
+    }
+
+    let albums = albums(photo, album).into_iter().map(|name| (name.to_string(), String::new())).collect();
+    vec![("Capture", capture), ("File", file), ("Camera Settings", settings), ("Albums", albums), ("Edit History", history)]
+}
+
+/// The sheet as plain text, for pasting into a report.
+pub fn report(photo: &Photo, sections: &[Section]) -> String {
+    let mut lines = vec![];
+    if !photo.title.is_empty() { lines.push(photo.title.clone()); }
+    if !photo.caption.is_empty() { lines.push(photo.caption.clone()); }
+    for (heading, rows) in sections {
+        if !lines.is_empty() { lines.push(String::new()); }
+        lines.push(heading.to_string());
+        lines.extend(rows.iter().map(|(label, value)| match value.is_empty() {
+            true => format!("  {label}"),
+            false => format!("  {label}: {value}")
+        }));
+    }
+    lines.join("\n")
+}
@@ -1,18 +1,10 @@
 use pelican_ui::{include_assets, Theme, Component, Context, Plugins, Plugin, maverick_start, start, Application, PelicanEngine, MaverickOS};
 use pelican_ui::drawable::{Color, Drawable, Component};
 use pelican_ui::layout::{Area, SizeRequest, Layout};
-use pelican_ui::events::{OnEvent, Event};
+use pelican_ui::events::{OnEvent, Event, TickEvent};
 use pelican_ui::runtime::{Services, ServiceList};
-use pelican_ui::hardware::ApplicationSupport;
 use pelican_ui_std::{Stack, Interface};
 
-
-use std::fs::File;
-use std::path::Path;
-use std::io::Write;
-use std::io::BufReader;
-use tempfile::NamedTempFile;
-
 // mod bdk;
 // use bdk::BDKPlugin;
 // mod msg;
@@ -26,7 +18,47 @@ use service::LensService;
 use service::MyCameraRoll;
 mod components;
 mod events;
-use events::TakePhotoEvent;
+use events::DeletePhotosEvent;
+mod worker;
+mod frames;
+use frames::LivePhotos;
+mod quality;
+mod storage;
+mod draw;
+mod modes;
+mod scanner;
+mod document;
+mod motion;
+use motion::{MotionAlbum, MotionLog, MotionSettings, MotionSync};
+mod stopmotion;
+use stopmotion::StopMotion;
+mod exposure;
+use exposure::LongExposure;
+mod booth;
+use booth::PhotoBooth;
+mod chroma;
+mod settings;
+mod presets;
+use presets::Presets;
+mod session;
+use session::CameraSession;
+mod auto;
+use auto::AutoAdjust;
+mod lut;
+mod edit;
+mod curves;
+mod effects;
+mod markup;
+mod compare;
+mod viewer;
+mod info;
+mod timeline;
+use timeline::RollView;
+use curves::ToneCurves;
+mod history;
+use lut::{Filters, Luts};
+use chroma::ChromaKey;
+use scanner::ScanHistory;
 mod pages;
 use pages::CameraHome;
 
@@ -78,46 +110,52 @@ impl Application for MyApp {
 start!(MyApp);
 
 #[derive(Debug, Component)]
-pub struct App(Stack, Interface, #[skip] bool);
+pub struct App(Stack, Interface, #[skip] bool, #[skip] MotionSync);
 
 impl App {
     pub fn new(ctx: &mut Context) -> Box<Self> {
-        let storage_path = ApplicationSupport::get().unwrap();
-        std::fs::create_dir_all(&storage_path).unwrap();
-        let path = storage_path.join("my_camera_roll.json");
-        let photos = Self::load_photos(&path);
- 
-        ctx.state().set(MyCameraRoll(photos));
+        ctx.state().set(MyCameraRoll::load());
+        ctx.state().set(ScanHistory::load());
+        ctx.state().set(MotionSettings::load());
+        ctx.state().set(MotionAlbum::load());
+        ctx.state().set(MotionLog::load());
+        ctx.state().set(StopMotion::load());
+        ctx.state().set(LongExposure::load());
+        ctx.state().set(PhotoBooth::load());
+        ctx.state().set(ChromaKey::load());
+        let presets = Presets::load();
+        let mut session = CameraSession::load();
+        // A default preset takes over from the last-used settings at launch, but not the mode.
+        if let Some(preset) = presets.default_preset() {
+            session.values.extend(preset.values.clone());
+        }
+        ctx.state().set(session.mode);
+        ctx.state().set(LivePhotos(session.live));
+        ctx.state().set(session);
+        ctx.state().set(presets);
+        ctx.state().set(AutoAdjust::load());
+        let filters = Filters::load();
+        let luts = Luts::load(ctx, &filters);
+        ctx.state().set(filters);
+        ctx.state().set(luts);
+        ctx.state().set(ToneCurves::load());
+        ctx.state().set(RollView::load());
 
         let home = CameraHome::new(ctx, None);
         let interface = Interface::new(ctx, Box::new(home), None, None);
-        Box::new(App(Stack::default(), interface, false))
-    }
-
-    pub fn load_photos<P: AsRef<Path>>(path: P) -> Vec<(String, (f32, f32))> {
-        let path = path.as_ref();
-        if !path.exists() {
-            return Vec::new();
-        }
-        let file = File::open(path).expect("Could not open path");
-        let reader = BufReader::new(file);
-        let photos = serde_json::from_reader(reader).expect("Could not read from reader");
-        photos
+        Box::new(App(Stack::default(), interface, false, MotionSync::new()))
     }
 }
 
 impl OnEvent for App {
     fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
-        if event.downcast_ref::<TakePhotoEvent>().is_some() {
-            let storage_path = ApplicationSupport::get().unwrap();
-            std::fs::create_dir_all(&storage_path).unwrap();
-            let path = storage_path.join("my_camera_roll.json");
-            let photos = ctx.state().get_or_default::<MyCameraRoll>().0.clone();
-            let bytes = serde_json::to_vec_pretty(&photos).expect("Could not vec to pretty");
-            let mut tmp = NamedTempFile::new_in(path.parent().unwrap_or_else(|| Path::new("."))).expect("Could not write temp");
-            tmp.write_all(&bytes).expect("Could not write all");
-            tmp.flush().expect("Could not flush");
-            tmp.persist(path).expect("Colud not persist");
+        if event.downcast_ref::<TickEvent>().is_some() {
+            self.3.poll(ctx);
+        } else if let Some(DeletePhotosEvent(ids)) = event.downcast_ref::<DeletePhotosEvent>() {
+            let mut roll = ctx.state().get_or_default::<MyCameraRoll>().clone();
+            roll.remove(ids);
+            roll.save();
+            ctx.state().set(roll);
         }
         true
     }
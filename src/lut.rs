@@ -0,0 +1,255 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use image::{Rgba, RgbaImage};
+use pelican_ui::Context;
+use serde::{Serialize, Deserialize};
+
+use crate::storage;
+
+// Shipped in resources/luts.
+const BUNDLED: [&str; 5] = ["warm.cube", "cool.cube", "mono.cube", "faded.cube", "teal-orange.cube"];
+const MAX_SIZE: usize = 256;
+// Graded previews are drawn at this width; captures are graded at full resolution.
+pub const PREVIEW_WIDTH: u32 = 640;
+pub const THUMBNAIL_SIZE: u32 = 96;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    Trilinear,
+    /// Smoother on hue shifts and cheaper, but some LUTs were built for trilinear.
+    #[default]
+    Tetrahedral,
+}
+
+impl Interpolation {
+    pub const ALL: [Interpolation; 2] = [Interpolation::Trilinear, Interpolation::Tetrahedral];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Interpolation::Trilinear => "Trilinear",
+            Interpolation::Tetrahedral => "Tetrahedral",
+        }
+    }
+}
+
+/// A 3D color lookup table read from a `.cube` file.
+#[derive(Debug, PartialEq)]
+pub struct Lut {
+    pub name: String,
+    size: usize,
+    /// Red changes fastest, then green, then blue, as in the file.
+    table: Vec<[f32; 3]>,
+    domain: ([f32; 3], [f32; 3]),
+}
+
+impl Lut {
+    /// Parses `.cube` text. `fallback` names the LUT when the file has no TITLE.
+    pub fn parse(fallback: &str, text: &str) -> Result<Self, String> {
+        let mut name = fallback.to_string();
+        let mut size = None;
+        let mut domain = ([0.0; 3], [1.0; 3]);
+        let mut table = Vec::new();
+        let triple = |words: &[&str]| -> Result<[f32; 3], String> {
+            let values = words.iter().map(|w| w.parse::<f32>().map_err(|_| format!("Bad number \"{w}\"."))).collect::<Result<Vec<_>, _>>()?;
+            values.try_into().map_err(|_| "Expected three numbers.".to_string())
+        };
+
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words[0] {
+                "TITLE" => name = line["TITLE".len()..].trim().trim_matches('"').to_string(),
+                "LUT_3D_SIZE" => size = words.get(1).and_then(|s| s.parse::<usize>().ok()).filter(|s| (2..=MAX_SIZE).contains(s)),
+                "LUT_1D_SIZE" => return Err("1D LUTs aren't supported.".to_string()),
+                "DOMAIN_MIN" => domain.0 = triple(&words[1..])?,
+                "DOMAIN_MAX" => domain.1 = triple(&words[1..])?,
+                // Other keywords, such as LUT_3D_INPUT_RANGE, don't change how the table is read.
+                keyword if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {},
+                _ => table.push(triple(&words)?),
+            }
+        }
+
+        let size = size.ok_or("Missing or invalid LUT_3D_SIZE.")?;
+        if table.len() != size * size * size {
+            return Err(format!("Expected {} entries, found {}.", size * size * size, table.len()));
+        }
+        if (0..3).any(|c| domain.1[c] <= domain.0[c]) {
+            return Err("Invalid domain.".to_string());
+        }
+        Ok(Lut { name, size, table, domain })
+    }
+
+    fn at(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + g * self.size + b * self.size * self.size]
+    }
+
+    /// Looks up a color with components from 0 to 1.
+    pub fn sample(&self, rgb: [f32; 3], interpolation: Interpolation) -> [f32; 3] {
+        let last = (self.size - 1) as f32;
+        let position: [f32; 3] = std::array::from_fn(|c| {
+            let (lo, hi) = (self.domain.0[c], self.domain.1[c]);
+            ((rgb[c] - lo) / (hi - lo)).clamp(0.0, 1.0) * last
+        });
+        let base = position.map(|p| (p.floor() as usize).min(self.size - 2));
+        let [fr, fg, fb] = std::array::from_fn(|c| position[c] - base[c] as f32);
+        let corner = |r: usize, g: usize, b: usize| self.at(base[0] + r, base[1] + g, base[2] + b);
+        let mix = |weights: &[(f32, [f32; 3])]| -> [f32; 3] {
+            std::array::from_fn(|c| weights.iter().map(|(w, v)| w * v[c]).sum())
+        };
+
+        match interpolation {
+            Interpolation::Trilinear => {
+                let lerp = |a: [f32; 3], b: [f32; 3], t: f32| mix(&[(1.0 - t, a), (t, b)]);
+                let c00 = lerp(corner(0, 0, 0), corner(1, 0, 0), fr);
+                let c10 = lerp(corner(0, 1, 0), corner(1, 1, 0), fr);
+                let c01 = lerp(corner(0, 0, 1), corner(1, 0, 1), fr);
+                let c11 = lerp(corner(0, 1, 1), corner(1, 1, 1), fr);
+                lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb)
+            },
+            // Splits the cell into six tetrahedra along its diagonal and blends the four corners of the one holding the point.
+            Interpolation::Tetrahedral => {
+                let (c000, c111) = (corner(0, 0, 0), corner(1, 1, 1));
+                match (fr >= fg, fg >= fb, fr >= fb) {
+                    (true, true, _) => mix(&[(1.0 - fr, c000), (fr - fg, corner(1, 0, 0)), (fg - fb, corner(1, 1, 0)), (fb, c111)]),
+                    (true, false, true) => mix(&[(1.0 - fr, c000), (fr - fb, corner(1, 0, 0)), (fb - fg, corner(1, 0, 1)), (fg, c111)]),
+                    (true, false, false) => mix(&[(1.0 - fb, c000), (fb - fr, corner(0, 0, 1)), (fr - fg, corner(1, 0, 1)), (fg, c111)]),
+                    (false, true, true) => mix(&[(1.0 - fg, c000), (fg - fr, corner(0, 1, 0)), (fr - fb, corner(1, 1, 0)), (fb, c111)]),
+                    (false, true, false) => mix(&[(1.0 - fg, c000), (fg - fb, corner(0, 1, 0)), (fb - fr, corner(0, 1, 1)), (fr, c111)]),
+                    (false, false, _) => mix(&[(1.0 - fb, c000), (fb - fg, corner(0, 0, 1)), (fg - fr, corner(0, 1, 1)), (fr, c111)]),
+                }
+            }
+        }
+    }
+
+    /// Grades `image`, blending with the original by `intensity` from 0 to 100.
+    pub fn apply(&self, image: &RgbaImage, intensity: f32, interpolation: Interpolation) -> RgbaImage {
+        let amount = intensity.clamp(0.0, 100.0) / 100.0;
+        RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+            let p = image.get_pixel(x, y).0;
+            let graded = self.sample([p[0], p[1], p[2]].map(|c| c as f32 / 255.0), interpolation);
+            Rgba(std::array::from_fn(|c| match c {
+                3 => p[3],
+                _ => (p[c] as f32 * (1.0 - amount) + graded[c] * 255.0 * amount).round().clamp(0.0, 255.0) as u8
+            }))
+        })
+    }
+}
+
+/// The chosen filter and any LUTs imported from disk.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Filters {
+    /// Name of the active LUT, if any.
+    pub selected: Option<String>,
+    /// 0 to 100.
+    pub intensity: f32,
+    pub interpolation: Interpolation,
+    /// Storage file names of imported `.cube` files.
+    pub imported: Vec<String>,
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Filters { selected: None, intensity: 100.0, interpolation: Interpolation::default(), imported: Vec::new() }
+    }
+}
+
+impl Filters {
+    const FILE: &'static str = "filters.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+}
+
+/// A small copy of the latest camera frame for the filter picker, and when it was taken.
+#[derive(Clone, Debug, Default)]
+pub struct LiveThumbnail(pub Option<(Instant, Arc<RgbaImage>)>);
+
+/// Every loaded LUT, bundled ones first. Names are unique.
+#[derive(Clone, Debug, Default)]
+pub struct Luts(pub Vec<Arc<Lut>>);
+
+impl Luts {
+    /// Keeps the last LUT of each name, so an import replaces a bundled LUT it shares a name with.
+    fn unique(luts: impl IntoIterator<Item = Lut>) -> Self {
+        let mut unique = Luts::default();
+        luts.into_iter().for_each(|lut| unique.insert(Arc::new(lut)));
+        unique
+    }
+
+    fn insert(&mut self, lut: Arc<Lut>) {
+        self.0.retain(|existing| existing.name != lut.name);
+        self.0.push(lut);
+    }
+
+    pub fn load(ctx: &mut Context, filters: &Filters) -> Self {
+        let bundled = BUNDLED.iter().filter_map(|file| {
+            let bytes = ctx.assets.load_file(&format!("luts/{file}"))?;
+            Lut::parse(file.trim_end_matches(".cube"), &String::from_utf8_lossy(&bytes)).ok()
+        });
+        let imported = filters.imported.iter().filter_map(|file| {
+            let text = String::from_utf8(storage::read(file)?).ok()?;
+            Lut::parse(file.trim_end_matches(".cube"), &text).ok()
+        });
+        Luts::unique(bundled.chain(imported))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Lut>> {
+        self.0.iter().find(|lut| lut.name == name).cloned()
+    }
+
+    /// The active LUT applied to `image`, or `None` when no filter is selected.
+    pub fn grade(&self, filters: &Filters, image: &RgbaImage) -> Option<RgbaImage> {
+        let lut = self.get(filters.selected.as_deref()?)?;
+        Some(lut.apply(image, filters.intensity, filters.interpolation))
+    }
+
+    /// Reads a LUT from the text of a `.cube` file and keeps a copy in storage.
+    /// A LUT with the same name as an existing one replaces it.
+    pub fn import(&mut self, filters: &mut Filters, cube: &str) -> Result<Arc<Lut>, String> {
+        let fallback = (1..).map(|n| match n {
+            1 => "Imported".to_string(),
+            n => format!("Imported {n}")
+        }).find(|name| self.get(name).is_none()).unwrap();
+        let lut = Arc::new(Lut::parse(&fallback, cube.trim())?);
+        let slug = lut.name.chars().map(|c| if c.is_alphanumeric() {c.to_ascii_lowercase()} else {'-'}).collect::<String>();
+        let file = format!("lut-{slug}.cube");
+        // The name may be the fallback, so it's written into the copy as its TITLE.
+        let body = cube.lines().filter(|line| !line.trim_start().starts_with("TITLE")).collect::<Vec<_>>().join("\n");
+        storage::write(&storage::path(&file), format!("TITLE \"{}\"\n{body}\n", lut.name).as_bytes());
+        if !filters.imported.contains(&file) {
+            filters.imported.push(file);
+        }
+        self.insert(lut.clone());
+        Ok(lut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(name: &str) -> Lut {
+        let table = (0..8).map(|i| [(i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32]);
+        let body = table.map(|[r, g, b]| format!("{r} {g} {b}")).collect::<Vec<_>>().join("\n");
+        Lut::parse(name, &format!("LUT_3D_SIZE 2\n{body}")).unwrap()
+    }
+
+    #[test]
+    fn later_luts_replace_earlier_ones_with_the_same_name() {
+        let mut imported = identity("warm");
+        imported.domain.1 = [2.0; 3];
+        let luts = Luts::unique([identity("warm"), identity("cool"), imported]);
+        assert_eq!(luts.0.iter().map(|lut| lut.name.as_str()).collect::<Vec<_>>(), ["cool", "warm"]);
+        assert_eq!(luts.get("warm").unwrap().domain.1, [2.0; 3]);
+    }
+
+    #[test]
+    fn paths_are_not_cube_files() {
+        assert!(Lut::parse("Imported", "/sdcard/Download/warm.cube").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use image::{Rgba, RgbaImage};
+use serde::{Serialize, Deserialize};
+
+use crate::draw;
+use crate::edit::Edits;
+
+// Stroke widths are per thousand pixels of the photo's long edge, so marks look the
+// same on the preview and on the full size photo they're flattened into.
+const PER_MILLE: f32 = 1000.0;
+// Font dots per unit of stroke width.
+const TEXT_DOTS: f32 = 0.8;
+const ELLIPSE_SEGMENTS: usize = 72;
+// Arrowheads are this many stroke widths long, this many degrees either side of the shaft.
+const HEAD_LENGTH: f32 = 4.0;
+const HEAD_ANGLE: f32 = 28.0;
+const TEXT_SHADOW: Rgba<u8> = Rgba([0, 0, 0, 140]);
+// A pen stroke only takes a new point once the finger has moved this share of the photo,
+// and shapes smaller than this are dropped as stray taps.
+const MIN_STEP: f32 = 0.003;
+const UNDO_LIMIT: usize = 100;
+
+pub const COLORS: [(&str, [u8; 3]); 6] = [
+    ("Red", [230, 45, 45]),
+    ("Yellow", [250, 205, 30]),
+    ("Green", [40, 190, 85]),
+    ("Blue", [40, 120, 240]),
+    ("White", [255, 255, 255]),
+    ("Black", [0, 0, 0]),
+];
+
+pub const WIDTHS: [(&str, f32); 3] = [("Thin", 3.0), ("Medium", 6.0), ("Thick", 12.0)];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MarkupTool {
+    #[default]
+    Pen,
+    Arrow,
+    Rectangle,
+    Ellipse,
+    Text,
+}
+
+impl MarkupTool {
+    pub const ALL: [MarkupTool; 5] = [MarkupTool::Pen, MarkupTool::Arrow, MarkupTool::Rectangle, MarkupTool::Ellipse, MarkupTool::Text];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarkupTool::Pen => "Pen",
+            MarkupTool::Arrow => "Arrow",
+            MarkupTool::Rectangle => "Rectangle",
+            MarkupTool::Ellipse => "Ellipse",
+            MarkupTool::Text => "Text",
+        }
+    }
+}
+
+/// Points are fractions of the original photo, so marks stay put when the crop or turns change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Shape {
+    Pen(Vec<(f32, f32)>),
+    Arrow((f32, f32), (f32, f32)),
+    /// Opposite corners.
+    Rectangle((f32, f32), (f32, f32)),
+    /// Opposite corners of the box it fills.
+    Ellipse((f32, f32), (f32, f32)),
+    /// The label's top left corner.
+    Text((f32, f32), String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mark {
+    pub shape: Shape,
+    pub color: [u8; 3],
+    pub width: f32,
+}
+
+impl Shape {
+    fn map(&self, f: impl Fn((f32, f32)) -> (f32, f32)) -> Shape {
+        match self {
+            Shape::Pen(points) => Shape::Pen(points.iter().map(|p| f(*p)).collect()),
+            Shape::Arrow(a, b) => Shape::Arrow(f(*a), f(*b)),
+            Shape::Rectangle(a, b) => Shape::Rectangle(f(*a), f(*b)),
+            Shape::Ellipse(a, b) => Shape::Ellipse(f(*a), f(*b)),
+            Shape::Text(corner, label) => Shape::Text(f(*corner), label.clone()),
+        }
+    }
+}
+
+impl Mark {
+    /// Draws the mark on a render of the photo, an original of `size`, with `edits`.
+    pub fn draw(&self, image: &mut RgbaImage, edits: &Edits, size: (f32, f32)) {
+        let placed = Mark { shape: self.shape.map(|p| edits.place(p, size)), ..self.clone() };
+        placed.draw_placed(image);
+    }
+
+    // Draws with points as fractions of `image` itself.
+    fn draw_placed(&self, image: &mut RgbaImage) {
+        let (w, h) = (image.width() as f32, image.height() as f32);
+        let at = |(x, y): (f32, f32)| (x * w, y * h);
+        let width = (self.width * w.max(h) / PER_MILLE).max(1.0);
+        let [r, g, b] = self.color;
+        let color = Rgba([r, g, b, 255]);
+        match &self.shape {
+            // A tap leaves a stroke of two matching points, which draws as a dot.
+            Shape::Pen(points) => points.windows(2).for_each(|p| draw::line(image, at(p[0]), at(p[1]), width, color)),
+            Shape::Arrow(from, to) => {
+                let (from, to) = (at(*from), at(*to));
+                draw::line(image, from, to, width, color);
+                let back = (from.1 - to.1).atan2(from.0 - to.0);
+                for side in [-1.0, 1.0] {
+                    let angle = back + side * HEAD_ANGLE.to_radians();
+                    let tip = (to.0 + angle.cos() * width * HEAD_LENGTH, to.1 + angle.sin() * width * HEAD_LENGTH);
+                    draw::line(image, to, tip, width, color);
+                }
+            },
+            Shape::Rectangle(a, b) => {
+                let (a, b) = (at(*a), at(*b));
+                draw::rect(image, (a.0.min(b.0), a.1.min(b.1), (b.0 - a.0).abs(), (b.1 - a.1).abs()), width, color);
+            },
+            Shape::Ellipse(a, b) => {
+                let (a, b) = (at(*a), at(*b));
+                let (cx, cy, rx, ry) = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0, (b.0 - a.0).abs() / 2.0, (b.1 - a.1).abs() / 2.0);
+                let points = (0..ELLIPSE_SEGMENTS).map(|i| {
+                    let t = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    (cx + rx * t.cos(), cy + ry * t.sin())
+                }).collect::<Vec<_>>();
+                draw::polygon(image, &points, width, color);
+            },
+            Shape::Text(corner, label) => {
+                let (x, y) = at(*corner);
+                let scale = (width * TEXT_DOTS).max(1.0);
+                // A soft shadow keeps labels readable on any background.
+                draw::text(image, label, (x + scale / 2.0, y + scale / 2.0), scale, TEXT_SHADOW);
+                draw::text(image, label, (x, y), scale, color);
+            },
+        }
+    }
+}
+
+/// Marks drawn over a photo, kept as shapes so they stay editable. They're only
+/// flattened into pixels for the copy that's shown and shared.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Annotations(pub Vec<Mark>);
+
+impl Annotations {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Draws every mark on `image`, a render of an original of `size` with `edits`.
+    pub fn flatten(&self, mut image: RgbaImage, edits: &Edits, size: (f32, f32)) -> RgbaImage {
+        self.0.iter().for_each(|mark| mark.draw(&mut image, edits, size));
+        image
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Annotations being drawn on a photo, with undo and redo. Nothing is saved until the page applies it.
+#[derive(Debug)]
+pub struct Markup {
+    pub annotations: Annotations,
+    pub tool: MarkupTool,
+    /// Indexes into `COLORS` and `WIDTHS`.
+    pub color: usize,
+    pub width: usize,
+    /// What the text tool writes.
+    pub label: String,
+    /// A mark still being drawn.
+    draft: Option<Mark>,
+    /// The photo with its edits, at preview size.
+    base: RgbaImage,
+    /// Marks are drawn on the render but kept against the original, so these map between the two.
+    edits: Edits,
+    size: (f32, f32),
+    undo: Vec<Annotations>,
+    redo: Vec<Annotations>,
+}
+
+impl Markup {
+    /// `base` is the photo, an original of `size`, rendered with `edits`.
+    pub fn new(base: RgbaImage, annotations: Annotations, edits: Edits, size: (f32, f32)) -> Self {
+        Markup {
+            annotations, tool: MarkupTool::default(), color: 0, width: 1, label: String::new(), draft: None, base, edits, size,
+            undo: Vec::new(), redo: Vec::new()
+        }
+    }
+
+    /// Follows a drag across the photo as it's shown, edits included.
+    pub fn drag(&mut self, start: (f32, f32), end: (f32, f32), done: bool) {
+        let (start, end) = (self.edits.source(start, self.size), self.edits.source(end, self.size));
+        let label = &self.label;
+        let shape = match (self.tool, self.draft.take().map(|m| m.shape)) {
+            (MarkupTool::Pen, Some(Shape::Pen(mut points))) => {
+                if points.last().is_none_or(|last| distance(*last, end) >= MIN_STEP) { points.push(end); }
+                Some(Shape::Pen(points))
+            },
+            (MarkupTool::Pen, _) => Some(Shape::Pen(vec![start, end])),
+            (MarkupTool::Text, _) => (!label.trim().is_empty()).then(|| Shape::Text(end, label.trim().to_string())),
+            _ if distance(start, end) < MIN_STEP => None,
+            (MarkupTool::Arrow, _) => Some(Shape::Arrow(start, end)),
+            (MarkupTool::Rectangle, _) => Some(Shape::Rectangle(start, end)),
+            (MarkupTool::Ellipse, _) => Some(Shape::Ellipse(start, end)),
+        };
+        let mark = shape.map(|shape| Mark { shape, color: COLORS[self.color].1, width: WIDTHS[self.width].1 });
+        match done {
+            true => if let Some(mark) = mark {
+                let mut annotations = self.annotations.clone();
+                annotations.0.push(mark);
+                self.change(annotations);
+            },
+            false => self.draft = mark
+        }
+    }
+
+    fn change(&mut self, annotations: Annotations) {
+        self.undo.push(std::mem::replace(&mut self.annotations, annotations));
+        self.redo.clear();
+        if self.undo.len() > UNDO_LIMIT { self.undo.remove(0); }
+    }
+
+    pub fn clear(&mut self) {
+        if !self.annotations.is_empty() { self.change(Annotations::default()); }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(previous) = self.undo.pop() {
+            self.redo.push(std::mem::replace(&mut self.annotations, previous));
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(next) = self.redo.pop() {
+            self.undo.push(std::mem::replace(&mut self.annotations, next));
+        }
+    }
+
+    /// The photo with every mark, and the one being drawn, on top.
+    pub fn render(&self) -> RgbaImage {
+        let mut image = self.annotations.flatten(self.base.clone(), &self.edits, self.size);
+        if let Some(draft) = &self.draft { draft.draw(&mut image, &self.edits, self.size); }
+        image
+    }
+}
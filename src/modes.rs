@@ -0,0 +1,83 @@
+use std::time::Instant;
+
+use image::RgbaImage;
+use pelican_ui::Context;
+use serde::{Serialize, Deserialize};
+
+use crate::scanner::{self, Scanner, ScannedCode};
+use crate::document::{self, Detector, Quad};
+use crate::motion::{Monitor, MotionSettings};
+use crate::stopmotion::StopMotion;
+use crate::exposure::Stacker;
+use crate::booth::BoothSession;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptureMode {
+    #[default]
+    Photo,
+    Scan,
+    Document,
+    Motion,
+    StopMotion,
+    LongExposure,
+    PhotoBooth,
+    ChromaKey,
+}
+
+impl CaptureMode {
+    pub const ALL: [CaptureMode; 8] = [
+        CaptureMode::Photo, CaptureMode::Scan, CaptureMode::Document,
+        CaptureMode::Motion, CaptureMode::StopMotion, CaptureMode::LongExposure,
+        CaptureMode::PhotoBooth, CaptureMode::ChromaKey,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CaptureMode::Photo => "Photo",
+            CaptureMode::Scan => "Scan",
+            CaptureMode::Document => "Document",
+            CaptureMode::Motion => "Motion",
+            CaptureMode::StopMotion => "Stop Motion",
+            CaptureMode::LongExposure => "Long Exposure",
+            CaptureMode::PhotoBooth => "Photo Booth",
+            CaptureMode::ChromaKey => "Green Screen",
+        }
+    }
+}
+
+/// Per-mode working state owned by `AlbacoreCamera`, rebuilt whenever the mode changes.
+#[derive(Debug)]
+pub enum ModeState {
+    Photo,
+    Scan(Scanner, Vec<ScannedCode>),
+    Document(Detector, Option<Quad>),
+    /// The monitor, whether it is armed, and the zone being dragged out (start and end, normalized).
+    Motion(Monitor, bool, Option<((f32, f32), (f32, f32))>),
+    /// Onion skin layers from the active project, newest first.
+    StopMotion(Vec<RgbaImage>),
+    /// The exposure in progress and when it started, if the shutter has been pressed.
+    LongExposure(Option<(Stacker, Instant)>),
+    /// The countdown sequence in progress, if the shutter has been pressed.
+    PhotoBooth(Option<BoothSession>),
+    /// The chosen background, scaled to the preview size.
+    ChromaKey(Option<(Uuid, RgbaImage)>),
+}
+
+impl ModeState {
+    pub fn new(ctx: &mut Context, mode: CaptureMode) -> Self {
+        match mode {
+            CaptureMode::Photo => ModeState::Photo,
+            CaptureMode::Scan => ModeState::Scan(scanner::scanner(), Vec::new()),
+            CaptureMode::Document => ModeState::Document(document::detector(), None),
+            CaptureMode::Motion => ModeState::Motion(Monitor::new(MotionSettings::load()), false, None),
+            CaptureMode::StopMotion => {
+                let layers = ctx.state().get_or_default::<StopMotion>().active().onion_frames();
+                ModeState::StopMotion(layers)
+            },
+            CaptureMode::LongExposure => ModeState::LongExposure(None),
+            CaptureMode::PhotoBooth => ModeState::PhotoBooth(None),
+            CaptureMode::ChromaKey => ModeState::ChromaKey(None),
+        }
+    }
+}
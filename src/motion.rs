@@ -0,0 +1,376 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::{DateTime, Utc};
+use image::{GrayImage, RgbaImage};
+use image::imageops;
+use pelican_ui::Context;
+use pelican_ui::hardware::{self, Camera};
+use pelican_ui::runtime::{self, BackgroundTask, async_trait};
+use pelican_ui_std::EncodedImage;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+use crate::frames::{self, FrameSource, luma, live_frame};
+use crate::service::Photo;
+use crate::storage;
+use crate::worker::Worker;
+
+const ANALYSIS_WIDTH: u32 = 160;
+// Per-pixel luma change that counts as movement rather than sensor noise.
+const PIXEL_DELTA: i16 = 25;
+const CLIP_INTERVAL: Duration = Duration::from_millis(100);
+const PRE_ROLL: Duration = Duration::from_secs(2);
+const POST_ROLL: Duration = Duration::from_secs(3);
+const MAX_CLIP: Duration = Duration::from_secs(20);
+const SETTINGS_REFRESH: Duration = Duration::from_secs(5);
+
+/// A detection zone as x, y, width and height in fractions of the frame.
+pub type Zone = (f32, f32, f32, f32);
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MotionSettings {
+    /// 0 to 100. Higher triggers on smaller movements.
+    pub sensitivity: f32,
+    /// Empty means the whole frame is watched.
+    pub zones: Vec<Zone>,
+    /// Save a pre/post-roll clip with each event instead of just a still.
+    pub clips: bool,
+    /// Let `orange background` keep monitoring with no window open.
+    pub headless: bool,
+}
+
+impl Default for MotionSettings {
+    fn default() -> Self {
+        MotionSettings { sensitivity: 50.0, zones: Vec::new(), clips: true, headless: false }
+    }
+}
+
+impl MotionSettings {
+    const FILE: &'static str = "motion_settings.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    /// Fraction of the watched pixels that must change to count as motion.
+    pub fn threshold(&self) -> f32 {
+        let s = self.sensitivity.clamp(0.0, 100.0) / 100.0;
+        0.25 * (1.0 - s).powi(2) + 0.001
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MotionRecord {
+    pub photo: Uuid,
+    pub date: DateTime<Utc>,
+    /// Seconds of footage, zero for stills.
+    pub duration: f32,
+    /// Largest fraction of the watched area that changed during the event.
+    pub peak: f32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct MotionLog(pub Vec<MotionRecord>);
+
+impl MotionLog {
+    const FILE: &'static str = "motion_log.json";
+    const LIMIT: usize = 1000;
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    /// Changes the saved log, which `orange background` may be writing to as well.
+    pub fn update(change: impl FnOnce(&mut Self)) -> Self {
+        storage::update(Self::FILE, change)
+    }
+
+    pub fn record(&mut self, record: MotionRecord) {
+        self.0.insert(0, record);
+        self.0.truncate(Self::LIMIT);
+    }
+}
+
+/// Motion captures are kept apart from the camera roll so a night of
+/// monitoring doesn't bury the user's own photos. Stills are kept in files of
+/// their own, like live clips, so the album file only holds their names.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct MotionAlbum(pub Vec<Photo>);
+
+impl MotionAlbum {
+    const FILE: &'static str = "motion_album.json";
+    // Events the log has dropped would have no record to open them from.
+    const LIMIT: usize = MotionLog::LIMIT;
+
+    pub fn load() -> Self {
+        let saved = storage::modified(Self::FILE).map(DateTime::<Utc>::from).unwrap_or_else(Utc::now);
+        let mut album: Self = storage::update_if(Self::FILE, |album: &mut Self| {
+            album.0.iter_mut().map(|p| p.upgrade(saved) | Self::store(p)).filter(|moved| *moved).count() > 0
+        });
+        // A still whose file is gone has nothing left to show.
+        album.0.retain_mut(|photo| match storage::read_encoded(&photo.image) {
+            Some(image) => { photo.image = image; true },
+            None => false
+        });
+        album
+    }
+
+    /// Changes the saved album, which `orange background` may be writing to as well.
+    /// New photos' stills are written out to their files.
+    pub fn update(change: impl FnOnce(&mut Self)) {
+        storage::update(Self::FILE, |album: &mut Self| {
+            change(album);
+            album.0.iter_mut().for_each(|p| { Self::store(p); });
+        });
+    }
+
+    fn still(id: Uuid) -> String {
+        format!("still-{id}.png")
+    }
+
+    // Moves a still held as base64 out to its file. Returns whether it did.
+    fn store(photo: &mut Photo) -> bool {
+        if photo.image == Self::still(photo.id) { return false; }
+        storage::write_encoded(&Self::still(photo.id), &photo.image);
+        photo.image = Self::still(photo.id);
+        true
+    }
+
+    /// Adds a new event, returning the oldest ones that no longer fit.
+    pub fn add(&mut self, photo: Photo) -> Vec<Photo> {
+        self.0.push(photo);
+        self.0.drain(..self.0.len().saturating_sub(Self::LIMIT)).collect()
+    }
+
+    /// Deletes a photo's still and clip.
+    pub fn remove_media(photo: &Photo) {
+        storage::remove(&Self::still(photo.id));
+        photo.remove_live();
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&Photo> {
+        self.0.iter().find(|p| p.id == id)
+    }
+}
+
+/// Fraction of the pixels inside `zones` whose luma moved by more than `PIXEL_DELTA`.
+pub fn changed_fraction(previous: &GrayImage, current: &GrayImage, zones: &[Zone]) -> f32 {
+    let (w, h) = current.dimensions();
+    if previous.dimensions() != (w, h) {
+        return 0.0;
+    }
+    let whole = [(0.0, 0.0, 1.0, 1.0)];
+    let zones = if zones.is_empty() { &whole[..] } else { zones };
+    let inside = |x: u32, y: u32| {
+        let (fx, fy) = ((x as f32 + 0.5) / w as f32, (y as f32 + 0.5) / h as f32);
+        zones.iter().any(|(zx, zy, zw, zh)| fx >= *zx && fx <= zx + zw && fy >= *zy && fy <= zy + zh)
+    };
+    let (mut watched, mut changed) = (0u32, 0u32);
+    for (x, y, p) in current.enumerate_pixels() {
+        if !inside(x, y) { continue; }
+        watched += 1;
+        if (p.0[0] as i16 - previous.get_pixel(x, y).0[0] as i16).abs() > PIXEL_DELTA {
+            changed += 1;
+        }
+    }
+    if watched == 0 { 0.0 } else { changed as f32 / watched as f32 }
+}
+
+#[derive(Debug)]
+struct Recording {
+    date: DateTime<Utc>,
+    started: Instant,
+    last_motion: Instant,
+    peak: f32,
+    still: RgbaImage,
+    frames: Vec<RgbaImage>,
+}
+
+/// Watches successive frames for motion and records an event from `PRE_ROLL`
+/// before the first movement to `POST_ROLL` after the last. Finished events are
+/// encoded and written to the motion album on a worker thread.
+#[derive(Debug)]
+pub struct Monitor {
+    settings: MotionSettings,
+    previous: Option<GrayImage>,
+    clip: VecDeque<(Instant, RgbaImage)>,
+    recording: Option<Recording>,
+    saver: Sender<Recording>,
+    saved: Receiver<(Photo, MotionRecord)>,
+}
+
+impl Monitor {
+    pub fn new(settings: MotionSettings) -> Self {
+        let (saver, recordings) = mpsc::channel::<Recording>();
+        let (saved_tx, saved) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(recording) = recordings.recv() {
+                if saved_tx.send(save(recording)).is_err() { break; }
+            }
+        });
+        Monitor { settings, previous: None, clip: VecDeque::new(), recording: None, saver, saved }
+    }
+
+    pub fn settings(&self) -> &MotionSettings { &self.settings }
+    pub fn set_settings(&mut self, settings: MotionSettings) { self.settings = settings; }
+
+    pub fn is_recording(&self) -> bool { self.recording.is_some() }
+
+    pub fn push(&mut self, frame: &RgbaImage) {
+        let now = Instant::now();
+        let gray = imageops::blur(&luma(frame, ANALYSIS_WIDTH), 1.0);
+        let level = self.previous.as_ref().map(|p| changed_fraction(p, &gray, &self.settings.zones)).unwrap_or(0.0);
+        self.previous = Some(gray);
+        let moving = level > self.settings.threshold();
+
+        if self.clip.back().is_none_or(|(t, _)| now.duration_since(*t) >= CLIP_INTERVAL) {
+            self.clip.push_back((now, live_frame(frame)));
+        }
+
+        match &mut self.recording {
+            None => {
+                while self.clip.front().is_some_and(|(t, _)| now.duration_since(*t) > PRE_ROLL) {
+                    self.clip.pop_front();
+                }
+                if moving {
+                    self.recording = Some(Recording {
+                        date: Utc::now(), started: now, last_motion: now, peak: level, still: frame.clone(), frames: Vec::new()
+                    });
+                }
+            },
+            Some(recording) => {
+                if moving {
+                    recording.last_motion = now;
+                    if level > recording.peak {
+                        recording.peak = level;
+                        recording.still = frame.clone();
+                    }
+                }
+                if now.duration_since(recording.last_motion) >= POST_ROLL || now.duration_since(recording.started) >= MAX_CLIP {
+                    self.finish();
+                }
+            }
+        }
+    }
+
+    /// Ends any event in progress, e.g. when monitoring is switched off.
+    pub fn finish(&mut self) {
+        let Some(mut recording) = self.recording.take() else { return };
+        let frames = self.clip.drain(..).map(|(_, f)| f);
+        recording.frames = if self.settings.clips { frames.collect() } else { Vec::new() };
+        let _ = self.saver.send(recording);
+    }
+
+    pub fn poll(&mut self) -> Option<(Photo, MotionRecord)> {
+        self.saved.try_recv().ok()
+    }
+}
+
+fn save(recording: Recording) -> (Photo, MotionRecord) {
+    let (width, height) = recording.still.dimensions();
+    let mut photo = Photo::new(EncodedImage::encode_rgba(recording.still), (width as f32, height as f32));
+    photo.date = recording.date;
+    photo.set_live(recording.frames);
+    let record = MotionRecord {
+        photo: photo.id,
+        date: recording.date,
+        duration: photo.live.len() as f32 * CLIP_INTERVAL.as_secs_f32(),
+        peak: recording.peak,
+    };
+
+    MotionAlbum::update(|album| album.add(photo.clone()).iter().for_each(MotionAlbum::remove_media));
+    MotionLog::update(|log| log.record(record.clone()));
+    (photo, record)
+}
+
+/// Runs the monitor with no window when started as `orange background`, as the
+/// debian service does. Idles until the user turns on background monitoring, and
+/// looks for a camera again while there isn't one.
+pub struct HeadlessMonitor {
+    source: Option<Box<dyn FrameSource + Send>>,
+    monitor: Monitor,
+    refreshed: Instant,
+}
+
+impl HeadlessMonitor {
+    fn source() -> Option<Box<dyn FrameSource + Send>> {
+        match Camera::new_custom() {
+            Ok(camera) => Some(Box::new(camera)),
+            Err(_) => frames::fallback_source()
+        }
+    }
+}
+
+#[async_trait]
+impl BackgroundTask for HeadlessMonitor {
+    async fn new(_ctx: &mut hardware::Context) -> Self {
+        HeadlessMonitor { source: None, monitor: Monitor::new(MotionSettings::load()), refreshed: Instant::now() }
+    }
+
+    async fn run(&mut self, _ctx: &mut hardware::Context) -> Result<Option<Duration>, runtime::Error> {
+        // Zones and sensitivity, and whether to run at all, can be changed from the app while this runs.
+        if self.refreshed.elapsed() >= SETTINGS_REFRESH {
+            self.refreshed = Instant::now();
+            self.monitor.set_settings(MotionSettings::load());
+        }
+        if !self.monitor.settings().headless {
+            // Let the camera go so the app can have it.
+            self.monitor.finish();
+            self.source = None;
+            return Ok(Some(SETTINGS_REFRESH));
+        }
+        if self.source.is_none() {
+            self.source = Self::source();
+        }
+        let Some(source) = &mut self.source else { return Ok(Some(SETTINGS_REFRESH)) };
+        if let Some(frame) = source.next_frame() {
+            self.monitor.push(&frame);
+        }
+        // Events are already in the album and log, which the app reloads.
+        while self.monitor.poll().is_some() {}
+        Ok(Some(Duration::from_millis(33)))
+    }
+}
+
+/// Picks up events `orange background` saved while the app is open. The album and
+/// log are read back on a worker, since the album's stills are read with it.
+#[derive(Debug)]
+pub struct MotionSync {
+    checked: Instant,
+    seen: [Option<SystemTime>; 2],
+    loader: Worker<(), (MotionAlbum, MotionLog)>,
+}
+
+impl MotionSync {
+    const INTERVAL: Duration = Duration::from_secs(2);
+
+    /// Made once the album and log have been loaded.
+    pub fn new() -> Self {
+        MotionSync { checked: Instant::now(), seen: Self::modified(), loader: Worker::latest(|_| (MotionAlbum::load(), MotionLog::load())) }
+    }
+
+    fn modified() -> [Option<SystemTime>; 2] {
+        [storage::modified(MotionAlbum::FILE), storage::modified(MotionLog::FILE)]
+    }
+
+    pub fn poll(&mut self, ctx: &mut Context) {
+        if let Some((album, log)) = self.loader.poll().pop() {
+            ctx.state().set(album);
+            ctx.state().set(log);
+        }
+        if self.checked.elapsed() < Self::INTERVAL { return; }
+        self.checked = Instant::now();
+        let modified = Self::modified();
+        if modified != self.seen {
+            self.seen = modified;
+            self.loader.submit(());
+        }
+    }
+}
@@ -8,25 +8,68 @@ use pelican_ui::hardware::ImageSettings;
 use pelican_ui_std::{
     IconButton, 
     Stack, RoundedRectangle, 
-    AppPage, ExpandableImage, 
+    AppPage, 
     Size, Offset, Padding,
     Header, Column, NavigateEvent,
     Page, Content, Slider, Bumper,
     Text, TextStyle, Brand, EncodedImage,
+    Button, Alert, ListItem, ListItemGroup,
+    Timestamp, ExpandableImage, TextInput, ButtonState, DataItem,
 };
 
 use crate::events::SetCameraSetting;
-use crate::events::{OpenSettingsEvent, NewSettingSelectedEvent, TakePhotoEvent, SelectImageEvent, SettingsSelect};
-use crate::components::{AlbacoreCamera, CameraBumper, EditSettingsBumper, PhotoWrap, CameraRollButton};
+use crate::events::{OpenSettingsEvent, TakePhotoEvent, SelectImageEvent, SettingsSelect, ReviewLowQualityEvent, DeletePhotosEvent};
+use crate::events::{CodesScannedEvent, CloseSheetEvent};
+use crate::components::{AlbacoreCamera, CameraBumper, EditSettingsBumper, PhotoWrap, PhotoViewer, RetakeToast, ScanResultsBumper, DocumentBumper, MotionBumper, LongExposureBumper};
+use crate::components::{AnimationPreview, FrameRateSlider, OnionOptions, ProjectOptions, FrameRow, PhotoBoothBumper, BoothShots, PresetsBumper, FiltersBumper};
+use crate::events::{DocumentPageAddedEvent, SaveDocumentEvent, DiscardDocumentEvent, OpenMotionSettingsEvent};
+use crate::motion::{MotionAlbum, MotionLog};
+use crate::stopmotion::{GifExport, StopMotion};
+use crate::events::{StopMotionEditEvent, OpenLongExposureEvent, OpenPhotoBoothEvent, PresetEvent};
+use crate::scanner::{self, ScanHistory};
+use crate::document::{self, DocumentDraft};
+use crate::service::{LensRequest, Rendering, Preview, PreviewKind, PhotoPreview};
+use crate::{storage, LensPlugin};
+use crate::service::{Photo, MyCameraRoll, Album};
+use crate::settings::CameraSetting;
+use crate::presets::{Preset, Presets};
+use crate::session::CameraSession;
+use crate::auto::AutoAdjust;
+use crate::modes::CaptureMode;
+use crate::frames::LivePhotos;
+use crate::events::{ResetSettingsEvent, SetCaptureModeEvent, OpenFiltersEvent, HistoryEvent};
+use crate::history::SettingsHistory;
+use crate::edit::{AspectRatio, Axis, EditOp, EditSession, EditTool, Edits};
+use crate::events::EditPhotoEvent;
+use crate::components::{CompareButton, CompareOptions};
+use crate::components::{EditCanvas, EditOptions, EditSteps, AdjustmentSlider, StraightenSlider, SettingsOptions, ToneBumper, ToneEditor, EffectSlider};
+use crate::events::{OpenToneEvent, ToneEvent, EffectEvent};
+use crate::effects::{Effect, EffectKind};
+use crate::markup::{self, Markup, MarkupTool};
+use crate::events::{MarkupEvent, RollSelectionEvent};
+use crate::service::BatchProgress;
+use crate::edit::CopiedEdits;
+use crate::compare::{BeforeAfter, CompareMode, Comparison, LiveComparison};
+use crate::events::{CompareEvent, ShowNeighbourEvent, InfoEvent, RollViewEvent};
+use crate::info;
+use crate::timeline::{RollView, RollSort, RollGrouping};
+use uuid::Uuid;
+
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Component)]
-pub struct CameraHome(Stack, Page, #[skip] Option<String>);
+pub struct CameraHome(Stack, Page, #[skip] Option<CameraSetting>, #[skip] usize, #[skip] Option<Instant>, #[skip] Option<ImageSettings>);
 
 impl AppPage for CameraHome {
     fn has_nav(&self) -> bool { true }
     fn navigate(mut self: Box<Self>, ctx: &mut Context, index: usize) -> Result<Box<dyn AppPage>, Box<dyn AppPage>> { 
+        self.close_comparison(ctx);
+        self.remember(ctx, true);
         match index {
             0 => Ok(Box::new(CameraRoll::new(ctx))),
+            1 => Ok(Box::new(ScanHistoryPage::new(ctx))),
+            2 => Ok(Box::new(MotionLogPage::new(ctx))),
+            3 => Ok(Box::new(StopMotionPage::new(ctx))),
             _ => Err(self),
         }
     }
@@ -40,21 +83,128 @@ impl CameraHome {
 
         let color = ctx.theme.colors.background.primary;
         let mut camera = camera.unwrap_or(AlbacoreCamera::new(ctx));
+        if let Some(camera) = camera.camera() {
+            CameraSetting::apply_all(camera, ctx.state().get_or_default::<CameraSession>().values.clone());
+        }
         let settings = camera.camera().as_ref().unwrap().get_settings().unwrap().clone();
         let view = CameraView::new(camera, CameraBumper::new(ctx, 0));
         let text_size = ctx.theme.fonts.size.h5;
         let text = Text::new(ctx, "Brightness", TextStyle::Heading, text_size, Align::Center);
         let bumper = EditSettingsBumper::new(ctx, settings);
         let content = Content::new(ctx, Offset::Start, vec![Box::new(view)]);
-        CameraHome(Stack::default(), Page::new(None, content, None), None)
+        let photos = ctx.state().get_or_default::<MyCameraRoll>().0.len();
+        ctx.state().set(LiveComparison::default());
+        CameraHome(Stack::default(), Page::new(None, content, None), None, photos, None, None)
+    }
+
+    // Changes are written once the camera has been left alone for a moment, so a slider drag is one write.
+    const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+    // Records the camera as it is now, from whichever control changed it, and saves it when due or when `flush` is set.
+    fn remember(&mut self, ctx: &mut Context, flush: bool) {
+        if self.5.is_some() { return; }
+        let Some(settings) = self.settings() else { return };
+        let mode = *ctx.state().get_or_default::<CaptureMode>();
+        let live = ctx.state().get_or_default::<LivePhotos>().0;
+        let session = CameraSession::capture(&settings, mode, live);
+        if session != *ctx.state().get_or_default::<CameraSession>() {
+            ctx.state().set(session);
+            self.4 = Some(Instant::now());
+        }
+        if self.4.is_some_and(|changed| flush || changed.elapsed() >= Self::SAVE_DELAY) {
+            ctx.state().get_or_default::<CameraSession>().save();
+            self.4 = None;
+        }
+    }
+
+    fn albacore(&mut self) -> Option<&mut AlbacoreCamera> {
+        self.1.content().find::<CameraView>()?.camera().as_mut()
+    }
+
+    // Adds whatever changed since `before` to the undo history.
+    fn record(&mut self, ctx: &mut Context, before: Option<ImageSettings>) {
+        if let (Some(before), Some(after)) = (before, self.settings()) {
+            let mut history = ctx.state().get_or_default::<SettingsHistory>().clone();
+            history.record(&before, &after);
+            ctx.state().set(history);
+        }
+    }
+
+    // Swaps the adjusted settings for the defaults while comparing, and back again after.
+    fn compare(&mut self, on: bool) {
+        match (on, self.5.is_some()) {
+            (true, false) => {
+                self.5 = self.settings();
+                let Some(albacore) = self.albacore() else { return };
+                albacore.auto().paused = true;
+                if let Some(camera) = albacore.camera() {
+                    CameraSetting::apply_all(camera, CameraSession::reset().values);
+                }
+            },
+            (false, true) => {
+                let Some(settings) = self.5.take() else { return };
+                let Some(albacore) = self.albacore() else { return };
+                albacore.auto().paused = false;
+                if let Some(camera) = albacore.camera() {
+                    CameraSetting::apply_all(camera, CameraSetting::ALL.map(|setting| (setting, setting.get(&settings))));
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // Opens the comparison on the viewfinder, or changes its mode. The settings are held back
+    // from the camera and applied to the preview instead, so both sides come from one frame.
+    fn open_comparison(&mut self, ctx: &mut Context, mode: CompareMode) {
+        let mut live = ctx.state().get_or_default::<LiveComparison>().clone();
+        match live.comparison.as_mut() {
+            Some(comparison) => comparison.mode = mode,
+            None => {
+                self.compare(true);
+                let Some(settings) = self.5.clone() else { return };
+                live = LiveComparison { comparison: Some(Comparison::new(mode)), settings, dragging: false };
+            }
+        }
+        ctx.state().set(live);
+    }
+
+    fn close_comparison(&mut self, ctx: &mut Context) {
+        ctx.state().set(LiveComparison::default());
+        self.compare(false);
+    }
+
+    // While comparing, a change goes to the held back settings rather than the camera.
+    fn change_held(&mut self, ctx: &mut Context, setting: CameraSetting, value: f32) {
+        let Some(held) = self.5.as_mut() else { return };
+        setting.set(held, value);
+        let mut live = ctx.state().get_or_default::<LiveComparison>().clone();
+        live.settings = held.clone();
+        ctx.state().set(live);
     }
 
+    // The held back settings while comparing, since the camera itself is at its defaults.
     fn settings(&mut self) -> Option<ImageSettings> {
-        if let Some(view) = &mut self.1.content().find::<CameraView>() {
-            let camera = view.camera().as_mut().unwrap().camera().as_mut().unwrap();
-            return Some(camera.get_settings().unwrap().clone());
+        if let Some(held) = &self.5 { return Some(held.clone()); }
+        let view = self.1.content().find::<CameraView>()?;
+        let camera = view.camera().as_mut()?.camera().as_mut()?;
+        camera.get_settings().ok()
+    }
+
+    // Prompts for a retake when the photo that just landed in the roll scored poorly.
+    fn check_quality(&mut self, ctx: &mut Context) {
+        let photos = ctx.state().get_or_default::<MyCameraRoll>().0.clone();
+        let Some(view) = self.1.content().find::<CameraView>() else { return };
+        if view.toast().as_ref().is_some_and(|t| t.expired()) {
+            *view.toast() = None;
         }
-        None
+        if photos.len() > self.3 {
+            if let Some(photo) = photos.last() {
+                if let Some(issue) = photo.quality.and_then(|q| q.issues().first().copied()) {
+                    *view.toast() = Some(RetakeToast::new(ctx, photo, issue));
+                }
+            }
+        }
+        self.3 = photos.len();
     }
 
     fn settings_bumper(&mut self) -> Option<&mut EditSettingsBumper> {
@@ -68,13 +218,117 @@ impl CameraHome {
 impl OnEvent for CameraHome {
     fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
         if event.downcast_ref::<TickEvent>().is_some() {
-            if let Some(i) = self.2.clone() {
+            if let Some(setting) = self.2 {
                 let settings = self.settings().unwrap();
                 if let Some(crb) = self.settings_bumper() {
-                    crb.set_slider_value(SettingsValue::get(settings, i.to_string()));
+                    crb.set_value(setting.get(&settings));
                     self.2 = None;
                 }
             }
+            // Keep the slider with the camera while an auto mode is moving it.
+            let auto = *ctx.state().get_or_default::<AutoAdjust>();
+            if let Some(settings) = self.settings() {
+                if let Some(crb) = self.settings_bumper().filter(|crb| auto.drives(crb.setting())) {
+                    crb.set_value(crb.setting().get(&settings));
+                }
+            }
+            self.check_quality(ctx);
+            self.remember(ctx, false);
+        } else if event.downcast_ref::<ResetSettingsEvent>().is_some() {
+            self.close_comparison(ctx);
+            let session = CameraSession::reset();
+            let before = self.settings();
+            if let Some(camera) = self.1.content().find::<CameraView>().unwrap().camera().as_mut().unwrap().camera() {
+                CameraSetting::apply_all(camera, session.values.clone());
+            }
+            self.record(ctx, before);
+            ctx.state().set(LivePhotos(session.live));
+            ctx.state().set(session.mode);
+            AutoAdjust::default().save();
+            ctx.state().set(AutoAdjust::default());
+            ctx.trigger_event(SetCaptureModeEvent(session.mode));
+            ctx.trigger_event(OpenSettingsEvent::Open);
+            ctx.hardware.haptic();
+        } else if event.downcast_ref::<TakePhotoEvent>().is_some() {
+            if let Some(view) = self.1.content().find::<CameraView>() {
+                *view.toast() = None;
+            }
+        } else if let Some(CodesScannedEvent(codes)) = event.downcast_ref::<CodesScannedEvent>() {
+            let mut history = ctx.state().get_or_default::<ScanHistory>().clone();
+            codes.iter().for_each(|code| history.record(code));
+            history.save();
+            ctx.state().set(history);
+            let sheet = ScanResultsBumper::new(ctx, codes);
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+        } else if event.downcast_ref::<DocumentPageAddedEvent>().is_some() {
+            let sheet = DocumentBumper::new(ctx);
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+        } else if event.downcast_ref::<SaveDocumentEvent>().is_some() {
+            let draft = ctx.state().get_or_default::<DocumentDraft>().clone();
+            ctx.state().set(DocumentDraft::default());
+            if !draft.pages.is_empty() {
+                let mut guard = ctx.get::<LensPlugin>();
+                let plugin = guard.get().0;
+                plugin.request(LensRequest::SaveDocument(draft.pages, draft.filter));
+            }
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(CameraBumper::new(ctx, 0))]
+        } else if event.downcast_ref::<DiscardDocumentEvent>().is_some() {
+            ctx.state().set(DocumentDraft::default());
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(CameraBumper::new(ctx, 0))]
+        } else if event.downcast_ref::<OpenLongExposureEvent>().is_some() {
+            let sheet = LongExposureBumper::new(ctx);
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+        } else if let Some(preset_event) = event.downcast_ref::<PresetEvent>() {
+            match preset_event {
+                PresetEvent::Open(message) => {
+                    let sheet = PresetsBumper::new(ctx, message.as_deref());
+                    *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+                },
+                PresetEvent::Save => {
+                    let settings = self.settings().unwrap();
+                    let Some(sheet) = self.1.content().find::<CameraView>().unwrap().bumper().find::<PresetsBumper>() else { return true };
+                    let preset = Preset::capture(sheet.name(ctx), &settings);
+                    let mut presets = ctx.state().get_or_default::<Presets>().clone();
+                    presets.insert(preset);
+                    presets.save();
+                    ctx.state().set(presets);
+                    ctx.trigger_event(PresetEvent::Open(None));
+                },
+                PresetEvent::Apply(index) => {
+                    let Some(preset) = ctx.state().get_or_default::<Presets>().presets.get(*index).cloned() else { return true };
+                    self.close_comparison(ctx);
+                    let before = self.settings();
+                    if let Some(camera) = self.1.content().find::<CameraView>().unwrap().camera().as_mut().unwrap().camera() {
+                        CameraSetting::apply_all(camera, preset.values.clone());
+                    }
+                    self.record(ctx, before);
+                    // A preset sets white balance and exposure explicitly, so it takes over from the auto modes.
+                    let mut auto = *ctx.state().get_or_default::<AutoAdjust>();
+                    preset.values.keys().for_each(|setting| auto.release(*setting));
+                    auto.save();
+                    ctx.state().set(auto);
+                    ctx.hardware.haptic();
+                }
+            }
+        } else if let Some(CompareEvent::Mode(mode)) = event.downcast_ref::<CompareEvent>() {
+            match mode {
+                Some(mode) => self.open_comparison(ctx, *mode),
+                None => self.close_comparison(ctx),
+            }
+        } else if let Some(OpenFiltersEvent(message)) = event.downcast_ref::<OpenFiltersEvent>() {
+            let sheet = FiltersBumper::new(ctx, message.as_deref());
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+        } else if event.downcast_ref::<OpenToneEvent>().is_some() {
+            let sheet = ToneBumper::new(ctx);
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+        } else if event.downcast_ref::<OpenPhotoBoothEvent>().is_some() {
+            let sheet = PhotoBoothBumper::new(ctx);
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+        } else if event.downcast_ref::<OpenMotionSettingsEvent>().is_some() {
+            let sheet = MotionBumper::new(ctx);
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(sheet)]
+        } else if event.downcast_ref::<CloseSheetEvent>().is_some() {
+            *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(CameraBumper::new(ctx, 0))]
         } else if let Some(s) = event.downcast_ref::<OpenSettingsEvent>() {
             match s {
                 OpenSettingsEvent::Open => {
@@ -82,132 +336,147 @@ impl OnEvent for CameraHome {
                     *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(EditSettingsBumper::new(ctx, settings))]
                 },
                 OpenSettingsEvent::Close => {
+                    self.close_comparison(ctx);
                     *self.1.content().find::<CameraView>().unwrap().bumper().items() = vec![Box::new(CameraBumper::new(ctx, 0))]
                 }
             }
-        } else if let Some(SettingsSelect(id)) = event.downcast_ref::<SettingsSelect>() {
+        } else if let Some(SettingsSelect(setting)) = event.downcast_ref::<SettingsSelect>() {
             if let Some(view) = &mut self.1.content().find::<CameraView>() {
-                let camera = view.camera().as_mut().unwrap().camera().as_mut().unwrap();
-                let settings = camera.get_settings().unwrap().clone();
-                let value = SettingsValue::get(settings.clone(), id.to_string());
-            
                 if let Some(crb) = view.bumper().find::<EditSettingsBumper>() {
-                    crb.set_text(id.to_string());
-                    crb.set_slider(settings, ctx, id.to_string());
-                    self.2 = Some(id.to_string());
+                    crb.set_text(*setting);
+                    crb.set_slider(ctx, *setting);
+                    // The new slider has no size until it's laid out, so its value is set on the next tick.
+                    self.2 = Some(*setting);
                 }
             }
-        } else if let Some(setting) = event.downcast_ref::<SetCameraSetting>() {
-            if let Some(camera) = self.1.content().find::<CameraView>().as_mut().unwrap().camera().as_mut().unwrap().camera() {
-                match setting {
-                    SetCameraSetting::Brightness(p) => camera.set_brightness((((p/100.0)*200.0)-100.0) as i16),
-                    SetCameraSetting::Contrast(p) => camera.set_contrast(((p/100.0)*2.0)-1.0),
-                    SetCameraSetting::Saturation(p) => camera.set_saturation(((p/100.0)*2.0)-1.0),
-                    SetCameraSetting::Gamma(p) => camera.set_gamma((0.1+(p/100.0)*(3.0-0.1))),
-                    SetCameraSetting::Exposure(p) => camera.set_exposure(((p/100.0)*4.0)-2.0),
-                    SetCameraSetting::Temperature(p) => camera.set_temperature(2000.0+(p/100.0)*8000.0),
-                    SetCameraSetting::WhiteBalanceR(p) => camera.set_white_balance_r(0.5+(p/100.0)*1.5),
-                    SetCameraSetting::WhiteBalanceG(p) => camera.set_white_balance_g(0.5+(p/100.0)*1.5),
-                    SetCameraSetting::WhiteBalanceB(p) => camera.set_white_balance_b(0.5+(p/100.0)*1.5),
-                };
+        } else if let Some(SetCameraSetting(setting, value)) = event.downcast_ref::<SetCameraSetting>() {
+            // Adjusting a setting by hand takes it back from its auto mode.
+            let mut auto = *ctx.state().get_or_default::<AutoAdjust>();
+            if auto.drives(*setting) {
+                auto.release(*setting);
+                auto.save();
+                ctx.state().set(auto);
             }
+            let before = self.settings();
+            if self.5.is_some() {
+                self.change_held(ctx, *setting, *value);
+            } else if let Some(camera) = self.1.content().find::<CameraView>().as_mut().unwrap().camera().as_mut().unwrap().camera() {
+                CameraSetting::apply_all(camera, [(*setting, *value)]);
+            }
+            self.record(ctx, before);
+        } else if let Some(history_event) = event.downcast_ref::<HistoryEvent>() {
+            if let HistoryEvent::Compare(on) = history_event {
+                // With the comparison open the camera is already at its defaults.
+                let mut live = ctx.state().get_or_default::<LiveComparison>().clone();
+                match live.comparison.as_mut() {
+                    Some(comparison) => {
+                        comparison.holding = *on;
+                        ctx.state().set(live);
+                    },
+                    None => self.compare(*on)
+                }
+                return true;
+            }
+            self.close_comparison(ctx);
+            let mut history = ctx.state().get_or_default::<SettingsHistory>().clone();
+            let Some(camera) = self.albacore().and_then(|a| a.camera().as_mut()) else { return true };
+            let step = match history_event {
+                HistoryEvent::Undo => history.undo(camera),
+                _ => history.redo(camera),
+            };
+            ctx.state().set(history);
+            let Some(step) = step else { return true };
+            // Stepping through history is a manual change, so it takes over from the auto modes.
+            let mut auto = *ctx.state().get_or_default::<AutoAdjust>();
+            step.changes.iter().for_each(|(setting, ..)| auto.release(*setting));
+            auto.save();
+            ctx.state().set(auto);
+            match step.changes.as_slice() {
+                [(setting, ..)] => ctx.trigger_event(SettingsSelect(*setting)),
+                _ => self.2 = self.settings_bumper().map(|crb| crb.setting()),
+            }
+            ctx.hardware.haptic();
         }
         true
     }
 }
 
-pub struct SettingsValue;
-impl SettingsValue {
-    pub fn get(settings: ImageSettings, i: String) -> f32 {
-        match i.as_str() {
-            "brightness" => ((settings.brightness as f32 + 100.0)/200.0)*100.0,
-            "saturation" => ((settings.saturation + 1.0)/2.0)*100.0,
-            "gamma" => ((settings.temperature - 2000.0)/8000.0)*100.0,
-            "exposure" => ((settings.exposure + 2.0)/4.0)*100.0,
-            "contrast" => ((settings.contrast + 1.0)/2.0)*100.0,
-            "temperature" => ((settings.temperature - 2000.0)/8000.0)*100.0,
-            "white_balance_r" => ((settings.white_balance_r - 0.5)/1.5)*100.0,
-            "white_balance_g" => ((settings.white_balance_g - 0.5)/1.5)*100.0,
-            "white_balance_b" => ((settings.white_balance_b - 0.5)/1.5)*100.0,
-            _ => 0.0
-        }
-    }
-
-    pub fn event(i: String) -> Box<dyn FnMut(&mut Context, f32)> {
-        match i.as_str() {
-            "brightness" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("Brightness action: {}", p);
-                ctx.trigger_event(SetCameraSetting::Brightness(p))
-            }),
-            "saturation" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("Saturation action: {}", p);
-                ctx.trigger_event(SetCameraSetting::Saturation(p))
-            }),
-            "gamma" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("Gamma action: {}", p);
-                ctx.trigger_event(SetCameraSetting::Gamma(p))
-            }),
-            "exposure" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("Exposure action: {}", p);
-                ctx.trigger_event(SetCameraSetting::Exposure(p))
-            }),
-            "contrast" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("Contrast action: {}", p);
-                ctx.trigger_event(SetCameraSetting::Contrast(p))
-            }),
-            "temperature" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("Temperature action: {}", p);
-                ctx.trigger_event(SetCameraSetting::Temperature(p))
-            }),
-            "white_balance_r" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("WhiteBalanceR action: {}", p);
-                ctx.trigger_event(SetCameraSetting::WhiteBalanceR(p))
-            }),
-            "white_balance_g" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("WhiteBalanceG action: {}", p);
-                ctx.trigger_event(SetCameraSetting::WhiteBalanceG(p))
-            }),
-            "white_balance_b" => Box::new(|ctx: &mut Context, p: f32| {
-                println!("WhiteBalanceB action: {}", p);
-                ctx.trigger_event(SetCameraSetting::WhiteBalanceB(p))
-            }),
-            _ => Box::new(move |ctx: &mut Context, p: f32| {
-                println!("Unknown event: {} with value: {}", i, p);
-            }),
-        }
-    }
-}
-
 #[derive(Debug, Component)]
-pub struct CameraView(Stack, Option<AlbacoreCamera>, Bumper);
+pub struct CameraView(Stack, Option<AlbacoreCamera>, Option<RetakeToast>, Bumper);
 impl OnEvent for CameraView {}
 impl CameraView {
     pub fn new(camera: AlbacoreCamera, bumper: Bumper) -> Self {
-        CameraView(Stack(Offset::Center, Offset::End, Size::Fit, Size::Fit, Padding::default()), Some(camera), bumper)
+        CameraView(Stack(Offset::Center, Offset::End, Size::Fit, Size::Fit, Padding::default()), Some(camera), None, bumper)
     }
 
-    pub fn bumper(&mut self) -> &mut Bumper { &mut self.2 }
+    pub fn bumper(&mut self) -> &mut Bumper { &mut self.3 }
     pub fn camera(&mut self) -> &mut Option<AlbacoreCamera> {&mut self.1}
+    pub fn toast(&mut self) -> &mut Option<RetakeToast> {&mut self.2}
 }
 
 #[derive(Debug, Component)]
-pub struct CameraRoll(Stack, Page, #[skip] Option<(String, (f32, f32))>);
+pub struct CameraRoll(Stack, Page, #[skip] Option<Photo>, #[skip] bool, #[skip] Option<Vec<Uuid>>, #[skip] BatchProgress);
 
 impl AppPage for CameraRoll {
     fn has_nav(&self) -> bool { true }
     fn navigate(mut self: Box<Self>, ctx: &mut Context, index: usize) -> Result<Box<dyn AppPage>, Box<dyn AppPage>> { 
         match index {
             0 => Ok(Box::new(CameraHome::new(ctx, None))),
-            1 => Ok(Box::new(ViewPhoto::new(ctx, self.2.unwrap()))),
+            1 => Ok(Box::new(ViewPhoto::new(ctx, self.2.unwrap(), Album::Roll))),
             _ => Err(self),
         }
     }
 }
 
 impl OnEvent for CameraRoll {
-    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
-        if let Some(SelectImageEvent(image, s)) = event.downcast_ref::<SelectImageEvent>() {
-            self.2 = Some((image.to_string(), *s)) //Some((i.clone(), *s))
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(SelectImageEvent(photo)) = event.downcast_ref::<SelectImageEvent>() {
+            self.2 = Some(photo.clone())
+        } else if let Some(ReviewLowQualityEvent(review)) = event.downcast_ref::<ReviewLowQualityEvent>() {
+            self.3 = *review;
+            self.4 = None;
+            self.1 = Self::page(ctx, *review, None);
+        } else if event.downcast_ref::<TickEvent>().is_some() {
+            // Pasted edits land one photo at a time, so the thumbnails follow along.
+            let progress = *ctx.state().get_or_default::<BatchProgress>();
+            if progress != self.5 {
+                self.5 = progress;
+                if let Some(wrap) = self.1.content().find::<PhotoWrap>() {
+                    wrap.refresh(ctx);
+                }
+                if !self.3 {
+                    *self.1.bumper() = Self::bumper(ctx, self.3, self.4.as_deref());
+                }
+            }
+        } else if let Some(change) = event.downcast_ref::<RollViewEvent>() {
+            let mut view = *ctx.state().get_or_default::<RollView>();
+            match change {
+                RollViewEvent::Sort(sort) => view.sort = *sort,
+                RollViewEvent::Grouping(grouping) => view.grouping = *grouping,
+            }
+            view.save();
+            ctx.state().set(view);
+            self.1 = Self::page(ctx, self.3, self.4.as_deref());
+        } else if let Some(selection) = event.downcast_ref::<RollSelectionEvent>() {
+            match selection {
+                RollSelectionEvent::Begin => self.4 = Some(Vec::new()),
+                RollSelectionEvent::Toggle(id) => if let Some(ids) = &mut self.4 {
+                    match ids.iter().position(|i| i == id) {
+                        Some(i) => { ids.remove(i); },
+                        None => ids.push(*id),
+                    }
+                },
+                RollSelectionEvent::All => self.4 = Some(Self::photos(ctx, self.3).iter().map(|p| p.id).collect()),
+                RollSelectionEvent::End => self.4 = None,
+                RollSelectionEvent::PasteEdits => {
+                    let (Some(ids), CopiedEdits(Some(look))) = (self.4.take(), ctx.state().get_or_default::<CopiedEdits>().clone()) else { return true };
+                    let photos = Self::photos(ctx, self.3).iter().filter(|p| ids.contains(&p.id)).map(Rendering::of).collect::<Vec<_>>();
+                    ctx.get::<LensPlugin>().get().0.request(LensRequest::ApplyEdits(photos, look));
+                    ctx.trigger_event(RollSelectionEvent::End);
+                },
+                RollSelectionEvent::CancelPaste => ctx.get::<LensPlugin>().get().0.request(LensRequest::CancelBatch),
+            }
+            *self.1.bumper() = Self::bumper(ctx, self.3, self.4.as_deref());
         }
         true
     }
@@ -216,44 +485,921 @@ impl OnEvent for CameraRoll {
 impl CameraRoll {
     pub fn new(ctx: &mut Context) -> Self {
         ctx.theme.layout = LayoutResources::default();
-        let photo_wrap = PhotoWrap::new(ctx);
-        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(NavigateEvent(0)));
-        let header = Header::stack(ctx, Some(back), "Library", None);
-        let content = Content::new(ctx, Offset::Start, vec![Box::new(photo_wrap)]);
-        CameraRoll(Stack::default(), Page::new(Some(header), content, None), None)
+        let progress = *ctx.state().get_or_default::<BatchProgress>();
+        CameraRoll(Stack::default(), Self::page(ctx, false, None), None, false, None, progress)
+    }
+
+    /// The photos edits can be pasted onto: what's showing, less documents.
+    fn photos(ctx: &mut Context, review: bool) -> Vec<Photo> {
+        ctx.state().get_or_default::<MyCameraRoll>().visible()
+            .filter(|p| !p.is_document() && (!review || p.is_low_quality())).cloned().collect()
+    }
+
+    fn bumper(ctx: &mut Context, review: bool, selection: Option<&[Uuid]>) -> Option<Bumper> {
+        let progress = *ctx.state().get_or_default::<BatchProgress>();
+        if progress.running() {
+            let label = format!("Cancel Pasting ({} of {})", progress.done + 1, progress.total);
+            let cancel = Button::secondary(ctx, None, &label, None, |ctx: &mut Context| ctx.trigger_event(RollSelectionEvent::CancelPaste), None);
+            return Some(Bumper::single_button(ctx, cancel));
+        }
+        match selection {
+            Some(ids) => {
+                let copied = ctx.state().get_or_default::<CopiedEdits>().0.is_some();
+                let cancel = Button::secondary(ctx, None, "Cancel", None, |ctx: &mut Context| ctx.trigger_event(RollSelectionEvent::End), None);
+                let all = Button::secondary(ctx, None, "Select All", None, |ctx: &mut Context| ctx.trigger_event(RollSelectionEvent::All), None);
+                let label = match copied {
+                    true => format!("Paste Edits ({})", ids.len()),
+                    false => "No Edits Copied".to_string()
+                };
+                let mut paste = Button::primary(ctx, &label, |ctx: &mut Context| ctx.trigger_event(RollSelectionEvent::PasteEdits));
+                if !copied || ids.is_empty() {
+                    *paste.status() = ButtonState::Disabled;
+                    paste.color(ctx);
+                }
+                Some(Bumper::new(ctx, vec![Box::new(cancel), Box::new(all), Box::new(paste)]))
+            },
+            None => (!review).then(|| {
+                let select = Button::secondary(ctx, None, "Select", None, |ctx: &mut Context| ctx.trigger_event(RollSelectionEvent::Begin), None);
+                Bumper::single_button(ctx, select)
+            })
+        }
+    }
+
+    // The review view is the same library filtered down to photos whose quality
+    // scores flagged an issue, with a bulk delete for cleanup.
+    fn page(ctx: &mut Context, review: bool, selection: Option<&[Uuid]>) -> Page {
+        let photo_wrap = PhotoWrap::new(ctx, review, selection);
+        let back = match review {
+            true => IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(ReviewLowQualityEvent(false))),
+            false => IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(NavigateEvent(0)))
+        };
+        let low = ctx.state().get_or_default::<MyCameraRoll>().0.iter()
+            .filter(|p| p.is_low_quality()).map(|p| p.id).collect::<Vec<_>>();
+        let (title, review_button, bumper) = match review {
+            true => {
+                let bumper = (!low.is_empty()).then(|| {
+                    let label = format!("Delete {} photo{}", low.len(), if low.len() == 1 {""} else {"s"});
+                    let button = Button::primary(ctx, &label, move |ctx: &mut Context| {
+                        ctx.trigger_event(DeletePhotosEvent(low.clone()));
+                        ctx.trigger_event(ReviewLowQualityEvent(true));
+                    });
+                    Bumper::single_button(ctx, button)
+                });
+                ("Review Low Quality", None, bumper)
+            },
+            false => {
+                let button = (!low.is_empty()).then(|| IconButton::navigation(ctx, "warning", |ctx: &mut Context| ctx.trigger_event(ReviewLowQualityEvent(true))));
+                ("Library", button, Self::bumper(ctx, review, selection))
+            }
+        };
+        let header = Header::stack(ctx, Some(back), title, review_button);
+        let view = *ctx.state().get_or_default::<RollView>();
+        let sorts = RollSort::ALL.into_iter().map(|sort| (sort.label(), sort == view.sort, RollViewEvent::Sort(sort))).collect();
+        let mut items: Vec<Box<dyn Drawable>> = vec![Box::new(EditOptions::new(ctx, sorts))];
+        // Sorting by size groups by size too, so there's nothing to group by.
+        if view.sort != RollSort::Largest {
+            let groupings = RollGrouping::ALL.into_iter().map(|grouping| (grouping.label(), grouping == view.grouping, RollViewEvent::Grouping(grouping))).collect();
+            items.push(Box::new(EditOptions::new(ctx, groupings)));
+        }
+        items.push(Box::new(photo_wrap));
+        let content = Content::new(ctx, Offset::Start, items);
+        Page::new(Some(header), content, bumper)
     }
 }
 
 #[derive(Debug, Component)]
-pub struct ViewPhoto(Stack, Page);
-impl OnEvent for ViewPhoto {}
+pub struct ViewPhoto(Stack, Page, #[skip] Album, #[skip] Option<Photo>, #[skip] Photo, #[skip] Option<Markup>, #[skip] Option<BeforeAfter>, #[skip] bool, #[skip] Option<PreviewKind>);
+
+impl OnEvent for ViewPhoto {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(SelectImageEvent(photo)) = event.downcast_ref::<SelectImageEvent>() {
+            self.3 = Some(photo.clone())
+        } else if event.downcast_ref::<TickEvent>().is_some() && self.8.is_some() {
+            let preview = ctx.state().get_or_default::<PhotoPreview>().0.clone().filter(|p| p.id() == self.4.id);
+            if let Some(preview) = preview {
+                ctx.state().set(PhotoPreview(None));
+                self.8 = None;
+                self.preview(ctx, preview);
+            }
+        } else if event.downcast_ref::<TickEvent>().is_some() && self.2 == Album::Roll && self.5.is_none() && self.6.is_none() && !self.7 {
+            // Saved edits are rendered in the background and reach the roll a moment later.
+            let saved = ctx.state().get_or_default::<MyCameraRoll>().get(self.4.id)
+                .filter(|p| p.edits != self.4.edits || p.annotations != self.4.annotations).cloned();
+            if let Some(photo) = saved {
+                *self = ViewPhoto::new(ctx, photo, self.2);
+            }
+        } else if let Some(change) = event.downcast_ref::<MarkupEvent>() {
+            self.markup(ctx, change);
+        } else if let Some(change) = event.downcast_ref::<CompareEvent>() {
+            self.compare(ctx, change);
+        } else if let Some(change) = event.downcast_ref::<InfoEvent>() {
+            self.info(ctx, change);
+        } else if let Some(ShowNeighbourEvent(step)) = event.downcast_ref::<ShowNeighbourEvent>() {
+            let photos = Self::album(ctx, self.2);
+            let Some(i) = photos.iter().position(|p| p.id == self.4.id) else { return true };
+            if let Some(photo) = i.checked_add_signed(*step).and_then(|i| photos.get(i)) {
+                *self = ViewPhoto::new(ctx, photo.clone(), self.2);
+            }
+        }
+        true
+    }
+}
 
 impl AppPage for ViewPhoto {
     fn has_nav(&self) -> bool { true }
     fn navigate(self: Box<Self>, ctx: &mut Context, index: usize) -> Result<Box<dyn AppPage>, Box<dyn AppPage>> { 
-        match index {
-            0 => Ok(Box::new(CameraRoll::new(ctx))),
+        match (index, self.2) {
+            (0, Album::Roll) => Ok(Box::new(CameraRoll::new(ctx))),
+            (0, Album::Motion) => Ok(Box::new(MotionLogPage::new(ctx))),
+            (1, album) if self.3.is_some() => Ok(Box::new(ViewPhoto::new(ctx, self.3.unwrap(), album))),
+            (2, Album::Roll) => Ok(Box::new(EditPhoto::new(ctx, self.4))),
             _ => Err(self),
         }
     }
 }
 
 impl ViewPhoto {
-    pub fn new(ctx: &mut Context, image: (String, (f32, f32))) -> Self {
+    pub fn new(ctx: &mut Context, photo: Photo, album: Album) -> Self {
         ctx.theme.layout.bumper_max = f32::MAX;
         ctx.theme.layout.content_max = f32::MAX;
         ctx.theme.layout.content_padding = 0.0;
-        let img = EncodedImage::decode(ctx, &image.0);
-        let exp_img = ExpandableImage::new(img, Some((image.1.0, image.1.1)));
-        let content = Content::new(ctx, Offset::Center, vec![Box::new(exp_img)]);
+        ViewPhoto(Stack::default(), Self::page(ctx, &photo, album), album, None, photo, None, None, false, None)
+    }
+
+    // The photos a swipe steps through, in the order they're listed.
+    fn album(ctx: &mut Context, album: Album) -> Vec<Photo> {
+        match album {
+            Album::Roll => {
+                let photos = ctx.state().get_or_default::<MyCameraRoll>().visible().cloned().collect();
+                ctx.state().get_or_default::<RollView>().sections(photos).into_iter().flat_map(|s| s.photos).collect()
+            },
+            Album::Motion => ctx.state().get_or_default::<MotionAlbum>().0.clone(),
+        }
+    }
+
+    fn neighbours(ctx: &mut Context, photo: &Photo, album: Album) -> [Option<Photo>; 2] {
+        let photos = Self::album(ctx, album);
+        let Some(i) = photos.iter().position(|p| p.id == photo.id) else { return [None, None] };
+        [i.checked_sub(1).and_then(|i| photos.get(i)).cloned(), photos.get(i + 1).cloned()]
+    }
+
+    fn page(ctx: &mut Context, photo: &Photo, album: Album) -> Page {
+        let issues = photo.quality.map(|q| q.issues()).unwrap_or_default();
+        let mut items: Vec<Box<dyn Drawable>> = vec![];
+        if !issues.is_empty() {
+            let label = issues.iter().map(|i| i.label()).collect::<Vec<_>>().join(" · ");
+            items.push(Box::new(Alert::new(ctx, &label)));
+        }
+        if !photo.title.is_empty() || !photo.caption.is_empty() {
+            let font_size = ctx.theme.fonts.size.sm;
+            let label = [photo.title.as_str(), photo.caption.as_str()].into_iter().filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" · ");
+            items.push(Box::new(Text::new(ctx, &label, TextStyle::Primary, font_size, Align::Center)));
+        }
+        if let Some(exposure) = photo.exposure {
+            let font_size = ctx.theme.fonts.size.sm;
+            items.push(Box::new(Text::new(ctx, &exposure.label(), TextStyle::Secondary, font_size, Align::Center)));
+        }
+        match photo.is_document() {
+            true => photo.all_pages().for_each(|page| {
+                let rgba = EncodedImage::decode_rgba(page);
+                let size = (rgba.width() as f32, rgba.height() as f32);
+                let image = ctx.assets.add_image(rgba);
+                items.push(Box::new(ExpandableImage::new(image, Some(size))));
+            }),
+            false => {
+                let neighbours = Self::neighbours(ctx, photo, album);
+                items.push(Box::new(PhotoViewer::new(ctx, photo, neighbours)))
+            }
+        }
+        if photo.is_booth_strip() {
+            let shots = ctx.state().get_or_default::<MyCameraRoll>().shots(photo);
+            items.push(Box::new(BoothShots::new(ctx, shots)));
+        }
+        let content = Content::new(ctx, Offset::Center, items);
 
+        let info = Button::secondary(ctx, Some("info"), "Info", None, |ctx: &mut Context| ctx.trigger_event(InfoEvent::Open), None);
+        let bumper = match photo.is_document() {
+            // The PDF opens in the desktop's viewer, where it can be saved or sent on. Mobile can't open files.
+            true if scanner::can_launch() => {
+                let pages = photo.all_pages().cloned().collect::<Vec<_>>();
+                let id = photo.id;
+                let label = format!("Open PDF ({} page{})", pages.len(), if pages.len() == 1 {""} else {"s"});
+                let button = Button::primary(ctx, &label, move |_ctx: &mut Context| {
+                    let pages = pages.iter().map(|p| EncodedImage::decode_rgba(p)).collect::<Vec<_>>();
+                    let name = format!("{id}.pdf");
+                    if !storage::open_export(&name, &document::pdf(&pages)) {
+                        log::warn!("Could not open {}", storage::path(&name).display());
+                    }
+                });
+                Bumper::double_button(ctx, info, button)
+            },
+            true => Bumper::single_button(ctx, info),
+            // Edits and markup are saved back to the roll, so motion captures are left as they are.
+            false => match album {
+                Album::Roll => {
+                    let markup = Button::secondary(ctx, None, "Markup", None, |ctx: &mut Context| ctx.trigger_event(MarkupEvent::Open), None);
+                    let edit = Button::secondary(ctx, Some("edit"), "Edit", None, |ctx: &mut Context| ctx.trigger_event(NavigateEvent(2)), None);
+                    let mut buttons: Vec<Box<dyn Drawable>> = vec![Box::new(info)];
+                    if photo.edited.is_some() {
+                        let compare = Button::secondary(ctx, None, "Compare", None, |ctx: &mut Context| ctx.trigger_event(CompareEvent::Mode(Some(CompareMode::Split))), None);
+                        buttons.push(Box::new(compare));
+                    }
+                    buttons.extend([Box::new(markup) as Box<dyn Drawable>, Box::new(edit)]);
+                    Bumper::new(ctx, buttons)
+                },
+                Album::Motion => Bumper::single_button(ctx, info)
+            }
+        };
+
+        let title = if photo.is_document() {"View Document"} else {"View Photo"};
         let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(NavigateEvent(0)));
+        let shared = photo.clone();
         let share = IconButton::navigation(ctx, "share", move |ctx: &mut Context| {
-            let decoded = EncodedImage::decode_rgba(&image.clone().0);
+            let decoded = EncodedImage::decode_rgba(shared.shown());
             ctx.hardware.share_image(decoded);
         });
         
-        let header = Header::stack(ctx, Some(back), "View Photo", Some(share));
-        ViewPhoto(Stack::default(), Page::new(Some(header), content, None))
+        let header = Header::stack(ctx, Some(back), title, Some(share));
+        Page::new(Some(header), content, Some(bumper))
+    }
+
+    fn info(&mut self, ctx: &mut Context, change: &InfoEvent) {
+        match change {
+            InfoEvent::Open => {
+                self.1 = Self::info_page(ctx, &self.4, self.2);
+                self.7 = true;
+            },
+            InfoEvent::Copy => {
+                let photo = self.titled();
+                ctx.hardware.copy(info::report(&photo, &info::sections(&photo, self.2)));
+            },
+            InfoEvent::Done => {
+                let photo = self.titled();
+                if photo != self.4 {
+                    Self::retitle(ctx, &photo, self.2);
+                    self.4 = photo;
+                }
+                self.close_info(ctx);
+            },
+            InfoEvent::Cancel => self.close_info(ctx),
+        }
+    }
+
+    fn close_info(&mut self, ctx: &mut Context) {
+        self.7 = false;
+        self.1 = Self::page(ctx, &self.4, self.2);
+    }
+
+    // The photo with the title and caption as typed on the info sheet.
+    fn titled(&mut self) -> Photo {
+        let mut photo = self.4.clone();
+        let content = self.1.content();
+        if let Some(input) = content.find_at::<TextInput>(0) { photo.title = input.value().trim().to_string(); }
+        if let Some(input) = content.find_at::<TextInput>(1) { photo.caption = input.value().trim().to_string(); }
+        photo
+    }
+
+    // Only the title and caption are written back, so edits still rendering aren't overwritten.
+    fn retitle(ctx: &mut Context, photo: &Photo, album: Album) {
+        let apply = |p: &mut Photo| if p.id == photo.id {
+            p.title = photo.title.clone();
+            p.caption = photo.caption.clone();
+        };
+        match album {
+            Album::Roll => {
+                let mut roll = ctx.state().get_or_default::<MyCameraRoll>().clone();
+                roll.0.iter_mut().for_each(apply);
+                roll.save();
+                ctx.state().set(roll);
+            },
+            Album::Motion => {
+                MotionAlbum::update(|motion| motion.0.iter_mut().for_each(apply));
+                let mut motion = ctx.state().get_or_default::<MotionAlbum>().clone();
+                motion.0.iter_mut().for_each(apply);
+                ctx.state().set(motion);
+            }
+        }
+    }
+
+    fn info_page(ctx: &mut Context, photo: &Photo, album: Album) -> Page {
+        let mut items: Vec<Box<dyn Drawable>> = vec![
+            Box::new(TextInput::new(ctx, Some(&photo.title), Some("Title"), "Add a title...", None, TextInput::NO_ICON, false)),
+            Box::new(TextInput::new(ctx, Some(&photo.caption), Some("Caption"), "Add a caption...", None, TextInput::NO_ICON, false)),
+        ];
+        info::sections(photo, album).into_iter().for_each(|(heading, rows)| {
+            let table = rows.iter().map(|(label, value)| (label.as_str(), value.as_str())).collect();
+            items.push(Box::new(DataItem::new(ctx, None, heading, None, None, Some(table), None)));
+        });
+        let content = Content::new(ctx, Offset::Start, items);
+
+        let copy = Button::secondary(ctx, Some("copy"), "Copy as Text", None, |ctx: &mut Context| ctx.trigger_event(InfoEvent::Copy), None);
+        let done = Button::primary(ctx, "Done", |ctx: &mut Context| ctx.trigger_event(InfoEvent::Done));
+        let bumper = Bumper::double_button(ctx, copy, done);
+
+        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(InfoEvent::Cancel));
+        let header = Header::stack(ctx, Some(back), "Info", None);
+        Page::new(Some(header), content, Some(bumper))
+    }
+
+    fn markup(&mut self, ctx: &mut Context, change: &MarkupEvent) {
+        if let MarkupEvent::Open = change {
+            self.request_preview(ctx, PreviewKind::Markup);
+            return;
+        }
+        // The label is kept on the markup so it survives the page being rebuilt.
+        let label = self.1.content().find::<TextInput>().map(|input| input.value().clone());
+        let Some(markup) = self.5.as_mut() else { return };
+        if let Some(label) = label {
+            markup.label = label;
+        }
+        match change {
+            MarkupEvent::Tool(tool) => markup.tool = *tool,
+            MarkupEvent::Color(color) => markup.color = *color,
+            MarkupEvent::Width(width) => markup.width = *width,
+            MarkupEvent::Drag(start, end, done) => markup.drag(*start, *end, *done),
+            MarkupEvent::Undo => markup.undo(),
+            MarkupEvent::Redo => markup.redo(),
+            MarkupEvent::Clear => markup.clear(),
+            MarkupEvent::Done => {
+                if markup.annotations != self.4.annotations {
+                    let rendering = Rendering { annotations: markup.annotations.clone(), ..Rendering::of(&self.4) };
+                    ctx.get::<LensPlugin>().get().0.request(LensRequest::Render(Box::new(rendering)));
+                }
+                self.close_markup(ctx);
+                return;
+            },
+            MarkupEvent::Cancel => {
+                self.close_markup(ctx);
+                return;
+            },
+            MarkupEvent::Open => {}
+        }
+        match change {
+            MarkupEvent::Tool(_) | MarkupEvent::Color(_) | MarkupEvent::Width(_) => self.1 = Self::markup_page(ctx, markup),
+            _ => if let Some(canvas) = self.1.content().find::<EditCanvas>() {
+                canvas.set_image(ctx, markup.render());
+            }
+        }
+    }
+
+    // The service renders a reduced copy, and the tick opens it once it's back.
+    fn request_preview(&mut self, ctx: &mut Context, kind: PreviewKind) {
+        ctx.state().set(PhotoPreview(None));
+        self.8 = Some(kind);
+        let request = LensRequest::Preview(Box::new(Rendering::of(&self.4)), kind);
+        ctx.get::<LensPlugin>().get().0.request(request);
+    }
+
+    fn preview(&mut self, ctx: &mut Context, preview: Preview) {
+        match preview {
+            // Marks are drawn over the photo as edited, so they're placed against what will be shared.
+            Preview::Markup(_, base) => {
+                let markup = Markup::new(EncodedImage::decode_rgba(&base), self.4.annotations.clone(), self.4.edits.clone(), self.4.size);
+                self.1 = Self::markup_page(ctx, &markup);
+                self.5 = Some(markup);
+            },
+            Preview::Compare(_, mode, original, edited) => {
+                let before_after = BeforeAfter::new(EncodedImage::decode_rgba(&original), EncodedImage::decode_rgba(&edited), mode);
+                self.1 = Self::compare_page(ctx, &before_after);
+                self.6 = Some(before_after);
+            },
+        }
+    }
+
+    fn close_markup(&mut self, ctx: &mut Context) {
+        self.5 = None;
+        self.1 = Self::page(ctx, &self.4, self.2);
+    }
+
+    fn compare(&mut self, ctx: &mut Context, change: &CompareEvent) {
+        match (change, self.6.as_mut()) {
+            (CompareEvent::Mode(Some(mode)), None) => {
+                self.request_preview(ctx, PreviewKind::Compare(*mode));
+                return;
+            },
+            (CompareEvent::Mode(None), _) => {
+                self.6 = None;
+                self.1 = Self::page(ctx, &self.4, self.2);
+                return;
+            },
+            (_, None) => return,
+            (CompareEvent::Mode(Some(mode)), Some(before_after)) => before_after.comparison.mode = *mode,
+            (CompareEvent::Split(split), Some(before_after)) => before_after.comparison.split = *split,
+            (CompareEvent::Hold(on), Some(before_after)) => before_after.comparison.holding = *on,
+        }
+        if let (Some(canvas), Some(before_after)) = (self.1.content().find::<EditCanvas>(), &self.6) {
+            canvas.set_image(ctx, before_after.render());
+        }
+    }
+
+    fn compare_page(ctx: &mut Context, before_after: &BeforeAfter) -> Page {
+        let canvas = EditCanvas::with_drag(ctx, before_after.render(), |ctx, _, end, _| ctx.trigger_event(CompareEvent::Split(end.0)));
+        let options = CompareOptions::new(ctx, Some(before_after.comparison.mode));
+        let hold = CompareButton::new(ctx, "Hold for Original", |ctx, on| ctx.trigger_event(CompareEvent::Hold(on)));
+        let content = Content::new(ctx, Offset::Start, vec![Box::new(canvas), Box::new(options), Box::new(hold)]);
+
+        let done = Button::primary(ctx, "Done", |ctx: &mut Context| ctx.trigger_event(CompareEvent::Mode(None)));
+        let bumper = Bumper::single_button(ctx, done);
+
+        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(CompareEvent::Mode(None)));
+        let header = Header::stack(ctx, Some(back), "Compare", None);
+        Page::new(Some(header), content, Some(bumper))
+    }
+
+    fn markup_page(ctx: &mut Context, markup: &Markup) -> Page {
+        let canvas = EditCanvas::with_drag(ctx, markup.render(), |ctx, start, end, done| ctx.trigger_event(MarkupEvent::Drag(start, end, done)));
+        let tools = MarkupTool::ALL.into_iter().map(|tool| (tool.label(), tool == markup.tool, MarkupEvent::Tool(tool))).collect();
+        let colors = markup::COLORS.iter().enumerate().map(|(i, (label, _))| (*label, i == markup.color, MarkupEvent::Color(i))).collect();
+        let widths = markup::WIDTHS.iter().enumerate().map(|(i, (label, _))| (*label, i == markup.width, MarkupEvent::Width(i))).collect();
+        let history = vec![("Undo", false, MarkupEvent::Undo), ("Redo", false, MarkupEvent::Redo), ("Clear", false, MarkupEvent::Clear)];
+        let mut items: Vec<Box<dyn Drawable>> = vec![
+            Box::new(canvas), Box::new(EditOptions::new(ctx, tools)), Box::new(EditOptions::new(ctx, colors)), Box::new(EditOptions::new(ctx, widths))
+        ];
+        if markup.tool == MarkupTool::Text {
+            let label = (!markup.label.is_empty()).then_some(markup.label.as_str());
+            items.push(Box::new(TextInput::new(ctx, label, None, "Label, then tap where it goes", None, TextInput::NO_ICON, false)));
+        }
+        items.push(Box::new(EditOptions::new(ctx, history)));
+        let content = Content::new(ctx, Offset::Start, items);
+
+        let cancel = Button::secondary(ctx, None, "Cancel", None, |ctx: &mut Context| ctx.trigger_event(MarkupEvent::Cancel), None);
+        let done = Button::primary(ctx, "Done", |ctx: &mut Context| ctx.trigger_event(MarkupEvent::Done));
+        let bumper = Bumper::double_button(ctx, cancel, done);
+
+        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(MarkupEvent::Cancel));
+        let header = Header::stack(ctx, Some(back), "Markup", None);
+        Page::new(Some(header), content, Some(bumper))
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct EditPhoto(Stack, Page, #[skip] EditSession);
+
+impl AppPage for EditPhoto {
+    fn has_nav(&self) -> bool { true }
+    fn navigate(self: Box<Self>, ctx: &mut Context, index: usize) -> Result<Box<dyn AppPage>, Box<dyn AppPage>> { 
+        match index {
+            0 => {
+                let photo = ctx.state().get_or_default::<MyCameraRoll>().get(self.2.photo.id).cloned().unwrap_or(self.2.photo);
+                Ok(Box::new(ViewPhoto::new(ctx, photo, Album::Roll)))
+            },
+            _ => Err(self),
+        }
+    }
+}
+
+impl OnEvent for EditPhoto {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            let first = self.2.preview.is_none();
+            if self.2.poll() {
+                // The first render brings the photo's real shape and its histogram.
+                match first {
+                    true => self.1 = Self::page(ctx, &self.2),
+                    false => self.show(ctx)
+                }
+            }
+        } else if let Some(edit) = event.downcast_ref::<EditPhotoEvent>() {
+            let session = &mut self.2;
+            match edit {
+                EditPhotoEvent::Tool(tool) => {
+                    if *tool == EditTool::Crop {
+                        session.edits.crop_last();
+                    }
+                    session.tool = *tool;
+                    session.adjusting = CameraSetting::ALL[0];
+                    session.draft = None;
+                    session.refresh();
+                    self.1 = Self::page(ctx, &self.2);
+                    return true;
+                },
+                EditPhotoEvent::Ratio(ratio) => {
+                    session.ratio = *ratio;
+                    // Refit the current crop from its top left corner.
+                    if let Some(EditOp::Crop(x, y, w, h)) = session.edits.crop() {
+                        if let Some(crop) = session.crop((x, y), (x + w, y + h)) {
+                            session.edits.push(crop);
+                        }
+                    }
+                    self.1 = Self::page(ctx, &self.2);
+                    return true;
+                },
+                EditPhotoEvent::Drag(start, end, done) => {
+                    if session.tool != EditTool::Crop { return true; }
+                    session.draft = session.crop(*start, *end);
+                    if *done {
+                        if let Some(crop) = session.draft.take() {
+                            session.edits.push(crop);
+                        }
+                    }
+                    self.show(ctx);
+                    if !*done { return true; }
+                },
+                EditPhotoEvent::Apply(op) => {
+                    session.edits.push(op.clone());
+                    session.refresh();
+                },
+                EditPhotoEvent::Remove(i) => {
+                    session.edits.remove(*i);
+                    session.refresh();
+                },
+                EditPhotoEvent::Revert => {
+                    session.edits = Edits::default();
+                    session.refresh();
+                },
+                EditPhotoEvent::Copy => {
+                    ctx.state().set(CopiedEdits(Some(session.edits.look())));
+                    return true;
+                },
+                EditPhotoEvent::Paste => {
+                    let CopiedEdits(Some(look)) = ctx.state().get_or_default::<CopiedEdits>().clone() else { return true };
+                    session.edits = session.edits.with_look(&look);
+                    session.refresh();
+                    self.1 = Self::page(ctx, &self.2);
+                    return true;
+                },
+                EditPhotoEvent::Save => {
+                    let rendering = Rendering { edits: session.edits.clone(), ..Rendering::of(&session.photo) };
+                    ctx.get::<LensPlugin>().get().0.request(LensRequest::Render(Box::new(rendering)));
+                    ctx.trigger_event(NavigateEvent(0));
+                    return true;
+                },
+            }
+            self.steps(ctx);
+            // Removing a step or reverting can change the tone and what it's drawn over, or the effects.
+            if matches!(edit, EditPhotoEvent::Remove(_) | EditPhotoEvent::Revert) {
+                match self.2.tool {
+                    EditTool::Tone => {
+                        let editor = ToneEditor::new(ctx, self.2.edits.tone(), self.2.tone_histogram(), false);
+                        if let Some(tone) = self.1.content().find::<ToneEditor>() {
+                            *tone = editor;
+                        }
+                    },
+                    EditTool::Effects => self.1 = Self::page(ctx, &self.2),
+                    _ => {}
+                }
+            }
+        } else if let Some(ToneEvent::Set(curves)) = event.downcast_ref::<ToneEvent>() {
+            self.2.edits.push(EditOp::Tone(curves.clone()));
+            self.2.refresh();
+            self.steps(ctx);
+        } else if let Some(change) = event.downcast_ref::<EffectEvent>() {
+            let session = &mut self.2;
+            let mut effects = session.edits.effects();
+            match change {
+                EffectEvent::Add(kind) => {
+                    effects.0.push(Effect::new(*kind));
+                    session.effect = effects.0.len() - 1;
+                },
+                EffectEvent::Select(i) => session.effect = *i,
+                EffectEvent::Set(i, param, value) => if let Some(effect) = effects.0.get_mut(*i) {
+                    effect.set(*param, *value);
+                },
+                EffectEvent::Raise(i) => {
+                    effects.raise(*i);
+                    session.effect = i.saturating_sub(1);
+                },
+                EffectEvent::Remove(i) => if *i < effects.0.len() {
+                    effects.0.remove(*i);
+                    session.effect = session.effect.min(effects.0.len().saturating_sub(1));
+                },
+                EffectEvent::Reseed(i) => if let Some(effect) = effects.0.get_mut(*i) {
+                    effect.reseed();
+                },
+            }
+            if effects != session.edits.effects() {
+                session.edits.push(EditOp::Effects(effects));
+                session.refresh();
+            }
+            match change {
+                // Sliders update themselves while they're dragged.
+                EffectEvent::Set(..) | EffectEvent::Reseed(_) => self.steps(ctx),
+                _ => self.1 = Self::page(ctx, &self.2),
+            }
+        } else if let Some(SettingsSelect(setting)) = event.downcast_ref::<SettingsSelect>() {
+            self.2.adjusting = *setting;
+            let value = self.2.edits.value(*setting);
+            if let Some(slider) = self.1.content().find::<AdjustmentSlider>() {
+                *slider = AdjustmentSlider::new(ctx, *setting, value);
+            }
+        }
+        true
+    }
+}
+
+impl EditPhoto {
+    pub fn new(ctx: &mut Context, photo: Photo) -> Self {
+        ctx.theme.layout = LayoutResources::default();
+        let session = EditSession::new(photo);
+        EditPhoto(Stack::default(), Self::page(ctx, &session), session)
+    }
+
+    fn steps(&mut self, ctx: &mut Context) {
+        let edits = self.2.edits.clone();
+        if let Some(steps) = self.1.content().find::<EditSteps>() {
+            *steps = EditSteps::new(ctx, &edits);
+        }
+    }
+
+    fn show(&mut self, ctx: &mut Context) {
+        let Some(image) = self.2.shown() else { return };
+        if let Some(canvas) = self.1.content().find::<EditCanvas>() {
+            canvas.set_image(ctx, image);
+        }
+    }
+
+    fn page(ctx: &mut Context, session: &EditSession) -> Page {
+        let image = session.shown().unwrap_or_else(|| session.placeholder());
+        let tools = EditTool::ALL.into_iter().map(|tool| (tool.label(), tool == session.tool, EditPhotoEvent::Tool(tool))).collect();
+        let mut items: Vec<Box<dyn Drawable>> = vec![Box::new(EditCanvas::new(ctx, image)), Box::new(EditOptions::new(ctx, tools))];
+        match session.tool {
+            EditTool::Crop => {
+                let ratios = AspectRatio::ALL.into_iter().map(|ratio| (ratio.label(), ratio == session.ratio, EditPhotoEvent::Ratio(ratio))).collect();
+                let font_size = ctx.theme.fonts.size.sm;
+                items.push(Box::new(EditOptions::new(ctx, ratios)));
+                items.push(Box::new(Text::new(ctx, "Drag across the photo to crop.", TextStyle::Secondary, font_size, Align::Center)));
+            },
+            EditTool::Rotate => {
+                let turns = vec![
+                    ("Rotate Left", false, EditPhotoEvent::Apply(EditOp::Rotate(3))),
+                    ("Rotate Right", false, EditPhotoEvent::Apply(EditOp::Rotate(1))),
+                    ("Flip Horizontal", false, EditPhotoEvent::Apply(EditOp::Flip(Axis::Horizontal))),
+                    ("Flip Vertical", false, EditPhotoEvent::Apply(EditOp::Flip(Axis::Vertical))),
+                ];
+                items.push(Box::new(EditOptions::new(ctx, turns)));
+                items.push(Box::new(StraightenSlider::new(ctx, session.edits.straighten())));
+            },
+            EditTool::Adjust => {
+                items.push(Box::new(SettingsOptions::new(ctx)));
+                items.push(Box::new(AdjustmentSlider::new(ctx, session.adjusting, session.edits.value(session.adjusting))));
+            },
+            EditTool::Tone => items.push(Box::new(ToneEditor::new(ctx, session.edits.tone(), session.tone_histogram(), false))),
+            EditTool::Effects => {
+                let kinds = EffectKind::ALL.into_iter().map(|kind| (kind.label(), false, EffectEvent::Add(kind))).collect();
+                items.push(Box::new(EditOptions::new(ctx, kinds)));
+                let effects = session.edits.effects();
+                let font_size = ctx.theme.fonts.size.sm;
+                match effects.0.get(session.effect) {
+                    None => items.push(Box::new(Text::new(ctx, "Add effects to stack them, first to last.", TextStyle::Secondary, font_size, Align::Center))),
+                    Some(effect) => {
+                        let labels = effects.0.iter().enumerate().map(|(i, e)| format!("{}. {}", i + 1, e.kind.label())).collect::<Vec<_>>();
+                        let stack = labels.iter().enumerate().map(|(i, label)| (label.as_str(), i == session.effect, EffectEvent::Select(i))).collect();
+                        items.push(Box::new(EditOptions::new(ctx, stack)));
+                        for param in 0..effect.kind.params().len() {
+                            items.push(Box::new(EffectSlider::new(ctx, session.effect, effect, param)));
+                        }
+                        let i = session.effect;
+                        let mut actions = Vec::new();
+                        if effect.kind == EffectKind::Grain { actions.push(("New Grain", false, EffectEvent::Reseed(i))); }
+                        if i > 0 { actions.push(("Move Earlier", false, EffectEvent::Raise(i))); }
+                        actions.push(("Remove", false, EffectEvent::Remove(i)));
+                        items.push(Box::new(EditOptions::new(ctx, actions)));
+                    }
+                }
+            },
+        }
+        items.push(Box::new(EditSteps::new(ctx, &session.edits)));
+        items.push(Box::new(EditOptions::new(ctx, vec![("Copy Edits", false, EditPhotoEvent::Copy), ("Paste Edits", false, EditPhotoEvent::Paste)])));
+        let content = Content::new(ctx, Offset::Start, items);
+
+        let revert = Button::secondary(ctx, None, "Revert to Original", None, |ctx: &mut Context| ctx.trigger_event(EditPhotoEvent::Revert), None);
+        let save = Button::primary(ctx, "Save", |ctx: &mut Context| ctx.trigger_event(EditPhotoEvent::Save));
+        let bumper = Bumper::double_button(ctx, revert, save);
+
+        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(NavigateEvent(0)));
+        let header = Header::stack(ctx, Some(back), "Edit Photo", None);
+        Page::new(Some(header), content, Some(bumper))
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct ScanHistoryPage(Stack, Page);
+impl OnEvent for ScanHistoryPage {}
+
+impl AppPage for ScanHistoryPage {
+    fn has_nav(&self) -> bool { true }
+    fn navigate(self: Box<Self>, ctx: &mut Context, index: usize) -> Result<Box<dyn AppPage>, Box<dyn AppPage>> { 
+        match index {
+            0 => Ok(Box::new(CameraHome::new(ctx, None))),
+            1 => Ok(Box::new(ScanHistoryPage::new(ctx))),
+            _ => Err(self),
+        }
+    }
+}
+
+impl ScanHistoryPage {
+    pub fn new(ctx: &mut Context) -> Self {
+        ctx.theme.layout = LayoutResources::default();
+        let history = ctx.state().get_or_default::<ScanHistory>().clone();
+        let content: Box<dyn Drawable> = match history.0.is_empty() {
+            true => {
+                let font_size = ctx.theme.fonts.size.md;
+                Box::new(Text::new(ctx, "Codes you scan will show up here.", TextStyle::Secondary, font_size, Align::Center))
+            },
+            false => {
+                let items = history.0.iter().map(|record| {
+                    let text = record.text.clone();
+                    let date = Timestamp::new(record.date.with_timezone(&chrono::Local)).friendly();
+                    ListItem::new(
+                        ctx, false, &record.text, None, Some(&record.format), None, Some(&date), None, None, None, None, true,
+                        move |ctx: &mut Context| ctx.hardware.copy(text.clone())
+                    )
+                }).collect();
+                Box::new(ListItemGroup::new(items))
+            }
+        };
+
+        let bumper = (!history.0.is_empty()).then(|| {
+            let button = Button::secondary(ctx, Some("delete"), "Clear History", None, |ctx: &mut Context| {
+                let history = ScanHistory::default();
+                history.save();
+                ctx.state().set(history);
+                ctx.trigger_event(NavigateEvent(1));
+            }, None);
+            Bumper::single_button(ctx, button)
+        });
+
+        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(NavigateEvent(0)));
+        let header = Header::stack(ctx, Some(back), "Scan History", None);
+        let content = Content::new(ctx, Offset::Start, vec![content]);
+        ScanHistoryPage(Stack::default(), Page::new(Some(header), content, bumper))
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct MotionLogPage(Stack, Page, #[skip] Option<Photo>);
+
+impl AppPage for MotionLogPage {
+    fn has_nav(&self) -> bool { true }
+    fn navigate(self: Box<Self>, ctx: &mut Context, index: usize) -> Result<Box<dyn AppPage>, Box<dyn AppPage>> { 
+        match index {
+            0 => Ok(Box::new(CameraHome::new(ctx, None))),
+            1 => Ok(Box::new(ViewPhoto::new(ctx, self.2.unwrap(), Album::Motion))),
+            2 => Ok(Box::new(MotionLogPage::new(ctx))),
+            _ => Err(self),
+        }
+    }
+}
+
+impl OnEvent for MotionLogPage {
+    fn on_event(&mut self, _ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if let Some(SelectImageEvent(photo)) = event.downcast_ref::<SelectImageEvent>() {
+            self.2 = Some(photo.clone())
+        }
+        true
+    }
+}
+
+impl MotionLogPage {
+    pub fn new(ctx: &mut Context) -> Self {
+        ctx.theme.layout = LayoutResources::default();
+        let log = ctx.state().get_or_default::<MotionLog>().clone();
+        let album = ctx.state().get_or_default::<MotionAlbum>().clone();
+        let content: Box<dyn Drawable> = match log.0.is_empty() {
+            true => {
+                let font_size = ctx.theme.fonts.size.md;
+                Box::new(Text::new(ctx, "Motion events will show up here.", TextStyle::Secondary, font_size, Align::Center))
+            },
+            false => {
+                let items = log.0.iter().map(|record| {
+                    let date = Timestamp::new(record.date.with_timezone(&chrono::Local)).friendly();
+                    let kind = match record.duration > 0.0 {
+                        true => format!("{:.1}s clip", record.duration),
+                        false => "Still".to_string()
+                    };
+                    let photo = album.get(record.photo).cloned();
+                    ListItem::new(
+                        ctx, photo.is_some(), "Motion detected", None, Some(&date), None, Some(&kind), None, None, None, None, true,
+                        move |ctx: &mut Context| if let Some(photo) = &photo {
+                            ctx.trigger_event(SelectImageEvent(photo.clone()));
+                            ctx.trigger_event(NavigateEvent(1));
+                        }
+                    )
+                }).collect();
+                Box::new(ListItemGroup::new(items))
+            }
+        };
+
+        let bumper = (!log.0.is_empty()).then(|| {
+            let button = Button::secondary(ctx, Some("delete"), "Clear Events", None, |ctx: &mut Context| {
+                MotionAlbum::update(|album| album.0.drain(..).for_each(|photo| MotionAlbum::remove_media(&photo)));
+                let log = MotionLog::update(|log| log.0.clear());
+                ctx.state().set(log);
+                ctx.state().set(MotionAlbum::default());
+                ctx.trigger_event(NavigateEvent(2));
+            }, None);
+            Bumper::single_button(ctx, button)
+        });
+
+        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(NavigateEvent(0)));
+        let header = Header::stack(ctx, Some(back), "Motion Events", None);
+        let content = Content::new(ctx, Offset::Start, vec![content]);
+        MotionLogPage(Stack::default(), Page::new(Some(header), content, bumper), None)
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct StopMotionPage(Stack, Page, #[skip] f32, #[skip] GifExport);
+
+impl AppPage for StopMotionPage {
+    fn has_nav(&self) -> bool { true }
+    fn navigate(self: Box<Self>, ctx: &mut Context, index: usize) -> Result<Box<dyn AppPage>, Box<dyn AppPage>> { 
+        match index {
+            0 => Ok(Box::new(CameraHome::new(ctx, None))),
+            _ => Err(self),
+        }
+    }
+}
+
+impl OnEvent for StopMotionPage {
+    fn on_event(&mut self, ctx: &mut Context, event: &mut dyn Event) -> bool {
+        if event.downcast_ref::<TickEvent>().is_some() {
+            // The frame rate slider only writes state, so pick up its changes here.
+            let project = ctx.state().get_or_default::<StopMotion>().active();
+            let (fps, duration) = (project.fps, project.frame_duration());
+            if fps != self.2 {
+                self.2 = fps;
+                if let Some(preview) = self.1.content().find::<AnimationPreview>() {
+                    preview.set_frame_duration(duration);
+                }
+            }
+            // Exports are encoded by the service; the page follows along.
+            let export = ctx.state().get_or_default::<GifExport>().clone();
+            if export != self.3 {
+                if let GifExport::Done(name) = &export {
+                    if !storage::open_file(name) {
+                        log::warn!("Could not open {}", storage::path(name).display());
+                    }
+                    ctx.state().set(GifExport::Idle);
+                }
+                self.3 = ctx.state().get_or_default::<GifExport>().clone();
+                self.1 = Self::page(ctx);
+            }
+        } else if let Some(edit) = event.downcast_ref::<StopMotionEditEvent>() {
+            let mut stop_motion = ctx.state().get_or_default::<StopMotion>().clone();
+            match edit {
+                StopMotionEditEvent::Shift(i, by) => stop_motion.project().shift(*i, *by),
+                StopMotionEditEvent::Remove(i) => stop_motion.project().remove(*i),
+                StopMotionEditEvent::Onion(layers) => stop_motion.project().onion = *layers,
+                StopMotionEditEvent::Select(i) => stop_motion.active = (*i).min(stop_motion.projects.len() - 1),
+                StopMotionEditEvent::NewProject => stop_motion.create(),
+            }
+            stop_motion.save();
+            ctx.state().set(stop_motion);
+            self.1 = Self::page(ctx);
+        }
+        true
+    }
+}
+
+impl StopMotionPage {
+    pub fn new(ctx: &mut Context) -> Self {
+        ctx.theme.layout = LayoutResources::default();
+        let fps = ctx.state().get_or_default::<StopMotion>().active().fps;
+        let export = ctx.state().get_or_default::<GifExport>().clone();
+        StopMotionPage(Stack::default(), Self::page(ctx), fps, export)
+    }
+
+    fn page(ctx: &mut Context) -> Page {
+        let mut stop_motion = ctx.state().get_or_default::<StopMotion>().clone();
+        let projects = ProjectOptions::new(ctx, &stop_motion);
+        let project = stop_motion.project().clone();
+
+        let export = ctx.state().get_or_default::<GifExport>().clone();
+        let mut items: Vec<Box<dyn Drawable>> = vec![Box::new(projects)];
+        if let GifExport::Failed(error) = &export {
+            items.push(Box::new(Alert::new(ctx, &format!("Could not export GIF: {error}"))));
+        }
+        match project.frames.is_empty() {
+            true => {
+                let font_size = ctx.theme.fonts.size.md;
+                let help = "Press the shutter in Stop Motion mode to add frames.";
+                items.push(Box::new(Text::new(ctx, help, TextStyle::Secondary, font_size, Align::Center)));
+            },
+            false => items.push(Box::new(AnimationPreview::new(ctx, &project)))
+        }
+        items.push(Box::new(FrameRateSlider::new(ctx, project.fps)));
+        items.push(Box::new(OnionOptions::new(ctx, project.onion)));
+        project.frames.iter().enumerate().for_each(|(i, frame)| items.push(Box::new(FrameRow::new(ctx, i, frame))));
+        let content = Content::new(ctx, Offset::Start, items);
+
+        // The GIF opens in the desktop's viewer, where it can be saved or sent on. Mobile can't open files.
+        let bumper = (!project.frames.is_empty() && scanner::can_launch()).then(|| {
+            let exporting = export == GifExport::Running;
+            let label = if exporting {"Exporting GIF..."} else {"Open as GIF"};
+            let button = Button::primary(ctx, label, move |ctx: &mut Context| {
+                if exporting { return; }
+                ctx.state().set(GifExport::Running);
+                let mut guard = ctx.get::<LensPlugin>();
+                let plugin = guard.get().0;
+                plugin.request(LensRequest::ExportGif(project.clone()));
+            });
+            Bumper::single_button(ctx, button)
+        });
+
+        let back = IconButton::navigation(ctx, "left", |ctx: &mut Context| ctx.trigger_event(NavigateEvent(0)));
+        let new = IconButton::navigation(ctx, "add", |ctx: &mut Context| ctx.trigger_event(StopMotionEditEvent::NewProject));
+        let header = Header::stack(ctx, Some(back), "Stop Motion", Some(new));
+        Page::new(Some(header), content, bumper)
     }
 }
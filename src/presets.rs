@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use pelican_ui::hardware::ImageSettings;
+use serde::{Serialize, Deserialize};
+
+use crate::settings::CameraSetting;
+use crate::storage;
+
+/// A named set of camera settings. Settings missing from an imported preset are left as they are.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    pub values: BTreeMap<CameraSetting, f32>,
+}
+
+impl Preset {
+    pub fn capture(name: String, settings: &ImageSettings) -> Self {
+        Preset { name, values: CameraSetting::ALL.into_iter().map(|s| (s, s.get(settings))).collect() }
+    }
+
+    /// The preset as JSON, which is what gets shared so it can be pasted into Import elsewhere.
+    pub fn export(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Could not vec to pretty")
+    }
+
+    /// Reads a preset from the JSON text made by `export`.
+    pub fn import(text: &str) -> Result<Self, String> {
+        let preset: Preset = serde_json::from_str(text.trim()).map_err(|_| "The clipboard doesn't hold a preset.".to_string())?;
+        let values = preset.values.into_iter().map(|(s, v)| (s, CameraSetting::clamp(&s, v))).collect();
+        Ok(Preset { name: preset.name, values })
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Presets {
+    pub presets: Vec<Preset>,
+    /// Name of the preset applied when the camera starts.
+    pub default: Option<String>,
+}
+
+impl Presets {
+    const FILE: &'static str = "presets.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn default_preset(&self) -> Option<&Preset> {
+        self.default.as_ref().and_then(|name| self.presets.iter().find(|p| p.name == *name))
+    }
+
+    /// Adds a preset, replacing any existing one with the same name.
+    pub fn insert(&mut self, preset: Preset) {
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.presets.len() { return; }
+        let removed = self.presets.remove(index);
+        if self.default.as_ref() == Some(&removed.name) {
+            self.default = None;
+        }
+    }
+
+    /// A name that isn't taken yet, for presets saved without one.
+    pub fn next_name(&self) -> String {
+        (1..).map(|n| format!("Preset {n}")).find(|name| self.presets.iter().all(|p| p.name != *name)).unwrap()
+    }
+}
@@ -0,0 +1,174 @@
+use image::{GrayImage, RgbaImage};
+use serde::{Serialize, Deserialize};
+
+use crate::frames::{luma, laplacian_variance};
+
+const ANALYSIS_WIDTH: u32 = 480;
+
+const BLURRY_BELOW: f32 = 100.0;
+const CLIPPED_ABOVE: f32 = 0.05;
+const UNDEREXPOSED_MEAN: f32 = 50.0;
+const UNDEREXPOSED_DARK: f32 = 0.5;
+const NOISY_ABOVE: f32 = 6.0;
+// Side of the square blocks noise is estimated over.
+const NOISE_BLOCK: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QualityIssue {
+    Blurry,
+    Overexposed,
+    Underexposed,
+    Noisy,
+}
+
+impl QualityIssue {
+    pub fn label(&self) -> &'static str {
+        match self {
+            QualityIssue::Blurry => "Blurry",
+            QualityIssue::Overexposed => "Overexposed",
+            QualityIssue::Underexposed => "Underexposed",
+            QualityIssue::Noisy => "Noisy",
+        }
+    }
+
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            QualityIssue::Blurry => "Looks blurry — retake?",
+            QualityIssue::Overexposed => "Looks overexposed — retake?",
+            QualityIssue::Underexposed => "Looks too dark — retake?",
+            QualityIssue::Noisy => "Looks grainy — retake?",
+        }
+    }
+}
+
+/// Scores computed once by `LensService` when a photo is saved.
+///
+/// `clipped` and `dark` are fractions of pixels at the ends of the luma range,
+/// `noise` is an estimated standard deviation in 8-bit levels.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct QualityScores {
+    pub sharpness: f32,
+    pub brightness: f32,
+    pub clipped: f32,
+    pub dark: f32,
+    pub noise: f32,
+}
+
+impl QualityScores {
+    pub fn score(image: &RgbaImage) -> Self {
+        let gray = luma(image, ANALYSIS_WIDTH);
+        let n = gray.pixels().len().max(1) as f64;
+        let (mut sum, mut clipped, mut dark) = (0u64, 0u32, 0u32);
+        for p in gray.pixels() {
+            let v = p.0[0];
+            sum += v as u64;
+            if v >= 250 { clipped += 1; }
+            if v <= 20 { dark += 1; }
+        }
+        QualityScores {
+            sharpness: laplacian_variance(&gray) as f32,
+            brightness: (sum as f64 / n) as f32,
+            clipped: (clipped as f64 / n) as f32,
+            dark: (dark as f64 / n) as f32,
+            noise: noise_sigma(&gray),
+        }
+    }
+
+    /// Issues ordered by how strongly they should drive a retake prompt.
+    /// Exposure comes first since a clipped or black frame also reads as blurry.
+    pub fn issues(&self) -> Vec<QualityIssue> {
+        let mut issues = Vec::new();
+        if self.clipped > CLIPPED_ABOVE { issues.push(QualityIssue::Overexposed); }
+        if self.brightness < UNDEREXPOSED_MEAN && self.dark > UNDEREXPOSED_DARK { issues.push(QualityIssue::Underexposed); }
+        if self.sharpness < BLURRY_BELOW { issues.push(QualityIssue::Blurry); }
+        if self.noise > NOISY_ABOVE { issues.push(QualityIssue::Noisy); }
+        issues
+    }
+
+    pub fn is_low(&self) -> bool {
+        !self.issues().is_empty()
+    }
+}
+
+/// Immerkær's fast noise estimate: the response to a mask that cancels
+/// smooth gradients and edges, leaving mostly pixel-level noise. Texture and
+/// strong edges still leak through, so it's taken per block and the median
+/// kept, which a few busy blocks can't drag up.
+fn noise_sigma(gray: &GrayImage) -> f32 {
+    let (w, h) = gray.dimensions();
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+    let px = |x: u32, y: u32| gray.get_pixel(x, y).0[0] as f64;
+    let mut blocks = Vec::new();
+    for top in (1..h - 1).step_by(NOISE_BLOCK as usize) {
+        for left in (1..w - 1).step_by(NOISE_BLOCK as usize) {
+            let (bottom, right) = ((top + NOISE_BLOCK).min(h - 1), (left + NOISE_BLOCK).min(w - 1));
+            let mut sum = 0.0;
+            for y in top..bottom {
+                for x in left..right {
+                    let v = px(x - 1, y - 1) - 2.0 * px(x, y - 1) + px(x + 1, y - 1)
+                        - 2.0 * px(x - 1, y) + 4.0 * px(x, y) - 2.0 * px(x + 1, y)
+                        + px(x - 1, y + 1) - 2.0 * px(x, y + 1) + px(x + 1, y + 1);
+                    sum += v.abs();
+                }
+            }
+            let n = 6.0 * ((bottom - top) * (right - left)) as f64;
+            blocks.push(std::f64::consts::FRAC_PI_2.sqrt() * sum / n);
+        }
+    }
+    blocks.sort_by(f64::total_cmp);
+    blocks[blocks.len() / 2] as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{imageops, Rgba};
+
+    fn checkerboard(cell: u32) -> RgbaImage {
+        RgbaImage::from_fn(480, 360, |x, y| match (x / cell + y / cell) % 2 {
+            0 => Rgba([200, 200, 200, 255]),
+            _ => Rgba([60, 60, 60, 255])
+        })
+    }
+
+    // Mid gray with deterministic noise of about `amount` levels either way.
+    fn noisy(amount: i32) -> RgbaImage {
+        let mut seed = 1u32;
+        RgbaImage::from_fn(480, 360, |_, _| {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            let v = (128 + (seed >> 24) as i32 % (2 * amount + 1) - amount) as u8;
+            Rgba([v, v, v, 255])
+        })
+    }
+
+    #[test]
+    fn sharp_well_exposed_photos_pass() {
+        let scores = QualityScores::score(&checkerboard(8));
+        assert_eq!(scores.issues(), vec![], "{scores:?}");
+        assert!(!scores.is_low());
+    }
+
+    #[test]
+    fn blur_is_flagged() {
+        let blurred = imageops::blur(&checkerboard(8), 6.0);
+        assert_eq!(QualityScores::score(&blurred).issues(), vec![QualityIssue::Blurry]);
+    }
+
+    #[test]
+    fn exposure_comes_before_blur() {
+        let white = RgbaImage::from_pixel(480, 360, Rgba([255, 255, 255, 255]));
+        assert_eq!(QualityScores::score(&white).issues(), vec![QualityIssue::Overexposed, QualityIssue::Blurry]);
+        let black = RgbaImage::from_pixel(480, 360, Rgba([5, 5, 5, 255]));
+        assert_eq!(QualityScores::score(&black).issues(), vec![QualityIssue::Underexposed, QualityIssue::Blurry]);
+    }
+
+    #[test]
+    fn noise_is_flagged_but_texture_is_not() {
+        assert!(QualityScores::score(&noisy(40)).issues().contains(&QualityIssue::Noisy));
+        assert!(!QualityScores::score(&noisy(2)).issues().contains(&QualityIssue::Noisy));
+        // Strong edges cross only some of the blocks, so the median leaves them out.
+        assert!(!QualityScores::score(&checkerboard(24)).issues().contains(&QualityIssue::Noisy));
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use image::{imageops, RgbaImage};
+use pelican_ui::Context;
+use serde::{Serialize, Deserialize};
+
+use crate::worker::Worker;
+use crate::storage;
+
+const DECODE_WIDTH: u32 = 960;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ScannedCode {
+    pub text: String,
+    pub format: String,
+    /// x, y, width, height in the pixel space of the frame that was scanned.
+    pub bounds: (f32, f32, f32, f32),
+}
+
+impl ScannedCode {
+    pub fn is_link(&self) -> bool {
+        self.text.starts_with("https://") || self.text.starts_with("http://")
+    }
+}
+
+/// Decodes every QR code and 1D barcode visible in `frame`.
+pub fn decode(frame: &RgbaImage) -> Vec<ScannedCode> {
+    let (w, h) = frame.dimensions();
+    let scale = (DECODE_WIDTH as f32 / w as f32).min(1.0);
+    let gray = imageops::grayscale(frame);
+    let gray = match scale < 1.0 {
+        true => imageops::resize(&gray, DECODE_WIDTH, ((h as f32 * scale).round() as u32).max(1), imageops::FilterType::Triangle),
+        false => gray
+    };
+    let (gw, gh) = gray.dimensions();
+    let Ok(results) = rxing::helpers::detect_multiple_in_luma(gray.into_raw(), gw, gh) else { return Vec::new() };
+    results.into_iter().map(|r| {
+        let xs = r.getPoints().iter().map(|p| p.x / scale);
+        let ys = r.getPoints().iter().map(|p| p.y / scale);
+        let (x0, x1) = xs.fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        let (y0, y1) = ys.fold((f32::MAX, f32::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)));
+        // 1D readers only report the scan line, so give the box some height.
+        let pad = if y1 - y0 < (x1 - x0) * 0.1 { (x1 - x0) * 0.15 } else { 0.0 };
+        ScannedCode {
+            text: r.getText().to_string(),
+            format: format!("{:?}", r.getBarcodeFormat()).replace('_', " "),
+            bounds: (x0, y0 - pad, x1 - x0, (y1 - y0) + pad * 2.0),
+        }
+    }).collect()
+}
+
+/// Decodes codes on a worker thread. Decoding usually takes longer than a frame, so
+/// frames are offered and skipped while one is in flight.
+pub type Scanner = Worker<RgbaImage, Vec<ScannedCode>>;
+
+pub fn scanner() -> Scanner {
+    Worker::new(|frame: RgbaImage| decode(&frame))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ScanRecord {
+    pub text: String,
+    pub format: String,
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScanHistory(pub Vec<ScanRecord>);
+
+impl ScanHistory {
+    const FILE: &'static str = "scan_history.json";
+    const LIMIT: usize = 500;
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    /// Newest first. Re-scanning a code moves it back to the top instead of duplicating it.
+    pub fn record(&mut self, code: &ScannedCode) {
+        self.0.retain(|r| r.text != code.text);
+        self.0.insert(0, ScanRecord { text: code.text.clone(), format: code.format.clone(), date: Utc::now() });
+        self.0.truncate(Self::LIMIT);
+    }
+}
+
+// The desktop's command for opening a link or file in its default app. Mobile has no launcher hook.
+fn launcher() -> Option<&'static str> {
+    match () {
+        _ if cfg!(target_os = "macos") => Some("open"),
+        _ if cfg!(target_os = "linux") => Some("xdg-open"),
+        _ => None
+    }
+}
+
+pub fn can_launch() -> bool {
+    launcher().is_some()
+}
+
+/// Opens a link or file in its default app, returning whether that worked.
+pub fn launch(target: impl AsRef<std::ffi::OsStr>) -> bool {
+    launcher().is_some_and(|l| std::process::Command::new(l).arg(target).spawn().is_ok())
+}
+
+/// Opens links in the system browser on desktop. Elsewhere, or if the launcher
+/// fails, the link goes to the share sheet instead.
+pub fn open_link(ctx: &mut Context, link: &str) {
+    if !launch(link) {
+        ctx.hardware.share(link);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use rxing::{BarcodeFormat, MultiFormatWriter, Writer};
+
+    use crate::frames::{FrameSource, StillFrames};
+    use crate::worker::wait;
+
+    fn qr_code(text: &str) -> RgbaImage {
+        let matrix = MultiFormatWriter.encode(text, &BarcodeFormat::QR_CODE, 300, 300).unwrap();
+        RgbaImage::from_fn(matrix.getWidth() + 80, matrix.getHeight() + 80, |x, y| {
+            let inside = (40..matrix.getWidth() + 40).contains(&x) && (40..matrix.getHeight() + 40).contains(&y);
+            match inside && matrix.get(x - 40, y - 40) {
+                true => Rgba([0, 0, 0, 255]),
+                false => Rgba([255, 255, 255, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn scanner_reads_codes_from_still_frames() {
+        let mut stills = StillFrames::new(vec![qr_code("https://example.com/albacore")]);
+        let mut scanner = scanner();
+        scanner.offer(|| stills.next_frame().unwrap());
+        let codes = wait(|| scanner.poll().pop());
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].text, "https://example.com/albacore");
+        assert!(codes[0].is_link());
+    }
+}
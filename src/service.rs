@@ -1,21 +1,289 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::LazyLock;
 use std::time::Duration;
 
 use maverick_os::Cache;
-use pelican_ui::runtime::{Services, Service, ServiceList, ThreadContext, async_trait, self};
+use pelican_ui::runtime::{Services, Service, ServiceList, BackgroundList, ThreadContext, async_trait, self};
 use pelican_ui::{hardware};
 use pelican_ui::State;
 use pelican_ui::air::{Id, Service as AirService, Protocol, Validation, ChildrenValidation, HeaderInfo, RecordPath, Permissions};
 // use pelican_ui_std::AvatarContent;
+use pelican_ui_std::EncodedImage;
 
 // use std::collections::HashSet;
 use serde::{Serialize, Deserialize};
 use chrono::{Utc, DateTime};
-// use uuid::Uuid;
+use uuid::Uuid;
+use image::RgbaImage;
+
+use crate::quality::QualityScores;
+use crate::document::ScanFilter;
+use crate::motion::HeadlessMonitor;
+use crate::exposure::ExposureRecord;
+use crate::edit::{self, Edits};
+use crate::compare::CompareMode;
+use crate::markup::Annotations;
+use crate::session::CameraSession;
+use crate::stopmotion::{GifExport, Project};
+use crate::storage;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Photo {
+    pub id: Uuid,
+    pub image: String,
+    pub size: (f32, f32),
+    /// Photos saved before this was kept have it filled in from when the roll was last written.
+    #[serde(default)]
+    pub date: DateTime<Utc>,
+    /// Storage file names of the live clip's frames.
+    #[serde(default)]
+    pub live: Vec<String>,
+    #[serde(default)]
+    pub quality: Option<QualityScores>,
+    /// Set for a document scan, whose first page is `image`.
+    #[serde(default)]
+    pub document: bool,
+    /// The pages of a document scan after the first.
+    #[serde(default)]
+    pub pages: Vec<String>,
+    #[serde(default)]
+    pub exposure: Option<ExposureRecord>,
+    /// For a photo booth composite, the individual shots it was made from.
+    #[serde(default)]
+    pub shots: Vec<Uuid>,
+    /// For a photo booth shot, the composite it belongs to. Shots are hidden from the roll.
+    #[serde(default)]
+    pub strip: Option<Uuid>,
+    /// Applied on top of `image`, which always keeps the original pixels.
+    #[serde(default)]
+    pub edits: Edits,
+    /// Drawn over the edited photo.
+    #[serde(default)]
+    pub annotations: Annotations,
+    /// `image` with `edits` applied and `annotations` flattened in, rendered once when they are saved.
+    #[serde(default)]
+    pub edited: Option<String>,
+    /// The camera's settings and mode when the photo was taken.
+    #[serde(default)]
+    pub capture: Option<CameraSession>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub caption: String,
+}
+
+impl Photo {
+    pub fn new(image: String, size: (f32, f32)) -> Self {
+        Photo { id: Uuid::new_v4(), image, size, date: Utc::now(), live: Vec::new(), quality: None, document: false, pages: Vec::new(), exposure: None, shots: Vec::new(), strip: None, edits: Edits::default(), annotations: Annotations::default(), edited: None, capture: None, title: String::new(), caption: String::new() }
+    }
+
+    pub fn is_low_quality(&self) -> bool {
+        self.quality.is_some_and(|q| q.is_low())
+    }
+
+    pub fn is_document(&self) -> bool {
+        self.document
+    }
+
+    /// Every page of a document scan, or just the image for anything else.
+    pub fn all_pages(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.image).chain(&self.pages)
+    }
+
+    pub fn is_booth_strip(&self) -> bool {
+        !self.shots.is_empty()
+    }
+
+    /// The image as it should be shown and shared, edits and annotations included.
+    pub fn shown(&self) -> &String {
+        self.edited.as_ref().unwrap_or(&self.image)
+    }
+
+    /// Writes each frame of the live clip to its own file, so saving the roll doesn't rewrite them.
+    pub fn set_live(&mut self, frames: Vec<RgbaImage>) {
+        self.live = frames.into_iter().enumerate().map(|(i, frame)| {
+            let name = format!("live-{}-{i}.png", self.id);
+            storage::write_image(&name, &frame);
+            name
+        }).collect();
+    }
+
+    pub fn live_frames(&self) -> Vec<RgbaImage> {
+        self.live.iter().filter_map(|name| storage::read_image(name)).collect()
+    }
+
+    /// Brings a photo saved by an older version up to date, dating it `saved` if it has no date.
+    /// Returns whether anything changed.
+    pub fn upgrade(&mut self, saved: DateTime<Utc>) -> bool {
+        self.store_live() | self.split_pages() | self.add_date(saved)
+    }
+
+    fn add_date(&mut self, saved: DateTime<Utc>) -> bool {
+        if self.date != DateTime::<Utc>::default() { return false; }
+        self.date = saved;
+        true
+    }
+
+    // Documents used to keep every page in `pages`, the first one twice.
+    fn split_pages(&mut self) -> bool {
+        if self.document || self.pages.is_empty() { return false; }
+        self.document = true;
+        if self.pages[0] == self.image {
+            self.pages.remove(0);
+        }
+        true
+    }
+
+    // Clips used to be kept inline as base64.
+    fn store_live(&mut self) -> bool {
+        if self.live.iter().all(|frame| frame.starts_with("live-")) { return false; }
+        let frames = self.live.iter().map(|frame| match frame.starts_with("live-") {
+            true => storage::read_image(frame).unwrap_or_default(),
+            false => EncodedImage::decode_rgba(frame)
+        }).collect();
+        self.set_live(frames);
+        true
+    }
+
+    pub fn remove_live(&self) {
+        self.live.iter().for_each(|name| storage::remove(name));
+    }
+}
+
+/// Which collection a photo being viewed belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Album {
+    Roll,
+    Motion,
+}
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
-pub struct MyCameraRoll(pub Vec<(String, (f32, f32))>);
+pub struct MyCameraRoll(pub Vec<Photo>);
+
+impl MyCameraRoll {
+    const FILE: &'static str = "my_camera_roll.json";
+
+    pub fn load() -> Self {
+        let Some(bytes) = storage::read(Self::FILE) else { return MyCameraRoll::default() };
+        // The best guess at when undated photos were taken is the last time the roll was written.
+        let saved = storage::modified(Self::FILE).map(DateTime::<Utc>::from).unwrap_or_else(Utc::now);
+        if let Ok(photos) = serde_json::from_slice::<Vec<Photo>>(&bytes) {
+            let mut roll = MyCameraRoll(photos);
+            if roll.0.iter_mut().map(|p| p.upgrade(saved)).filter(|moved| *moved).count() > 0 {
+                roll.save();
+            }
+            return roll;
+        }
+        // Rolls written before photos carried an id and date were bare (image, size) pairs.
+        let legacy: Vec<(String, (f32, f32))> = serde_json::from_slice(&bytes).expect("Could not read from reader");
+        let roll = MyCameraRoll(legacy.into_iter().map(|(image, size)| Photo { date: saved, ..Photo::new(image, size) }).collect());
+        roll.save();
+        roll
+    }
+
+    /// Removing a photo booth composite removes its shots with it.
+    pub fn remove(&mut self, ids: &[Uuid]) {
+        self.0.retain(|p| {
+            let keep = !ids.contains(&p.id) && !p.strip.is_some_and(|s| ids.contains(&s));
+            if !keep { p.remove_live(); }
+            keep
+        });
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&Photo> {
+        self.0.iter().find(|p| p.id == id)
+    }
+
+    /// The photos shown in the roll, leaving out shots grouped under a photo booth composite.
+    pub fn visible(&self) -> impl DoubleEndedIterator<Item = &Photo> {
+        self.0.iter().filter(|p| p.strip.is_none())
+    }
+
+    pub fn shots(&self, strip: &Photo) -> Vec<Photo> {
+        strip.shots.iter().filter_map(|id| self.0.iter().find(|p| p.id == *id)).cloned().collect()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, &self.0);
+    }
+}
+
+/// A photo's original with the edits and annotations to draw on it. The original travels with
+/// the request so the service never has to read the roll.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rendering {
+    pub id: Uuid,
+    pub image: String,
+    pub size: (f32, f32),
+    pub edits: Edits,
+    pub annotations: Annotations,
+}
+
+impl Rendering {
+    pub fn of(photo: &Photo) -> Self {
+        Rendering { id: photo.id, image: photo.image.clone(), size: photo.size, edits: photo.edits.clone(), annotations: photo.annotations.clone() }
+    }
+
+    /// Renders the edited copy at full resolution, leaving it out when there's nothing to draw.
+    fn render(self) -> Rendered {
+        let edited = (!self.edits.is_empty() || !self.annotations.is_empty()).then(|| {
+            let edited = self.edits.render(&EncodedImage::decode_rgba(&self.image));
+            EncodedImage::encode_rgba(self.annotations.flatten(edited, &self.edits, self.size))
+        });
+        Rendered { id: self.id, edits: self.edits, annotations: self.annotations, edited }
+    }
+
+    fn preview(&self, kind: PreviewKind) -> Preview {
+        let original = edit::proxy(&EncodedImage::decode_rgba(&self.image));
+        let edited = self.edits.render(&original);
+        match kind {
+            PreviewKind::Markup => Preview::Markup(self.id, EncodedImage::encode_rgba(edited)),
+            // The original keeps the crop and turns, so both sides line up.
+            PreviewKind::Compare(mode) => Preview::Compare(
+                self.id,
+                mode,
+                EncodedImage::encode_rgba(self.edits.framing().render(&original)),
+                EncodedImage::encode_rgba(self.annotations.flatten(edited, &self.edits, self.size))
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PreviewKind {
+    Markup,
+    Compare(CompareMode),
+}
+
+/// A reduced render for the photo viewer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Preview {
+    /// The photo with its edits, to draw marks over.
+    Markup(Uuid, String),
+    /// The original and the photo as edited, annotations included, to show side by side.
+    Compare(Uuid, CompareMode, String, String),
+}
+
+impl Preview {
+    pub fn id(&self) -> Uuid {
+        match self {
+            Preview::Markup(id, _) | Preview::Compare(id, ..) => *id
+        }
+    }
+}
+
+/// The latest preview from the service, until the viewer picks it up.
+#[derive(Clone, Debug, Default)]
+pub struct PhotoPreview(pub Option<Preview>);
+
+/// A photo's new edits and annotations and the copy rendered from them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rendered {
+    pub id: Uuid,
+    pub edits: Edits,
+    pub annotations: Annotations,
+    pub edited: Option<String>,
+}
 
 static PHOTOS: LazyLock<Id> = LazyLock::new(|| Id::hash(&"PhotosV1".to_string()));
 static PHOTO: LazyLock<Id> = LazyLock::new(|| Id::hash(&"PhotoV1".to_string()));
@@ -37,16 +305,53 @@ static PHOTO_PROTOCOL: LazyLock<Protocol> = LazyLock::new(|| {
     Protocol::new(validation, header, *PHOTO)
 });
 
+/// How far a batch of edits has got. Kept in state while it runs so the roll can show it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchProgress {
+    pub done: usize,
+    pub total: usize,
+    pub cancelled: bool,
+}
+
+impl BatchProgress {
+    pub fn running(&self) -> bool {
+        !self.cancelled && self.done < self.total
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum LensResponse {
+    Photo(Box<Photo>),
+    Batch(BatchProgress),
+    Gif(GifExport),
+    /// A photo with new edits and annotations rendered. Only those are saved to the roll.
+    Edited(Box<Rendered>),
+    Preview(Box<Preview>),
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum LensRequest {
     // CreateRoom(Uuid),
     // CreateAlbum,
-    SavePhoto(String, (f32, f32)),
+    SavePhoto(Box<Photo>),
+    SaveDocument(Vec<String>, ScanFilter),
+    /// Renders a photo with new edits or annotations at full resolution and saves them to it in the roll.
+    Render(Box<Rendering>),
+    /// Renders a reduced copy for the viewer to show.
+    Preview(Box<Rendering>, PreviewKind),
+    /// Pastes the look of the edits onto each photo, one at a time so it can be cancelled part way.
+    ApplyEdits(Vec<Rendering>, Edits),
+    /// Stops a running batch. Photos already done keep their new edits.
+    CancelBatch,
+    ExportGif(Project),
     // Share(Id, OrangeName),
 }
 
 #[derive(Debug)]
 pub struct LensService{
+    batch: VecDeque<Rendering>,
+    look: Edits,
+    progress: BatchProgress,
 }
 
 impl Services for LensService {
@@ -59,11 +364,14 @@ impl Services for LensService {
 
 #[async_trait]
 impl Service for LensService {
-    type Send = (String, (f32, f32));
+    type Send = LensResponse;
     type Receive = LensRequest;
 
     async fn new(_hardware: &mut hardware::Context) -> Self {
         LensService{
+            batch: VecDeque::new(),
+            look: Edits::default(),
+            progress: BatchProgress::default(),
         }
     }
 
@@ -80,14 +388,54 @@ impl Service for LensService {
                 // LensRequest::CreateAlbum => {
                 //     AirService::create_private(ctx, RecordPath::root(), PHOTOS_PROTOCOL.clone(), cache.albums_idx, PHOTOS_PERMISSIONS, serde_json::to_vec(&*MY_PHOTOS)?).await?;
                 // },
-                LensRequest::SavePhoto(data, size) => {
+                LensRequest::SavePhoto(mut photo) => {
                     println!("Saving photo...");
-                    ctx.callback((data, size));
+                    // Long exposures are blurred on purpose, so they aren't scored. Photo booth
+                    // shots are posed on a countdown and the composite is mostly border.
+                    if photo.exposure.is_none() && photo.strip.is_none() && !photo.is_booth_strip() {
+                        let rgba = EncodedImage::decode_rgba(&photo.image);
+                        photo.quality = Some(QualityScores::score(&rgba));
+                    }
+                    ctx.callback(LensResponse::Photo(photo));
                     // let mut x = cache.albums.get(&RecordPath::root().join(*MY_PHOTOS)).unwrap().1;
                     // while let (_, Some(_)) = AirService::create_private(ctx, RecordPath::root().join(*MY_PHOTOS), PHOTO_PROTOCOL.clone(), x, PHOTO_PERMISSIONS, serde_json::to_vec(&data)?).await? {
                     //     x += 1;
                     // }
                 },
+                // Pages arrive flattened but unfiltered so the filter can still be changed
+                // up until save. Documents skip quality scoring; a B/W page always reads as clipped.
+                LensRequest::SaveDocument(pages, filter) => {
+                    let pages = pages.iter()
+                        .map(|page| filter.apply(&EncodedImage::decode_rgba(page)))
+                        .collect::<Vec<_>>();
+                    let mut pages = pages.into_iter();
+                    let Some(first) = pages.next() else { continue };
+                    let (width, height) = first.dimensions();
+                    let mut photo = Photo::new(EncodedImage::encode_rgba(first), (width as f32, height as f32));
+                    photo.document = true;
+                    photo.pages = pages.map(EncodedImage::encode_rgba).collect();
+                    ctx.callback(LensResponse::Photo(Box::new(photo)));
+                },
+                LensRequest::Render(rendering) => ctx.callback(LensResponse::Edited(Box::new(rendering.render()))),
+                LensRequest::Preview(rendering, kind) => ctx.callback(LensResponse::Preview(Box::new(rendering.preview(kind)))),
+                LensRequest::ApplyEdits(renderings, look) => {
+                    self.progress = BatchProgress { done: 0, total: renderings.len(), cancelled: false };
+                    self.batch = renderings.into();
+                    self.look = look;
+                    ctx.callback(LensResponse::Batch(self.progress));
+                },
+                LensRequest::CancelBatch => if self.progress.running() {
+                    self.batch.clear();
+                    self.progress.cancelled = true;
+                    ctx.callback(LensResponse::Batch(self.progress));
+                },
+                LensRequest::ExportGif(project) => {
+                    let export = match project.export_gif() {
+                        Ok(name) => GifExport::Done(name),
+                        Err(e) => GifExport::Failed(e.to_string())
+                    };
+                    ctx.callback(LensResponse::Gif(export));
+                },
             //     LensRequest::Share(room, name) => {
             //         let message = Message::invisible(name.clone());
             //         let path = RecordPath::root().join(room);
@@ -100,13 +448,62 @@ impl Service for LensService {
             }
         }
 
+        // One photo per run, so a cancel queued behind the batch is read before the next.
+        if let Some(mut rendering) = self.batch.pop_front() {
+            rendering.edits = rendering.edits.with_look(&self.look);
+            self.progress.done += 1;
+            ctx.callback(LensResponse::Edited(Box::new(rendering.render())));
+            ctx.callback(LensResponse::Batch(self.progress));
+        }
+
         Ok(Some(Duration::from_millis(16)))
     }
 
+    fn background_tasks() -> BackgroundList {
+        let mut tasks = BackgroundList::default();
+        tasks.insert::<HeadlessMonitor>();
+        tasks
+    }
+
     fn callback(state: &mut State, response: Self::Send) {
+        let response = match response {
+            LensResponse::Photo(photo) => *photo,
+            LensResponse::Batch(progress) => {
+                state.set(progress);
+                return;
+            },
+            LensResponse::Gif(export) => {
+                state.set(export);
+                return;
+            },
+            // Only what was rendered is merged, so changes made to the photo meanwhile are kept.
+            LensResponse::Edited(rendered) => {
+                let mut roll = state.get::<MyCameraRoll>().unwrap().clone();
+                let Some(photo) = roll.0.iter_mut().find(|p| p.id == rendered.id) else {
+                    log::warn!("Rendered edits for {}, which is no longer in the roll", rendered.id);
+                    return;
+                };
+                photo.edits = rendered.edits;
+                photo.annotations = rendered.annotations;
+                photo.edited = rendered.edited;
+                roll.save();
+                state.set(roll);
+                return;
+            },
+            LensResponse::Preview(preview) => {
+                state.set(PhotoPreview(Some(*preview)));
+                return;
+            },
+        };
         let mut photos = state.get::<MyCameraRoll>().unwrap().0.clone();
-        photos.push(response);
-        state.set(MyCameraRoll(photos));
+        match photos.iter_mut().find(|p| p.id == response.id) {
+            // Only the score is new, so a title or edits added meanwhile are kept.
+            Some(photo) => photo.quality = response.quality,
+            None => photos.push(response),
+        }
+        let roll = MyCameraRoll(photos);
+        roll.save();
+        state.set(roll);
         // let mut rooms = state.get::<Rooms>().0;
         // // if response.2 {state.set(&Name(Some(response.0.clone())));}
         // rooms.insert(response.0, response.1);
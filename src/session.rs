@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use pelican_ui::hardware::ImageSettings;
+use serde::{Serialize, Deserialize};
+
+use crate::modes::CaptureMode;
+use crate::settings::CameraSetting;
+use crate::storage;
+
+/// The camera as it was last left, reapplied whenever the camera opens.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct CameraSession {
+    /// Settings left at their defaults are still recorded, so a reset survives a relaunch.
+    pub values: BTreeMap<CameraSetting, f32>,
+    pub mode: CaptureMode,
+    /// Live photos, the only viewfinder toggle not already kept with its mode's settings.
+    pub live: bool,
+}
+
+impl CameraSession {
+    const FILE: &'static str = "camera_session.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn capture(settings: &ImageSettings, mode: CaptureMode, live: bool) -> Self {
+        CameraSession { values: CameraSetting::ALL.into_iter().map(|s| (s, s.get(settings))).collect(), mode, live }
+    }
+
+    /// Every setting back at its default, in photo mode with live photos off.
+    pub fn reset() -> Self {
+        CameraSession { values: CameraSetting::ALL.into_iter().map(|s| (s, s.spec().default)).collect(), ..Default::default() }
+    }
+}
@@ -0,0 +1,262 @@
+use pelican_ui::hardware::{Camera, CameraError, ImageSettings};
+use serde::{Serialize, Deserialize};
+
+/// The adjustable camera settings. Everything the UI needs to know about one
+/// lives in its `SettingSpec`, so adding a setting means adding an entry here.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CameraSetting {
+    Brightness,
+    Contrast,
+    Saturation,
+    Gamma,
+    Exposure,
+    Temperature,
+    WhiteBalanceR,
+    WhiteBalanceG,
+    WhiteBalanceB,
+}
+
+// Slider travel, in percent, within which a drag snaps to a notable value.
+const SNAP: f32 = 1.5;
+
+/// How slider travel maps onto the setting's range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SliderMapping {
+    Linear,
+    /// Equal slider steps multiply the value by the same factor. The range must be positive.
+    Logarithmic,
+}
+
+#[derive(Debug)]
+pub struct SettingSpec {
+    pub label: &'static str,
+    pub icon: &'static str,
+    /// Appended to displayed values, empty for unitless settings.
+    pub unit: &'static str,
+    pub range: (f32, f32),
+    pub mapping: SliderMapping,
+    pub default: f32,
+    /// Granularity of slider adjustments; also decides how many decimals are shown.
+    pub step: f32,
+    /// Values the slider snaps to when dragged close, such as 0 EV or daylight.
+    pub notable: &'static [f32],
+    get: fn(&ImageSettings) -> f32,
+    set: fn(&mut Camera, f32) -> Result<(), CameraError>,
+}
+
+const BRIGHTNESS: SettingSpec = SettingSpec {
+    label: "Brightness", icon: "brightness", unit: "", range: (-100.0, 100.0), mapping: SliderMapping::Linear, default: 0.0, step: 1.0, notable: &[0.0],
+    get: |s| s.brightness as f32, set: |c, v| c.set_brightness(v.round() as i16),
+};
+const CONTRAST: SettingSpec = SettingSpec {
+    label: "Contrast", icon: "contrast", unit: "", range: (-1.0, 1.0), mapping: SliderMapping::Linear, default: 0.0, step: 0.01, notable: &[0.0],
+    get: |s| s.contrast, set: |c, v| c.set_contrast(v),
+};
+const SATURATION: SettingSpec = SettingSpec {
+    label: "Saturation", icon: "saturation", unit: "", range: (-1.0, 1.0), mapping: SliderMapping::Linear, default: 0.0, step: 0.01, notable: &[0.0],
+    get: |s| s.saturation, set: |c, v| c.set_saturation(v),
+};
+const GAMMA: SettingSpec = SettingSpec {
+    label: "Gamma", icon: "gamma", unit: "", range: (0.1, 3.0), mapping: SliderMapping::Logarithmic, default: 2.2, step: 0.05, notable: &[1.0, 2.2],
+    get: |s| s.gamma, set: |c, v| c.set_gamma(v),
+};
+const EXPOSURE: SettingSpec = SettingSpec {
+    label: "Exposure", icon: "exposure", unit: " EV", range: (-2.0, 2.0), mapping: SliderMapping::Linear, default: 0.0, step: 0.1, notable: &[0.0],
+    get: |s| s.exposure, set: |c, v| c.set_exposure(v),
+};
+const TEMPERATURE: SettingSpec = SettingSpec {
+    label: "Temperature", icon: "temperature", unit: " K", range: (2000.0, 10000.0), mapping: SliderMapping::Linear, default: 6500.0, step: 50.0, notable: &[3200.0, 5600.0, 6500.0],
+    get: |s| s.temperature, set: |c, v| c.set_temperature(v),
+};
+const WHITE_BALANCE_R: SettingSpec = SettingSpec {
+    label: "Red Balance", icon: "white_balance_r", unit: "×", range: (0.5, 2.0), mapping: SliderMapping::Linear, default: 1.0, step: 0.01, notable: &[1.0],
+    get: |s| s.white_balance_r, set: |c, v| c.set_white_balance_r(v),
+};
+const WHITE_BALANCE_G: SettingSpec = SettingSpec {
+    label: "Green Balance", icon: "white_balance_g", unit: "×", range: (0.5, 2.0), mapping: SliderMapping::Linear, default: 1.0, step: 0.01, notable: &[1.0],
+    get: |s| s.white_balance_g, set: |c, v| c.set_white_balance_g(v),
+};
+const WHITE_BALANCE_B: SettingSpec = SettingSpec {
+    label: "Blue Balance", icon: "white_balance_b", unit: "×", range: (0.5, 2.0), mapping: SliderMapping::Linear, default: 1.0, step: 0.01, notable: &[1.0],
+    get: |s| s.white_balance_b, set: |c, v| c.set_white_balance_b(v),
+};
+
+impl CameraSetting {
+    pub const ALL: [CameraSetting; 9] = [
+        CameraSetting::Brightness, CameraSetting::Contrast, CameraSetting::Saturation,
+        CameraSetting::Gamma, CameraSetting::Exposure, CameraSetting::Temperature,
+        CameraSetting::WhiteBalanceR, CameraSetting::WhiteBalanceG, CameraSetting::WhiteBalanceB,
+    ];
+
+    pub fn spec(&self) -> &'static SettingSpec {
+        match self {
+            CameraSetting::Brightness => &BRIGHTNESS,
+            CameraSetting::Contrast => &CONTRAST,
+            CameraSetting::Saturation => &SATURATION,
+            CameraSetting::Gamma => &GAMMA,
+            CameraSetting::Exposure => &EXPOSURE,
+            CameraSetting::Temperature => &TEMPERATURE,
+            CameraSetting::WhiteBalanceR => &WHITE_BALANCE_R,
+            CameraSetting::WhiteBalanceG => &WHITE_BALANCE_G,
+            CameraSetting::WhiteBalanceB => &WHITE_BALANCE_B,
+        }
+    }
+
+    pub fn label(&self) -> &'static str { self.spec().label }
+
+    /// The current value in the setting's own units.
+    pub fn get(&self, settings: &ImageSettings) -> f32 {
+        (self.spec().get)(settings)
+    }
+
+    /// Writes `value` into `settings`, as applying it to the camera would.
+    pub fn set(&self, settings: &mut ImageSettings, value: f32) {
+        let value = self.clamp(value);
+        match self {
+            CameraSetting::Brightness => settings.brightness = value.round() as i16,
+            CameraSetting::Contrast => settings.contrast = value,
+            CameraSetting::Saturation => settings.saturation = value,
+            CameraSetting::Gamma => settings.gamma = value,
+            CameraSetting::Exposure => settings.exposure = value,
+            CameraSetting::Temperature => settings.temperature = value,
+            CameraSetting::WhiteBalanceR => settings.white_balance_r = value,
+            CameraSetting::WhiteBalanceG => settings.white_balance_g = value,
+            CameraSetting::WhiteBalanceB => settings.white_balance_b = value,
+        }
+    }
+
+    pub fn apply(&self, camera: &mut Camera, value: f32) -> Result<(), CameraError> {
+        (self.spec().set)(camera, self.clamp(value))
+    }
+
+    /// Applies each value in turn, logging any the camera refuses and carrying on with the rest.
+    pub fn apply_all(camera: &mut Camera, values: impl IntoIterator<Item = (CameraSetting, f32)>) {
+        for (setting, value) in values {
+            if let Err(e) = setting.apply(camera, value) {
+                log::warn!("Could not set {} to {}: {:?}", setting.label(), setting.format(value), e);
+            }
+        }
+    }
+
+    /// The value with its unit, signed for settings that go either side of zero ("+0.7 EV").
+    pub fn format(&self, value: f32) -> String {
+        let spec = self.spec();
+        let decimals = (-spec.step.log10().floor()).max(0.0) as usize;
+        let text = match spec.range.0 < 0.0 {
+            true if value.abs() >= spec.step / 2.0 => format!("{:+.*}", decimals, value),
+            true => format!("{:.*}", decimals, 0.0),
+            false => format!("{:.*}", decimals, value),
+        };
+        format!("{}{}", text, spec.unit)
+    }
+
+    /// Reads a typed value, ignoring the unit and anything else that isn't part of the number.
+    pub fn parse(&self, text: &str) -> Option<f32> {
+        let number = text.chars().filter(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+')).collect::<String>();
+        number.parse::<f32>().ok().filter(|v| v.is_finite()).map(|v| self.clamp(v))
+    }
+
+    /// Snaps to a notable value within `SNAP` of slider travel, otherwise rounds to the step.
+    pub fn snap(&self, value: f32) -> f32 {
+        let position = self.slider_position(value);
+        let spec = self.spec();
+        match spec.notable.iter().find(|n| (self.slider_position(**n) - position).abs() <= SNAP) {
+            Some(notable) => *notable,
+            None => self.clamp((value / spec.step).round() * spec.step)
+        }
+    }
+
+    pub fn is_notable(&self, value: f32) -> bool {
+        self.spec().notable.contains(&value)
+    }
+
+    pub fn clamp(&self, value: f32) -> f32 {
+        let (lo, hi) = self.spec().range;
+        value.clamp(lo, hi)
+    }
+
+    /// Slider position from 0 to 100 for a value in the setting's units.
+    pub fn slider_position(&self, value: f32) -> f32 {
+        let (lo, hi) = self.spec().range;
+        let value = self.clamp(value);
+        let fraction = match self.spec().mapping {
+            SliderMapping::Linear => (value - lo) / (hi - lo),
+            SliderMapping::Logarithmic => (value / lo).ln() / (hi / lo).ln(),
+        };
+        fraction * 100.0
+    }
+
+    /// The value in the setting's units for a slider position from 0 to 100.
+    pub fn value_at(&self, percent: f32) -> f32 {
+        let (lo, hi) = self.spec().range;
+        let fraction = percent.clamp(0.0, 100.0) / 100.0;
+        match self.spec().mapping {
+            SliderMapping::Linear => lo + fraction * (hi - lo),
+            SliderMapping::Logarithmic => lo * (hi / lo).powf(fraction),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slider_round_trips_at_min_default_and_max() {
+        for setting in CameraSetting::ALL {
+            let spec = setting.spec();
+            for value in [spec.range.0, spec.default, spec.range.1] {
+                let back = setting.value_at(setting.slider_position(value));
+                assert!((back - value).abs() <= value.abs() * 1e-4 + 1e-4, "{:?}: {} came back as {}", setting, value, back);
+            }
+        }
+    }
+
+    #[test]
+    fn values_show_their_units_and_sign() {
+        assert_eq!(CameraSetting::Exposure.format(0.7), "+0.7 EV");
+        assert_eq!(CameraSetting::Exposure.format(-1.0), "-1.0 EV");
+        // Values that round to zero aren't signed.
+        assert_eq!(CameraSetting::Exposure.format(0.04), "0.0 EV");
+        assert_eq!(CameraSetting::Temperature.format(5600.0), "5600 K");
+        assert_eq!(CameraSetting::Brightness.format(-12.0), "-12");
+        assert_eq!(CameraSetting::WhiteBalanceR.format(1.25), "1.25×");
+        assert_eq!(CameraSetting::Gamma.format(2.2), "2.20");
+    }
+
+    #[test]
+    fn typed_values_ignore_units_and_are_clamped() {
+        assert_eq!(CameraSetting::Exposure.parse("+0.7 EV"), Some(0.7));
+        assert_eq!(CameraSetting::Temperature.parse("5600K"), Some(5600.0));
+        assert_eq!(CameraSetting::Temperature.parse("20000"), Some(10000.0));
+        assert_eq!(CameraSetting::Exposure.parse("-9"), Some(-2.0));
+        assert_eq!(CameraSetting::Exposure.parse("EV"), None);
+        assert_eq!(CameraSetting::Exposure.parse("1.2.3"), None);
+        // Everything shown reads back as the same value.
+        for setting in CameraSetting::ALL {
+            let spec = setting.spec();
+            for value in [spec.range.0, spec.default, spec.range.1] {
+                assert_eq!(setting.parse(&setting.format(value)), Some(value), "{:?}", setting);
+            }
+        }
+    }
+
+    #[test]
+    fn drags_snap_to_notable_values_and_steps() {
+        assert_eq!(CameraSetting::Exposure.snap(0.03), 0.0);
+        assert_eq!(CameraSetting::Temperature.snap(5650.0), 5600.0);
+        assert!((CameraSetting::Exposure.snap(0.76) - 0.8).abs() < 1e-6);
+        assert_eq!(CameraSetting::Temperature.snap(4020.0), 4000.0);
+        assert!(CameraSetting::Temperature.is_notable(3200.0));
+        assert!(!CameraSetting::Temperature.is_notable(3250.0));
+    }
+
+    #[test]
+    fn logarithmic_settings_spread_low_values() {
+        let gamma = CameraSetting::Gamma;
+        // The middle of the slider is the geometric mean of the range, not its midpoint.
+        assert!((gamma.value_at(50.0) - (0.1f32 * 3.0).sqrt()).abs() < 1e-4);
+        assert!(gamma.slider_position(1.0) > 50.0);
+        assert_eq!(gamma.slider_position(10.0), 100.0);
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::{DateTime, Utc};
+use image::{Delay, Frame, Rgba, RgbaImage};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::{self, FilterType};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+use crate::storage;
+
+// Frames are stored at this width; stop motion doesn't need full sensor resolution.
+const FRAME_WIDTH: u32 = 1280;
+const EXPORT_WIDTH: u32 = 480;
+pub const MIN_FPS: f32 = 1.0;
+pub const MAX_FPS: f32 = 24.0;
+pub const MAX_ONION: usize = 3;
+// Opacity of the newest onion layer; each older layer is half as strong.
+const ONION_OPACITY: f32 = 0.4;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Project {
+    pub id: Uuid,
+    pub name: String,
+    pub created: DateTime<Utc>,
+    pub fps: f32,
+    /// How many previous frames are overlaid on the viewfinder.
+    pub onion: usize,
+    /// Storage file names of the frames, in order, so saving the project doesn't rewrite them.
+    pub frames: Vec<String>,
+}
+
+impl Project {
+    pub fn new(name: String) -> Self {
+        Project { id: Uuid::new_v4(), name, created: Utc::now(), fps: 8.0, onion: 1, frames: Vec::new() }
+    }
+
+    pub fn add(&mut self, frame: &RgbaImage) {
+        let (w, h) = frame.dimensions();
+        let frame = match w > FRAME_WIDTH {
+            true => imageops::resize(frame, FRAME_WIDTH, ((h as f32 / w as f32) * FRAME_WIDTH as f32).round() as u32, FilterType::Triangle),
+            false => frame.clone()
+        };
+        let name = format!("frame-{}.png", Uuid::new_v4());
+        storage::write_image(&name, &frame);
+        self.frames.push(name);
+    }
+
+    /// Moves a frame one step earlier (`-1`) or later (`1`) in the sequence.
+    pub fn shift(&mut self, index: usize, by: isize) {
+        let target = index as isize + by;
+        if index < self.frames.len() && target >= 0 && (target as usize) < self.frames.len() {
+            self.frames.swap(index, target as usize);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.frames.len() {
+            storage::remove(&self.frames.remove(index));
+        }
+    }
+
+    pub fn frame(name: &str) -> RgbaImage {
+        storage::read_image(name).unwrap_or_default()
+    }
+
+    // Frames used to be kept inline as base64.
+    fn store_frames(&mut self) -> bool {
+        if self.frames.iter().all(|frame| frame.starts_with("frame-")) { return false; }
+        self.frames.iter_mut().filter(|frame| !frame.starts_with("frame-")).for_each(|frame| {
+            let name = format!("frame-{}.png", Uuid::new_v4());
+            storage::write_encoded(&name, frame);
+            *frame = name;
+        });
+        true
+    }
+
+    pub fn frame_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f32(1.0 / self.fps.clamp(MIN_FPS, MAX_FPS))
+    }
+
+    /// The frames to overlay on the viewfinder, newest first.
+    pub fn onion_frames(&self) -> Vec<RgbaImage> {
+        self.frames.iter().rev().take(self.onion).map(|f| Self::frame(f)).collect()
+    }
+
+    /// Writes the project to storage as a looping GIF and returns the file's name.
+    pub fn export_gif(&self) -> image::ImageResult<String> {
+        let name = format!("{}.gif", self.id);
+        let file = std::fs::File::create(storage::path(&name))?;
+        let mut encoder = GifEncoder::new_with_speed(file, 10);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let delay = Delay::from_saturating_duration(self.frame_duration());
+        for frame in &self.frames {
+            let frame = Self::frame(frame);
+            let (w, h) = frame.dimensions();
+            let height = ((h as f32 / w as f32) * EXPORT_WIDTH as f32).round().max(1.0) as u32;
+            let frame = imageops::resize(&frame, EXPORT_WIDTH.min(w), height.min(h), FilterType::Triangle);
+            encoder.encode_frame(Frame::from_parts(frame, 0, 0, delay))?;
+        }
+        Ok(name)
+    }
+}
+
+/// How far a GIF export has got. `LensService` encodes it and the stop motion page
+/// opens the file or shows what went wrong.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum GifExport {
+    #[default]
+    Idle,
+    Running,
+    /// The GIF's file name in storage.
+    Done(String),
+    Failed(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct StopMotion {
+    pub projects: Vec<Project>,
+    pub active: usize,
+}
+
+impl Default for StopMotion {
+    fn default() -> Self {
+        StopMotion { projects: vec![Project::new("Project 1".to_string())], active: 0 }
+    }
+}
+
+impl StopMotion {
+    const FILE: &'static str = "stop_motion.json";
+
+    pub fn load() -> Self {
+        let mut stop_motion: Self = storage::load(Self::FILE);
+        if stop_motion.projects.is_empty() {
+            stop_motion = StopMotion::default();
+        }
+        stop_motion.active = stop_motion.active.min(stop_motion.projects.len() - 1);
+        if stop_motion.projects.iter_mut().map(Project::store_frames).filter(|moved| *moved).count() > 0 {
+            stop_motion.save();
+        }
+        stop_motion
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn project(&mut self) -> &mut Project {
+        &mut self.projects[self.active]
+    }
+
+    pub fn active(&self) -> &Project {
+        &self.projects[self.active]
+    }
+
+    pub fn create(&mut self) {
+        let name = format!("Project {}", self.projects.len() + 1);
+        self.projects.push(Project::new(name));
+        self.active = self.projects.len() - 1;
+    }
+}
+
+/// Blends previous frames over the live frame so the next pose can be lined up.
+/// Layers are scaled to the live frame in place the first time they're used.
+pub fn onion_skin(live: &mut RgbaImage, layers: &mut [RgbaImage]) {
+    let (w, h) = live.dimensions();
+    // Oldest first so the most recent frame ends up on top.
+    for (i, layer) in layers.iter_mut().enumerate().rev() {
+        if layer.dimensions() != (w, h) {
+            *layer = imageops::resize(layer, w, h, FilterType::Triangle);
+        }
+        let alpha = ONION_OPACITY / (1 << i) as f32;
+        for (p, o) in live.pixels_mut().zip(layer.pixels()) {
+            *p = Rgba(std::array::from_fn(|c| match c {
+                3 => p.0[3],
+                _ => (p.0[c] as f32 * (1.0 - alpha) + o.0[c] as f32 * alpha).round() as u8
+            }));
+        }
+    }
+}
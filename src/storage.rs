@@ -0,0 +1,134 @@
+use std::fs::OpenOptions;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::{ImageFormat, RgbaImage};
+use pelican_ui::hardware::ApplicationSupport;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tempfile::NamedTempFile;
+
+use crate::scanner;
+
+pub fn path(name: &str) -> PathBuf {
+    let storage_path = ApplicationSupport::get().unwrap();
+    std::fs::create_dir_all(&storage_path).unwrap();
+    storage_path.join(name)
+}
+
+pub fn read(name: &str) -> Option<Vec<u8>> {
+    let path = path(name);
+    path.exists().then(|| std::fs::read(&path).expect("Could not read from storage"))
+}
+
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    read(name).and_then(|bytes| serde_json::from_slice(&bytes).ok()).unwrap_or_default()
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    let bytes = serde_json::to_vec_pretty(value).expect("Could not vec to pretty");
+    write(&path(name), &bytes);
+}
+
+/// Loads, changes and saves `name` while holding its lock, so the app and
+/// `orange background` writing the same file can't undo each other's changes.
+/// Returns what was saved.
+pub fn update<T: Serialize + DeserializeOwned + Default>(name: &str, change: impl FnOnce(&mut T)) -> T {
+    update_if(name, |value| {
+        change(value);
+        true
+    })
+}
+
+/// As `update`, but only saves when `change` returns whether it changed anything.
+pub fn update_if<T: Serialize + DeserializeOwned + Default>(name: &str, change: impl FnOnce(&mut T) -> bool) -> T {
+    let _lock = Lock::acquire(name);
+    let mut value = load(name);
+    if change(&mut value) {
+        save(name, &value);
+    }
+    value
+}
+
+pub fn modified(name: &str) -> Option<SystemTime> {
+    std::fs::metadata(path(name)).ok()?.modified().ok()
+}
+
+/// A lock file beside `name`, removed when dropped.
+struct Lock(PathBuf);
+
+impl Lock {
+    // A lock this old was left by a process that died holding it.
+    const STALE: Duration = Duration::from_secs(10);
+
+    fn acquire(name: &str) -> Self {
+        let path = path(&format!("{name}.lock"));
+        loop {
+            if OpenOptions::new().write(true).create_new(true).open(&path).is_ok() {
+                return Lock(path);
+            }
+            let age = std::fs::metadata(&path).and_then(|m| m.modified()).ok().and_then(|m| m.elapsed().ok());
+            match age.is_some_and(|age| age > Self::STALE) {
+                true => { let _ = std::fs::remove_file(&path); },
+                false => thread::sleep(Duration::from_millis(10))
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+// Written to a temp file and renamed so a crash mid-write never leaves a truncated file.
+pub fn write(path: &Path, bytes: &[u8]) {
+    let mut tmp = NamedTempFile::new_in(path.parent().unwrap_or_else(|| Path::new("."))).expect("Could not write temp");
+    tmp.write_all(bytes).expect("Could not write all");
+    tmp.flush().expect("Could not flush");
+    tmp.persist(path).expect("Colud not persist");
+}
+
+pub fn remove(name: &str) {
+    let _ = std::fs::remove_file(path(name));
+}
+
+/// Images kept beside the JSON files, as PNGs.
+pub fn write_image(name: &str, image: &RgbaImage) {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png).expect("Could not encode image");
+    write(&path(name), &bytes);
+}
+
+pub fn read_image(name: &str) -> Option<RgbaImage> {
+    image::load_from_memory(&read(name)?).ok().map(|image| image.to_rgba8())
+}
+
+/// Writes an image already encoded as base64, the way photos are held in memory, as its file.
+pub fn write_encoded(name: &str, encoded: &str) {
+    let bytes = BASE64.decode(encoded).expect("Could not decode image");
+    write(&path(name), &bytes);
+}
+
+/// Reads an image file back as base64.
+pub fn read_encoded(name: &str) -> Option<String> {
+    read(name).map(|bytes| BASE64.encode(bytes))
+}
+
+/// Writes an export and opens it in its default app, where it can be saved or sent
+/// on. Returns whether it opened.
+pub fn open_export(name: &str, bytes: &[u8]) -> bool {
+    write(&path(name), bytes);
+    open_file(name)
+}
+
+/// Opens a file already in storage in its default app. Only desktops have a launcher,
+/// and the platform layer can't hand files to other apps on mobile, so there it won't open.
+pub fn open_file(name: &str) -> bool {
+    scanner::launch(path(name))
+}
@@ -0,0 +1,358 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Datelike, Local};
+use pelican_ui::Context;
+use pelican_ui::layout::{Area, SizeRequest, Layout};
+use serde::{Serialize, Deserialize};
+
+use crate::info;
+use crate::service::Photo;
+use crate::storage;
+
+pub const THUMBNAIL: f32 = 64.0;
+pub const GAP: f32 = 8.0;
+pub const HEADER: f32 = 40.0;
+pub const SCRUBBER: f32 = 32.0;
+const KNOB: (f32, f32) = (6.0, 48.0);
+const BUBBLE: f32 = 180.0;
+// The scrubber only shows once the library is this many screens tall.
+const SCRUB_SCREENS: f32 = 3.0;
+// Size groups when sorting largest first, biggest first.
+const SIZES: [(usize, &str); 4] = [(10 << 20, "Over 10 MB"), (5 << 20, "5 to 10 MB"), (1 << 20, "1 to 5 MB"), (0, "Under 1 MB")];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RollSort {
+    #[default]
+    Newest,
+    Oldest,
+    /// By file size, grouped by size rather than date.
+    Largest,
+}
+
+impl RollSort {
+    pub const ALL: [RollSort; 3] = [RollSort::Newest, RollSort::Oldest, RollSort::Largest];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RollSort::Newest => "Newest",
+            RollSort::Oldest => "Oldest",
+            RollSort::Largest => "Largest",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RollGrouping {
+    #[default]
+    Day,
+    Month,
+    Year,
+}
+
+impl RollGrouping {
+    pub const ALL: [RollGrouping; 3] = [RollGrouping::Day, RollGrouping::Month, RollGrouping::Year];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RollGrouping::Day => "Days",
+            RollGrouping::Month => "Months",
+            RollGrouping::Year => "Years",
+        }
+    }
+
+    fn key(&self, date: DateTime<Local>) -> (i32, u32, u32) {
+        match self {
+            RollGrouping::Day => (date.year(), date.month(), date.day()),
+            RollGrouping::Month => (date.year(), date.month(), 0),
+            RollGrouping::Year => (date.year(), 0, 0),
+        }
+    }
+
+    fn title(&self, date: DateTime<Local>) -> String {
+        match self {
+            RollGrouping::Day => date.format("%A, %b %-d, %Y").to_string(),
+            RollGrouping::Month => date.format("%B %Y").to_string(),
+            RollGrouping::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub title: String,
+    pub photos: Vec<Photo>,
+}
+
+/// How the library is sorted and grouped, kept between launches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollView {
+    pub sort: RollSort,
+    pub grouping: RollGrouping,
+}
+
+impl RollView {
+    const FILE: &'static str = "roll_view.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn sections(&self, mut photos: Vec<Photo>) -> Vec<Section> {
+        match self.sort {
+            RollSort::Newest => photos.sort_by_key(|p| std::cmp::Reverse(p.date)),
+            RollSort::Oldest => photos.sort_by_key(|p| p.date),
+            RollSort::Largest => photos.sort_by_key(|p| std::cmp::Reverse(info::file_size(p.shown()))),
+        }
+        let mut sections: Vec<(_, Section)> = vec![];
+        for photo in photos {
+            let (key, title) = self.group(&photo);
+            match sections.last_mut() {
+                Some((k, section)) if *k == key => section.photos.push(photo),
+                _ => sections.push((key, Section { title, photos: vec![photo] }))
+            }
+        }
+        sections.into_iter().map(|(_, section)| section).collect()
+    }
+
+    // The section a photo falls in, as a key to tell sections apart and a title to show.
+    fn group(&self, photo: &Photo) -> ((i32, u32, u32), String) {
+        match self.sort {
+            RollSort::Largest => {
+                let size = info::file_size(photo.shown());
+                let i = SIZES.iter().position(|(min, _)| size >= *min).unwrap_or(SIZES.len() - 1);
+                ((i as i32, 0, 0), SIZES[i].1.to_string())
+            },
+            _ => {
+                let date = photo.date.with_timezone(&Local);
+                (self.grouping.key(date), self.grouping.title(date))
+            }
+        }
+    }
+}
+
+/// Where everything in the timeline sits for a given width, measured from the top of the timeline.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Geometry {
+    pub columns: usize,
+    /// Left edge of the grid, which is centered.
+    pub margin: f32,
+    /// Top of each section's header.
+    pub tops: Vec<f32>,
+    pub height: f32,
+}
+
+impl Geometry {
+    pub fn new(width: f32, counts: &[usize]) -> Self {
+        let columns = (((width + GAP) / (THUMBNAIL + GAP)).floor() as usize).max(1);
+        let margin = ((width - columns as f32 * (THUMBNAIL + GAP) + GAP) / 2.0).max(0.0);
+        let mut tops = Vec::with_capacity(counts.len());
+        let mut height = 0.0;
+        for count in counts {
+            tops.push(height);
+            height += HEADER + count.div_ceil(columns) as f32 * (THUMBNAIL + GAP);
+        }
+        Geometry { columns, margin, tops, height }
+    }
+
+    /// The `i`th photo of section `s`.
+    pub fn thumbnail(&self, s: usize, i: usize) -> (f32, f32) {
+        let (row, column) = (i / self.columns, i % self.columns);
+        (self.margin + column as f32 * (THUMBNAIL + GAP), self.tops[s] + HEADER + row as f32 * (THUMBNAIL + GAP))
+    }
+
+    /// The section showing at the top of the view.
+    pub fn section_at(&self, scroll: f32) -> usize {
+        self.tops.iter().rposition(|top| *top <= scroll).unwrap_or(0)
+    }
+
+    /// Sections with any part within a screen of the view, so their thumbnails are ready before they scroll in.
+    pub fn visible(&self, scroll: f32, view: f32) -> std::ops::Range<usize> {
+        if self.tops.is_empty() { return 0..0; }
+        self.section_at((scroll - view).max(0.0))..self.section_at(scroll + view * 2.0) + 1
+    }
+
+    /// The pinned header's section and offset, pushed up by the next header as it arrives.
+    pub fn sticky(&self, scroll: f32) -> (usize, f32) {
+        let section = self.section_at(scroll);
+        let pushed = self.tops.get(section + 1).map(|next| (next - scroll - HEADER).min(0.0)).unwrap_or(0.0);
+        (section, pushed)
+    }
+
+    pub fn max_scroll(&self, view: f32) -> f32 {
+        (self.height - view).max(0.0)
+    }
+
+    pub fn scrubbable(&self, view: f32) -> bool {
+        view > 0.0 && self.height > view * SCRUB_SCREENS
+    }
+
+    /// The scroll for a point on the scrubber, so the knob lands under the finger.
+    pub fn scrub(&self, y: f32, view: f32) -> f32 {
+        let travel = (view - KNOB.1).max(1.0);
+        ((y - KNOB.1 / 2.0) / travel).clamp(0.0, 1.0) * self.max_scroll(view)
+    }
+
+    /// Top of the knob for a scroll.
+    pub fn knob(&self, scroll: f32, view: f32) -> f32 {
+        let max = self.max_scroll(view);
+        if max <= 0.0 { return 0.0; }
+        (scroll / max).clamp(0.0, 1.0) * (view - KNOB.1).max(0.0)
+    }
+}
+
+/// Shared between the timeline and its layout, which measures it each frame.
+#[derive(Debug, Default)]
+pub struct Timeline {
+    /// Photos in each section, in order.
+    pub counts: Vec<usize>,
+    pub geometry: Geometry,
+    pub size: (f32, f32),
+    pub scroll: f32,
+    pub scrubbing: bool,
+}
+
+/// Lays out a timeline's children: its thumbnails, then a header per section, then the
+/// pinned header, the scrubber track, its knob and the label shown while scrubbing, and
+/// last anything shown when there are no photos.
+#[derive(Debug, Default, Clone)]
+pub struct TimelineLayout(pub Arc<Mutex<Timeline>>);
+
+impl Layout for TimelineLayout {
+    fn request_size(&self, _ctx: &mut Context, _children: Vec<SizeRequest>) -> SizeRequest {
+        SizeRequest::new(0.0, 0.0, f32::MAX, f32::MAX)
+    }
+
+    fn build(&self, _ctx: &mut Context, size: (f32, f32), children: Vec<SizeRequest>) -> Vec<Area> {
+        let mut timeline = self.0.lock().unwrap();
+        timeline.geometry = Geometry::new(size.0, &timeline.counts);
+        timeline.size = size;
+        timeline.scroll = timeline.scroll.clamp(0.0, timeline.geometry.max_scroll(size.1));
+        let (geometry, scroll) = (&timeline.geometry, timeline.scroll);
+        let hidden = Area { offset: (0.0, 0.0), size: (0.0, 0.0) };
+
+        let mut areas = timeline.counts.iter().enumerate().flat_map(|(s, count)| (0..*count).map(move |i| {
+            let (x, y) = geometry.thumbnail(s, i);
+            Area { offset: (x, y - scroll), size: (THUMBNAIL, THUMBNAIL) }
+        })).collect::<Vec<_>>();
+        areas.extend(geometry.tops.iter().map(|top| Area { offset: (0.0, top - scroll), size: (size.0, HEADER) }));
+        areas.push(match geometry.tops.is_empty() {
+            true => hidden,
+            false => Area { offset: (0.0, geometry.sticky(scroll).1), size: (size.0, HEADER) }
+        });
+        match geometry.scrubbable(size.1) {
+            true => {
+                let knob = geometry.knob(scroll, size.1);
+                areas.push(Area { offset: (size.0 - SCRUBBER, 0.0), size: (SCRUBBER, size.1) });
+                areas.push(Area { offset: (size.0 - (SCRUBBER + KNOB.0) / 2.0, knob), size: KNOB });
+                areas.push(match timeline.scrubbing {
+                    true => Area { offset: (size.0 - SCRUBBER - BUBBLE, knob + (KNOB.1 - HEADER) / 2.0), size: (BUBBLE, HEADER) },
+                    false => hidden
+                });
+            },
+            false => areas.extend([hidden, hidden, hidden])
+        }
+        areas.extend(children[areas.len().min(children.len())..].iter().map(|child| {
+            let child = child.get(size);
+            Area { offset: ((size.0 - child.0) / 2.0, (size.1 - child.1) / 2.0), size: child }
+        }));
+        areas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn taken(year: i32, month: u32, day: u32, hour: u32) -> Photo {
+        let date = Local.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap().with_timezone(&Utc);
+        Photo { date, ..Photo::new(String::new(), (1.0, 1.0)) }
+    }
+
+    fn sized(bytes: usize) -> Photo {
+        Photo::new("A".repeat(bytes / 3 * 4), (1.0, 1.0))
+    }
+
+    fn counts(sections: &[Section]) -> Vec<usize> {
+        sections.iter().map(|s| s.photos.len()).collect()
+    }
+
+    #[test]
+    fn sections_group_by_day_month_and_year() {
+        let photos = vec![taken(2023, 12, 31, 9), taken(2024, 3, 5, 9), taken(2024, 3, 5, 18), taken(2024, 3, 9, 12)];
+        let view = |sort, grouping| RollView { sort, grouping }.sections(photos.clone());
+
+        let days = view(RollSort::Newest, RollGrouping::Day);
+        assert_eq!(counts(&days), [1, 2, 1]);
+        assert_eq!(days[0].title, "Saturday, Mar 9, 2024");
+        assert_eq!(days[1].photos[0].date, photos[2].date);
+        assert_eq!(counts(&view(RollSort::Newest, RollGrouping::Month)), [3, 1]);
+        let years = view(RollSort::Oldest, RollGrouping::Year);
+        assert_eq!(counts(&years), [1, 3]);
+        assert_eq!(years.iter().map(|s| s.title.as_str()).collect::<Vec<_>>(), ["2023", "2024"]);
+        assert_eq!(view(RollSort::Oldest, RollGrouping::Month)[1].title, "March 2024");
+    }
+
+    #[test]
+    fn largest_first_groups_by_size() {
+        let photos = vec![sized(2 << 20), sized(100), sized(12 << 20), sized(3 << 20)];
+        let sections = RollView { sort: RollSort::Largest, grouping: RollGrouping::Day }.sections(photos.clone());
+        assert_eq!(sections.iter().map(|s| s.title.as_str()).collect::<Vec<_>>(), ["Over 10 MB", "1 to 5 MB", "Under 1 MB"]);
+        assert_eq!(sections[1].photos.iter().map(|p| p.id).collect::<Vec<_>>(), [photos[3].id, photos[0].id]);
+        assert!(RollView::default().sections(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn geometry_places_sections_and_thumbnails() {
+        let row = THUMBNAIL + GAP;
+        // Three columns fit, with what's left split either side.
+        let geometry = Geometry::new(3.0 * row + 10.0, &[4, 2]);
+        assert_eq!(geometry.columns, 3);
+        assert_eq!(geometry.margin, (10.0 + GAP) / 2.0);
+        assert_eq!(geometry.tops, [0.0, HEADER + 2.0 * row]);
+        assert_eq!(geometry.height, 2.0 * HEADER + 3.0 * row);
+        assert_eq!(geometry.thumbnail(0, 3), (geometry.margin, HEADER + row));
+        assert_eq!(Geometry::new(10.0, &[1]).columns, 1);
+
+        assert_eq!(geometry.section_at(0.0), 0);
+        assert_eq!(geometry.section_at(geometry.tops[1] - 1.0), 0);
+        assert_eq!(geometry.section_at(geometry.tops[1]), 1);
+        assert_eq!(geometry.visible(0.0, 10.0), 0..1);
+        assert_eq!(geometry.visible(0.0, 100.0), 0..2);
+        assert_eq!(Geometry::new(100.0, &[]).visible(0.0, 100.0), 0..0);
+    }
+
+    #[test]
+    fn sticky_header_is_pushed_up_by_the_next() {
+        let geometry = Geometry::new(THUMBNAIL, &[1, 1, 1]);
+        let next = geometry.tops[1];
+        assert_eq!(geometry.sticky(0.0), (0, 0.0));
+        assert_eq!(geometry.sticky(next - HEADER / 2.0), (0, -HEADER / 2.0));
+        assert_eq!(geometry.sticky(next), (1, 0.0));
+        assert_eq!(geometry.sticky(geometry.height), (2, 0.0));
+    }
+
+    #[test]
+    fn scrubber_maps_between_scroll_and_knob() {
+        let geometry = Geometry::new(THUMBNAIL, &[100]);
+        let view = 500.0;
+        assert!(geometry.scrubbable(view));
+        assert!(!Geometry::new(THUMBNAIL, &[2]).scrubbable(view));
+        let max = geometry.max_scroll(view);
+        assert_eq!(geometry.scrub(0.0, view), 0.0);
+        assert_eq!(geometry.scrub(view, view), max);
+        assert_eq!(geometry.knob(max, view), view - KNOB.1);
+        let middle = geometry.scrub(view / 2.0, view);
+        assert!((geometry.knob(middle, view) + KNOB.1 / 2.0 - view / 2.0).abs() < 1e-3);
+
+        // A view with no height has nowhere to scrub and nothing to scroll.
+        assert!(!geometry.scrubbable(0.0));
+        assert_eq!(geometry.knob(100.0, 0.0), 0.0);
+        assert!(geometry.scrub(10.0, 0.0).is_finite());
+        assert_eq!(Geometry::default().knob(0.0, view), 0.0);
+    }
+}
@@ -0,0 +1,409 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use image::{Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
+use pelican_ui_std::EncodedImage;
+use uuid::Uuid;
+
+use crate::edit::Edits;
+use crate::markup::Annotations;
+use crate::service::Photo;
+use crate::worker::Worker;
+
+// Zoom is relative to the whole photo fitting the view. The most it goes is this many
+// times past one photo pixel per rendered pixel, and never less than `MIN_MAX_ZOOM`.
+const BEYOND_ACTUAL: f32 = 4.0;
+const MIN_MAX_ZOOM: f32 = 4.0;
+// A double tap zooms to actual pixels, or at least this far on a photo smaller than the view.
+const DOUBLE_TAP_ZOOM: f32 = 2.0;
+const KEY_ZOOM: f32 = 1.5;
+// A wheel notch is reported as a scroll of 0.2, so this is about 20% a notch.
+const WHEEL_ZOOM: f32 = 1.0;
+const TAP_TIME: Duration = Duration::from_millis(250);
+const DOUBLE_TAP_TIME: Duration = Duration::from_millis(300);
+// Distances are fractions of the view.
+const TAP_SLOP: f32 = 0.02;
+const SWIPE_DISTANCE: f32 = 0.25;
+// A quick flick turns the page without the full distance, in views per second.
+const SWIPE_SPEED: f32 = 1.2;
+// Dragging past the first or last photo only moves this share of the finger's travel.
+const EDGE_RESISTANCE: f32 = 0.3;
+// Per sixtieth of a second, how much of the fling's speed and the swipe's offset is kept.
+const FRICTION: f32 = 0.92;
+const SPRING: f32 = 0.75;
+const MIN_SPEED: f32 = 0.02;
+const SETTLED: f32 = 0.002;
+// A finger resting this long before it lifts doesn't fling.
+const FLING_WINDOW: Duration = Duration::from_millis(80);
+// The UI keeps sending scrolls for a moment after a touch drag ends, which aren't the wheel.
+const FLING_GUARD: Duration = Duration::from_millis(1500);
+const MAX_TICK: f32 = 0.1;
+// Rendered at twice the view's size in points, and no wider than this.
+pub const RENDER_SCALE: f32 = 2.0;
+const MAX_RENDER: f32 = 2048.0;
+// Decoded photos kept around: the one showing and the ones either side.
+const CACHE_SIZE: usize = 3;
+const BACKDROP: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn midpoint(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+}
+
+/// How far into a photo the view is zoomed and where it's looking. Positions are fractions of the photo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    /// 1 shows the whole photo.
+    pub zoom: f32,
+    pub center: (f32, f32),
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport { zoom: 1.0, center: (0.5, 0.5) }
+    }
+}
+
+impl Viewport {
+    pub fn is_zoomed(&self) -> bool {
+        self.zoom > 1.0 + f32::EPSILON
+    }
+
+    /// x, y, width and height of the part in view.
+    pub fn visible(&self) -> (f32, f32, f32, f32) {
+        let size = 1.0 / self.zoom;
+        (self.center.0 - size / 2.0, self.center.1 - size / 2.0, size, size)
+    }
+
+    /// Zooms by `factor`, keeping whatever is under `anchor`, a point in the view, where it is.
+    pub fn zoom_at(&mut self, factor: f32, anchor: (f32, f32), max: f32) {
+        let (x, y, w, h) = self.visible();
+        let point = (x + anchor.0 * w, y + anchor.1 * h);
+        self.zoom = (self.zoom * factor).clamp(1.0, max.max(1.0));
+        let size = 1.0 / self.zoom;
+        self.center = (point.0 + (0.5 - anchor.0) * size, point.1 + (0.5 - anchor.1) * size);
+        self.clamp();
+    }
+
+    /// Follows a drag of `delta`, as a fraction of the view.
+    pub fn pan(&mut self, delta: (f32, f32)) {
+        self.center = (self.center.0 - delta.0 / self.zoom, self.center.1 - delta.1 / self.zoom);
+        self.clamp();
+    }
+
+    // Keeps the view inside the photo.
+    fn clamp(&mut self) {
+        let half = 0.5 / self.zoom;
+        self.center = (self.center.0.clamp(half, 1.0 - half), self.center.1.clamp(half, 1.0 - half));
+    }
+}
+
+/// Turns presses and drags on the photo viewer into zooming, panning and swiping.
+/// Points are fractions of the view, which always has the photo's shape.
+#[derive(Debug)]
+pub struct Viewer {
+    pub viewport: Viewport,
+    /// The zoom showing one photo pixel per rendered pixel.
+    pub actual: f32,
+    /// Whether there's a photo before and after this one.
+    pub edges: (bool, bool),
+    /// How far the photo has been dragged sideways toward a neighbour. Negative is toward the next one.
+    pub swipe: f32,
+    /// Fingers down, up to two. Touches come without ids, so each move goes to the nearer finger.
+    touches: Vec<(f32, f32)>,
+    pressed: Option<(Instant, (f32, f32))>,
+    moved: Option<Instant>,
+    /// Views per second.
+    velocity: (f32, f32),
+    last_tap: Option<(Instant, (f32, f32))>,
+    /// Set when one finger of a pinch lifts, so the other carries on as a drag.
+    resume: bool,
+    /// Whether the drag in progress came from a touch rather than the mouse.
+    touch: bool,
+    touch_ended: Option<Instant>,
+    ticked: Option<Instant>,
+}
+
+impl Viewer {
+    pub fn new(actual: f32, edges: (bool, bool)) -> Self {
+        Viewer {
+            viewport: Viewport::default(), actual, edges, swipe: 0.0, touches: Vec::new(), pressed: None, moved: None,
+            velocity: (0.0, 0.0), last_tap: None, resume: false, touch: false, touch_ended: None, ticked: None,
+        }
+    }
+
+    pub fn max_zoom(&self) -> f32 {
+        (self.actual * BEYOND_ACTUAL).max(MIN_MAX_ZOOM)
+    }
+
+    /// Whether the photo is shown whole and still, so the plain image will do.
+    pub fn is_resting(&self) -> bool {
+        !self.viewport.is_zoomed() && self.swipe == 0.0
+    }
+
+    /// Whether a finger or the mouse is down on the photo.
+    pub fn is_pressed(&self) -> bool {
+        !self.touches.is_empty() || self.resume
+    }
+
+    pub fn is_pinching(&self) -> bool {
+        self.touches.len() == 2
+    }
+
+    pub fn press(&mut self, point: (f32, f32), now: Instant) {
+        self.velocity = (0.0, 0.0);
+        match self.touches.len() {
+            0 => {
+                self.touches.push(point);
+                self.pressed = Some((now, point));
+                self.moved = Some(now);
+                self.touch = false;
+            },
+            1 => {
+                self.touches.push(point);
+                self.swipe = 0.0;
+            },
+            _ => {}
+        }
+    }
+
+    /// Follows a finger or the pressed mouse to `point`. Returns whether the view changed.
+    pub fn drag(&mut self, point: (f32, f32), touch: bool, now: Instant) -> bool {
+        if self.touches.is_empty() {
+            if self.resume && touch {
+                self.touches.push(point);
+                self.moved = Some(now);
+            }
+            return false;
+        }
+        self.touch |= touch;
+        if let [a, b] = self.touches[..] {
+            let nearer = if distance(a, point) <= distance(b, point) {0} else {1};
+            let (before, mid) = (distance(a, b), midpoint(a, b));
+            self.touches[nearer] = point;
+            let (a, b) = (self.touches[0], self.touches[1]);
+            if before > 0.0 {
+                let max = self.max_zoom();
+                self.viewport.zoom_at(distance(a, b) / before, mid, max);
+            }
+            let after = midpoint(a, b);
+            self.viewport.pan((after.0 - mid.0, after.1 - mid.1));
+            return true;
+        }
+        let last = std::mem::replace(&mut self.touches[0], point);
+        let delta = (point.0 - last.0, point.1 - last.1);
+        let elapsed = self.moved.replace(now).map_or(0.0, |t| now.duration_since(t).as_secs_f32()).max(0.001);
+        // Smoothed, since moves can arrive very close together.
+        self.velocity = ((self.velocity.0 + delta.0 / elapsed) / 2.0, (self.velocity.1 + delta.1 / elapsed) / 2.0);
+        match self.viewport.is_zoomed() {
+            true => self.viewport.pan(delta),
+            false => {
+                let swipe = self.swipe + delta.0;
+                let open = if swipe > 0.0 {self.edges.0} else {self.edges.1};
+                self.swipe += delta.0 * if open {1.0} else {EDGE_RESISTANCE};
+            }
+        }
+        delta != (0.0, 0.0)
+    }
+
+    /// Ends a press. Returns how many photos along to move, if the swipe went far or fast enough.
+    pub fn release(&mut self, now: Instant) -> Option<isize> {
+        if self.touch { self.touch_ended = Some(now); }
+        if self.touches.len() == 2 {
+            self.touches.clear();
+            self.resume = true;
+            return None;
+        }
+        self.resume = false;
+        let point = self.touches.pop()?;
+        if self.moved.is_some_and(|t| now.duration_since(t) > FLING_WINDOW) {
+            self.velocity = (0.0, 0.0);
+        }
+        if let Some((pressed, start)) = self.pressed.take() {
+            if now.duration_since(pressed) < TAP_TIME && distance(start, point) < TAP_SLOP {
+                self.tap(point, now);
+                return None;
+            }
+        }
+        if self.viewport.is_zoomed() { return None; }
+        let (swipe, speed) = (self.swipe, self.velocity.0);
+        self.velocity = (0.0, 0.0);
+        match (swipe < -SWIPE_DISTANCE || (swipe < 0.0 && speed < -SWIPE_SPEED), swipe > SWIPE_DISTANCE || (swipe > 0.0 && speed > SWIPE_SPEED)) {
+            (true, _) if self.edges.1 => Some(1),
+            (_, true) if self.edges.0 => Some(-1),
+            _ => None
+        }
+    }
+
+    // A second tap close enough to the first toggles between the whole photo and actual pixels.
+    fn tap(&mut self, point: (f32, f32), now: Instant) {
+        let double = self.last_tap.take().is_some_and(|(t, p)| now.duration_since(t) < DOUBLE_TAP_TIME && distance(p, point) < TAP_SLOP * 4.0);
+        if !double {
+            self.last_tap = Some((now, point));
+            return;
+        }
+        match self.viewport.is_zoomed() {
+            true => self.viewport = Viewport::default(),
+            false => {
+                let max = self.max_zoom();
+                self.viewport.zoom_at(self.actual.max(DOUBLE_TAP_ZOOM), point, max);
+            }
+        }
+    }
+
+    /// Zooms around `anchor` for a scroll of `amount`. Returns whether the view changed.
+    pub fn wheel(&mut self, amount: f32, anchor: (f32, f32), now: Instant) -> bool {
+        if !self.touches.is_empty() || self.touch_ended.is_some_and(|t| now.duration_since(t) < FLING_GUARD) {
+            return false;
+        }
+        let before = self.viewport;
+        let max = self.max_zoom();
+        self.viewport.zoom_at((-amount * WHEEL_ZOOM).exp().clamp(0.5, 2.0), anchor, max);
+        self.viewport != before
+    }
+
+    /// Zooms in or out a step around the middle, or back to the whole photo with 0.
+    pub fn key_zoom(&mut self, direction: i32) {
+        let max = self.max_zoom();
+        match direction {
+            0 => self.viewport = Viewport::default(),
+            d => self.viewport.zoom_at(KEY_ZOOM.powi(d.signum()), (0.5, 0.5), max),
+        }
+    }
+
+    /// Carries a fling on and eases a swipe that didn't turn the page back. Returns whether the view changed.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let elapsed = self.ticked.replace(now).map_or(0.0, |t| now.duration_since(t).as_secs_f32()).min(MAX_TICK);
+        if !self.touches.is_empty() || elapsed == 0.0 { return false; }
+        let frames = elapsed * 60.0;
+        let mut changed = false;
+        if self.velocity != (0.0, 0.0) {
+            let before = self.viewport;
+            self.viewport.pan((self.velocity.0 * elapsed, self.velocity.1 * elapsed));
+            let keep = FRICTION.powf(frames);
+            self.velocity = (self.velocity.0 * keep, self.velocity.1 * keep);
+            if self.velocity.0.hypot(self.velocity.1) < MIN_SPEED || self.viewport == before {
+                self.velocity = (0.0, 0.0);
+            }
+            changed = true;
+        }
+        if self.swipe != 0.0 {
+            self.swipe *= SPRING.powf(frames);
+            if self.swipe.abs() < SETTLED { self.swipe = 0.0; }
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// The size to render a view `shown` points across at.
+pub fn render_size((w, h): (f32, f32)) -> (u32, u32) {
+    let scale = RENDER_SCALE.min(MAX_RENDER / w.max(h).max(1.0));
+    (((w * scale).round() as u32).max(1), ((h * scale).round() as u32).max(1))
+}
+
+/// The part of `image` in view, at `size`. Past actual pixels it's enlarged without smoothing.
+pub fn render_view(image: &RgbaImage, viewport: &Viewport, size: (u32, u32)) -> RgbaImage {
+    let (x, y, w, h) = viewport.visible();
+    let (iw, ih) = (image.width() as f32, image.height() as f32);
+    let left = ((x * iw) as u32).min(image.width() - 1);
+    let top = ((y * ih) as u32).min(image.height() - 1);
+    let width = ((w * iw).round() as u32).clamp(1, image.width() - left);
+    let height = ((h * ih).round() as u32).clamp(1, image.height() - top);
+    let filter = if width < size.0 {FilterType::Nearest} else {FilterType::Triangle};
+    imageops::resize(&imageops::crop_imm(image, left, top, width, height).to_image(), size.0, size.1, filter)
+}
+
+/// `image` scaled to fit `size`, centered on black.
+pub fn slide(image: &RgbaImage, size: (u32, u32)) -> RgbaImage {
+    let scale = (size.0 as f32 / image.width() as f32).min(size.1 as f32 / image.height() as f32);
+    let (w, h) = (((image.width() as f32 * scale) as u32).max(1), ((image.height() as f32 * scale) as u32).max(1));
+    let mut canvas = RgbaImage::from_pixel(size.0, size.1, BACKDROP);
+    let fitted = imageops::resize(image, w, h, FilterType::Triangle);
+    imageops::replace(&mut canvas, &fitted, ((size.0 - w) / 2) as i64, ((size.1 - h) / 2) as i64);
+    canvas
+}
+
+/// The photo `swipe` of the way across, with the neighbour it's moving toward coming in beside it.
+/// The slides are all the same size.
+pub fn render_swipe(current: &RgbaImage, previous: Option<&RgbaImage>, next: Option<&RgbaImage>, swipe: f32) -> RgbaImage {
+    let (w, h) = current.dimensions();
+    let offset = (swipe * w as f32).round() as i64;
+    let mut canvas = RgbaImage::from_pixel(w, h, BACKDROP);
+    imageops::replace(&mut canvas, current, offset, 0);
+    match offset > 0 {
+        true => if let Some(previous) = previous { imageops::replace(&mut canvas, previous, offset - w as i64, 0) },
+        false => if let Some(next) = next { imageops::replace(&mut canvas, next, offset + w as i64, 0) },
+    }
+    canvas
+}
+
+/// Photos already decoded for the viewer, so stepping back and forth doesn't decode them again.
+/// Least recently used first, which is the first to go.
+#[derive(Clone, Debug, Default)]
+pub struct DecodedPhotos(Vec<(Uuid, Edits, Annotations, Arc<RgbaImage>)>);
+
+impl DecodedPhotos {
+    /// The decoded photo, counted as just used.
+    pub fn get(&mut self, photo: &Photo) -> Option<Arc<RgbaImage>> {
+        let i = self.0.iter().position(|(id, edits, annotations, _)| *id == photo.id && *edits == photo.edits && *annotations == photo.annotations)?;
+        let entry = self.0.remove(i);
+        let image = entry.3.clone();
+        self.0.push(entry);
+        Some(image)
+    }
+
+    pub fn insert(&mut self, photo: &Photo, image: Arc<RgbaImage>) {
+        self.0.retain(|(id, ..)| *id != photo.id);
+        self.0.push((photo.id, photo.edits.clone(), photo.annotations.clone(), image));
+        if self.0.len() > CACHE_SIZE { self.0.remove(0); }
+    }
+}
+
+/// Decodes photos on a worker thread, so the ones either side are ready before they're swiped to.
+pub type Preloader = Worker<(Uuid, String), (Uuid, RgbaImage)>;
+
+pub fn preloader() -> Preloader {
+    Worker::new(|(id, encoded): (Uuid, String)| (id, EncodedImage::decode_rgba(&encoded)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit::EditOp;
+
+    fn photo() -> Photo {
+        Photo::new(String::new(), (1.0, 1.0))
+    }
+
+    #[test]
+    fn least_recently_used_photos_go_first() {
+        let photos = (0..=CACHE_SIZE).map(|_| photo()).collect::<Vec<_>>();
+        let mut decoded = DecodedPhotos::default();
+        let image = Arc::new(RgbaImage::new(1, 1));
+        photos[..CACHE_SIZE].iter().for_each(|p| decoded.insert(p, image.clone()));
+
+        // Using the oldest makes the second oldest the next to go.
+        assert!(decoded.get(&photos[0]).is_some());
+        decoded.insert(&photos[CACHE_SIZE], image.clone());
+        assert!(decoded.get(&photos[1]).is_none());
+        assert!(decoded.get(&photos[0]).is_some());
+        assert!(decoded.get(&photos[CACHE_SIZE]).is_some());
+    }
+
+    #[test]
+    fn edited_photos_are_decoded_again() {
+        let mut photo = photo();
+        let mut decoded = DecodedPhotos::default();
+        decoded.insert(&photo, Arc::new(RgbaImage::new(1, 1)));
+        photo.edits.push(EditOp::Rotate(1));
+        assert!(decoded.get(&photo).is_none());
+        // Inserting the new version replaces the old one rather than keeping both.
+        decoded.insert(&photo, Arc::new(RgbaImage::new(2, 2)));
+        assert_eq!(decoded.0.len(), 1);
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+/// Runs a job on a background thread and hands its results back to the UI thread.
+pub struct Worker<I, O> {
+    requests: Sender<I>,
+    results: Receiver<(usize, O)>,
+    pending: usize,
+}
+
+impl<I: Send + 'static, O: Send + 'static> Worker<I, O> {
+    /// Runs every request, in order.
+    pub fn new(mut job: impl FnMut(I) -> O + Send + 'static) -> Self {
+        Self::spawn(move |requests: &Receiver<I>| {
+            let request = requests.recv().ok()?;
+            Some((1, job(request)))
+        })
+    }
+
+    /// Runs only the newest request, dropping any still queued behind it.
+    pub fn latest(mut job: impl FnMut(I) -> O + Send + 'static) -> Self {
+        Self::spawn(move |requests: &Receiver<I>| {
+            let mut request = requests.recv().ok()?;
+            let mut count = 1;
+            for newer in requests.try_iter() {
+                request = newer;
+                count += 1;
+            }
+            Some((count, job(request)))
+        })
+    }
+
+    fn spawn(mut next: impl FnMut(&Receiver<I>) -> Option<(usize, O)> + Send + 'static) -> Self {
+        let (requests, request_rx) = mpsc::channel::<I>();
+        let (result_tx, results) = mpsc::channel();
+        thread::spawn(move || {
+            while let Some(result) = next(&request_rx) {
+                if result_tx.send(result).is_err() { break; }
+            }
+        });
+        Worker { requests, results, pending: 0 }
+    }
+
+    pub fn submit(&mut self, request: I) {
+        if self.requests.send(request).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    /// Submits only while nothing is in flight, for per-frame work that would otherwise
+    /// fall behind. `request` isn't made at all when it would be dropped.
+    pub fn offer(&mut self, request: impl FnOnce() -> I) {
+        if !self.is_busy() {
+            self.submit(request());
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Every result that's ready, oldest first.
+    pub fn poll(&mut self) -> Vec<O> {
+        let mut ready = Vec::new();
+        loop {
+            match self.results.try_recv() {
+                Ok((count, result)) => {
+                    self.pending = self.pending.saturating_sub(count);
+                    ready.push(result);
+                },
+                Err(TryRecvError::Empty) => break,
+                // The job panicked, so nothing more is coming.
+                Err(TryRecvError::Disconnected) => {
+                    self.pending = 0;
+                    break;
+                }
+            }
+        }
+        ready
+    }
+}
+
+impl<I, O> fmt::Debug for Worker<I, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Worker").field("pending", &self.pending).finish()
+    }
+}
+
+/// Polls until a worker answers. Results arrive from another thread, so they get a moment.
+#[cfg(test)]
+pub fn wait<T>(mut poll: impl FnMut() -> Option<T>) -> T {
+    let started = Instant::now();
+    loop {
+        if let Some(result) = poll() { return result; }
+        assert!(started.elapsed() < Duration::from_secs(10), "worker never answered");
+        thread::sleep(Duration::from_millis(5));
+    }
+}